@@ -0,0 +1,43 @@
+//! Compares the owned and borrowed parsers on a large synthetic `scutil --dns` dump
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use scutil_parser::borrowed::parse_text_borrowed;
+use scutil_parser::dns::parse_text;
+
+/// A config with a few unscoped resolvers and `scoped` scoped ones
+fn synthetic_input(scoped: usize) -> String {
+    let mut input = String::from("DNS configuration\n\n");
+    input.push_str("resolver #1\n  search domain[0] : corp.example.com\n  search domain[1] : example.com\n  nameserver[0] : 192.168.1.1\n  nameserver[1] : fe80::1%en0\n  if_index : 15 (en0)\n  flags    : Request A records, Request AAAA records\n  reach    : 0x00020002 (Reachable,Directly Reachable Address)\n  order    : 200000\n\n");
+    input.push_str("resolver #2\n  domain   : local\n  options  : mdns\n  timeout  : 5\n  flags    : Request A records, Request AAAA records\n  reach    : 0x00000000 (Not Reachable)\n  order    : 300000\n\n");
+    input.push_str("\nDNS configuration (for scoped queries)\n\n");
+    for id in 1..=scoped {
+        input.push_str(&format!(
+            "resolver #{id}\n  search domain[0] : scope{id}.example.com\n  nameserver[0] : 10.{a}.{b}.1\n  nameserver[1] : 10.{a}.{b}.2\n  if_index : {id} (utun{id})\n  flags    : Scoped, Request A records\n  reach    : 0x00000003 (Reachable,Transient Connection)\n  order    : {id}\n\n",
+            a = id / 256,
+            b = id % 256,
+        ));
+    }
+    input
+}
+
+fn bench_parse(c: &mut Criterion) {
+    let input = synthetic_input(200);
+    // make sure both are parsing the same thing before timing them
+    assert_eq!(
+        parse_text_borrowed(&input).unwrap().to_owned(),
+        parse_text(&input).unwrap()
+    );
+
+    let mut group = c.benchmark_group("parse 200 scoped resolvers");
+    group.bench_function("owned", |b| {
+        b.iter(|| parse_text(black_box(&input)).unwrap())
+    });
+    group.bench_function("borrowed", |b| {
+        b.iter(|| parse_text_borrowed(black_box(&input)).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_parse);
+criterion_main!(benches);
@@ -0,0 +1,18 @@
+//! Feeds arbitrary bytes to the text parsers, which should return an error rather than panic
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use scutil_parser::dns::{parse_text, parse_text_with_options, ParseOptions};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(input) = std::str::from_utf8(data) else {
+        return;
+    };
+    let _ = parse_text(input);
+    let _ = parse_text_with_options(input, &ParseOptions::new().lenient(true).recover(true));
+    let _ = scutil_parser::borrowed::parse_text_borrowed(input);
+    let _ = scutil_parser::proxy::parse_text(input);
+    let _ = scutil_parser::reachability::parse_text(input);
+    let _ = scutil_parser::windows::parse_text(input);
+});
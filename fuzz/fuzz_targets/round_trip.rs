@@ -0,0 +1,12 @@
+//! Renders generated configs as `scutil --dns` text, which should parse back to the same config
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use scutil_parser::dns::{parse_text, DNSConfig};
+
+fuzz_target!(|config: DNSConfig| {
+    let rendered = config.to_scutil_string();
+    let reparsed = parse_text(&rendered).expect("rendered text should parse");
+    assert_eq!(reparsed, config, "{}", rendered);
+});
@@ -0,0 +1,244 @@
+//! [Arbitrary] implementations for the parsed types, for fuzzing with the `arbitrary` feature.
+//!
+//! These only generate values that scutil could have printed, so rendering one with
+//! [DNSConfig::to_scutil_string] and parsing it again gives back an equal value. Names are made
+//! up of lowercase letters, digits and dashes, and lists are kept short.
+
+use std::collections::HashSet;
+use std::time::Duration;
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+use crate::dns::{
+    DNSConfig, InterfaceIndex, Nameserver, NameserverAddress, Resolver, ResolverFlags,
+    ResolverOption, ResolverOptions, SearchDomain, SortListEntry,
+};
+use crate::reachability::{ReachabilityFlag, ReachabilityFlags};
+
+/// A name like `en0` or `x-3f`, starting with `prefix` so it can't clash with a known value
+fn token(u: &mut Unstructured<'_>, prefix: &str) -> Result<String> {
+    const CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789-";
+    let mut token = prefix.to_string();
+    for _ in 0..u.int_in_range(1..=12)? {
+        token.push(*u.choose(CHARS)? as char);
+    }
+    Ok(token)
+}
+
+/// A domain name with one to four labels
+fn domain(u: &mut Unstructured<'_>) -> Result<String> {
+    let labels = (0..u.int_in_range(1..=4)?)
+        .map(|_| token(u, ""))
+        .collect::<Result<Vec<String>>>()?;
+    Ok(labels.join("."))
+}
+
+/// Up to `max` values, generated by `f`
+fn list<T>(
+    u: &mut Unstructured<'_>,
+    max: usize,
+    mut f: impl FnMut(&mut Unstructured<'_>, usize) -> Result<T>,
+) -> Result<Vec<T>> {
+    (0..u.int_in_range(0..=max)?)
+        .map(|index| f(u, index))
+        .collect()
+}
+
+impl<'a> Arbitrary<'a> for ResolverFlags {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(match u.int_in_range(0..=5)? {
+            0 => ResolverFlags::RequestARecords,
+            1 => ResolverFlags::RequestAAAARecords,
+            2 => ResolverFlags::Scoped,
+            3 => ResolverFlags::ServiceSpecific,
+            4 => ResolverFlags::Supplemental,
+            _ => ResolverFlags::Unknown(token(u, "flag-")?),
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for ResolverOption {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(match u.int_in_range(0..=3)? {
+            0 => ResolverOption::Mdns,
+            1 => ResolverOption::Pdns,
+            2 => ResolverOption::Value {
+                key: token(u, "")?,
+                value: token(u, "")?,
+            },
+            _ => ResolverOption::Unknown(token(u, "opt-")?),
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for ResolverOptions {
+    /// There's always at least one option, as an empty `options` line isn't printed
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut options = vec![ResolverOption::arbitrary(u)?];
+        options.extend(list(u, 2, |u, _| ResolverOption::arbitrary(u))?);
+        Ok(ResolverOptions(options))
+    }
+}
+
+impl<'a> Arbitrary<'a> for InterfaceIndex {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(InterfaceIndex {
+            index: u.arbitrary()?,
+            interface: match u.arbitrary()? {
+                true => Some(token(u, "")?),
+                false => None,
+            },
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for NameserverAddress {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let address: std::net::IpAddr = u.arbitrary()?;
+        let scope = match address.is_ipv6() && u.arbitrary()? {
+            true => Some(token(u, "")?),
+            false => None,
+        };
+        Ok(NameserverAddress { address, scope })
+    }
+}
+
+impl<'a> Arbitrary<'a> for Nameserver {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Nameserver {
+            index: u.arbitrary()?,
+            address: u.arbitrary()?,
+            port: u.arbitrary()?,
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for SearchDomain {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(SearchDomain {
+            index: u.arbitrary()?,
+            domain: domain(u)?,
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for SortListEntry {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(SortListEntry {
+            address: u.arbitrary()?,
+            mask: u.arbitrary()?,
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for ReachabilityFlag {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(match u.int_in_range(0..=10)? {
+            0 => ReachabilityFlag::TransientConnection,
+            1 => ReachabilityFlag::Reachable,
+            2 => ReachabilityFlag::ConnectionRequired,
+            3 => ReachabilityFlag::ConnectionOnTraffic,
+            4 => ReachabilityFlag::InterventionRequired,
+            5 => ReachabilityFlag::ConnectionOnDemand,
+            6 => ReachabilityFlag::LocalAddress,
+            7 => ReachabilityFlag::DirectlyReachableAddress,
+            8 => ReachabilityFlag::WWAN,
+            9 => ReachabilityFlag::NotReachable,
+            _ => ReachabilityFlag::Unknown(token(u, "reach-")?),
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for ReachabilityFlags {
+    /// The value isn't tied to the descriptors, as they're both taken from the text as-is
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(ReachabilityFlags {
+            value: u.arbitrary()?,
+            flags: list(u, 3, |u, _| ReachabilityFlag::arbitrary(u))?,
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for Resolver {
+    /// Search domains and nameservers are numbered from zero, as scutil prints them, and `extra`
+    /// is always empty as it isn't rendered. When there's a numeric `flags_value` the flags are
+    /// decoded from it, as they would be when parsing.
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut resolver = Resolver {
+            id: u.arbitrary()?,
+            annotation: match u.arbitrary()? {
+                true => Some(format!("({})", token(u, "")?)),
+                false => None,
+            },
+            search_domains: list(u, 3, |u, index| {
+                Ok(SearchDomain {
+                    index,
+                    domain: domain(u)?,
+                })
+            })?,
+            nameservers: list(u, 3, |u, index| {
+                Ok(Nameserver {
+                    index,
+                    address: u.arbitrary()?,
+                    port: u.arbitrary()?,
+                })
+            })?,
+            if_index: u.arbitrary()?,
+            service_identifier: u.arbitrary()?,
+            flags: match u.arbitrary()? {
+                true => Some(list(u, 3, |u, _| ResolverFlags::arbitrary(u))?),
+                false => None,
+            },
+            reach: u.arbitrary()?,
+            order: u.arbitrary()?,
+            domain: match u.arbitrary()? {
+                true => Some(domain(u)?),
+                false => None,
+            },
+            timeout: Option::<u32>::arbitrary(u)?.map(|secs| Duration::from_secs(secs.into())),
+            options: u.arbitrary()?,
+            port: u.arbitrary()?,
+            config_id: match u.arbitrary()? {
+                true => Some(token(u, "com.example.")?),
+                false => None,
+            },
+            sortlist: list(u, 3, |u, _| u.arbitrary())?,
+            extra: Default::default(),
+            flags_value: None,
+            raw_lines: None,
+        };
+        if let Some(value) = u.arbitrary()? {
+            resolver.flags_value = Some(value);
+            resolver.flags = Some(ResolverFlags::from_value(value));
+        }
+        Ok(resolver)
+    }
+}
+
+/// Up to `max` resolvers for one section, dropping any whose id was already used as that doesn't
+/// parse
+fn section(u: &mut Unstructured<'_>, max: usize) -> Result<Vec<Resolver>> {
+    let mut ids = HashSet::new();
+    let mut resolvers = list(u, max, |u, _| Resolver::arbitrary(u))?;
+    resolvers.retain(|resolver| ids.insert(resolver.id));
+    Ok(resolvers)
+}
+
+impl<'a> Arbitrary<'a> for DNSConfig {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(DNSConfig {
+            dns_config: section(u, 4)?,
+            scoped_dns_config: section(u, 4)?,
+            service_specific_dns_config: section(u, 2)?,
+            metadata: Default::default(),
+            other_sections: match bool::arbitrary(u)? {
+                true => [(
+                    "DNS configuration (for mdns queries)".to_string(),
+                    section(u, 2)?,
+                )]
+                .into(),
+                false => Default::default(),
+            },
+        })
+    }
+}
@@ -0,0 +1,459 @@
+//! A zero-copy version of [crate::dns::parse_text], for when `scutil --dns` is parsed often
+//! enough for the allocations to matter, eg when polling it every few seconds.
+//!
+//! The text fields borrow from the input instead of being copied into `String`s, and `flags`
+//! and `options` are kept as the text scutil printed. [DNSConfigRef::to_owned] converts the
+//! result into a [DNSConfig] when it needs to outlive the input. Only strict parsing is
+//! supported, the [crate::dns::ParseOptions] need the owned types.
+
+use std::collections::{BTreeMap, HashMap};
+use std::net::IpAddr;
+use std::str::FromStr;
+use std::time::Duration;
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use crate::dns::{
+    field_key, field_value, flags_value, is_resolver_header, section_header, split_interface_index,
+    split_nameserver_port, split_resolver_header, strip_bom, DNSConfig, InterfaceIndex, Nameserver,
+    NameserverAddress, ParseError, ReachabilityFlags, Resolver, ResolverFlags, ResolverOptions,
+    SearchDomain, SectionKind, SortListEntry, NAMESERVER_PARSER, SEARCH_DOMAIN_PARSER,
+};
+
+/// A [DNSConfig] that borrows from the text it was parsed from, see [parse_text_borrowed]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct DNSConfigRef<'a> {
+    pub dns_config: Vec<ResolverRef<'a>>,
+    pub scoped_dns_config: Vec<ResolverRef<'a>>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub service_specific_dns_config: Vec<ResolverRef<'a>>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "BTreeMap::is_empty"))]
+    pub metadata: BTreeMap<&'a str, BTreeMap<&'a str, &'a str>>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "BTreeMap::is_empty"))]
+    pub other_sections: BTreeMap<&'a str, Vec<ResolverRef<'a>>>,
+}
+
+/// A [Resolver] that borrows from the text it was parsed from
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct ResolverRef<'a> {
+    pub id: usize,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub annotation: Option<&'a str>,
+    /// Sorted by index, so the order of the array is the order they're searched in
+    pub search_domains: Vec<SearchDomainRef<'a>>,
+    /// Sorted by index, which is the order they're queried in
+    pub nameservers: Vec<NameserverRef<'a>>,
+    pub if_index: Option<InterfaceIndexRef<'a>>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub service_identifier: Option<u32>,
+    /// The flags as scutil printed them, eg `Request A records`
+    pub flags: Option<Vec<&'a str>>,
+    /// The raw value when the `flags` line is a number, see [Resolver::flags_value]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub flags_value: Option<u32>,
+    pub reach: Option<ReachabilityFlags>,
+    pub order: Option<usize>,
+    pub domain: Option<&'a str>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "timeout_secs", serialize_with = "serialize_secs")
+    )]
+    pub timeout: Option<Duration>,
+    /// The options as scutil printed them, eg `mdns`
+    pub options: Option<&'a str>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub port: Option<u16>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub config_id: Option<&'a str>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub sortlist: Vec<SortListEntry>,
+}
+
+/// A [SearchDomain] that borrows from the text it was parsed from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct SearchDomainRef<'a> {
+    pub index: usize,
+    pub domain: &'a str,
+}
+
+/// A [Nameserver] that borrows from the text it was parsed from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct NameserverRef<'a> {
+    pub index: usize,
+    pub address: IpAddr,
+    /// The zone/scope id after the `%`, if there was one
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub scope: Option<&'a str>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub port: Option<u16>,
+}
+
+/// An [InterfaceIndex] that borrows from the text it was parsed from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct InterfaceIndexRef<'a> {
+    pub index: usize,
+    pub interface: Option<&'a str>,
+}
+
+#[cfg(feature = "serde")]
+fn serialize_secs<S: serde::Serializer>(
+    value: &Option<Duration>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match value {
+        Some(duration) => serializer.serialize_some(&duration.as_secs()),
+        None => serializer.serialize_none(),
+    }
+}
+
+impl DNSConfigRef<'_> {
+    /// Copies everything into a [DNSConfig], which is what [crate::dns::parse_text] would have
+    /// returned for the same input
+    pub fn to_owned(&self) -> DNSConfig {
+        let section = |resolvers: &[ResolverRef]| resolvers.iter().map(|r| r.to_owned()).collect();
+        DNSConfig {
+            dns_config: section(&self.dns_config),
+            scoped_dns_config: section(&self.scoped_dns_config),
+            service_specific_dns_config: section(&self.service_specific_dns_config),
+            metadata: self
+                .metadata
+                .iter()
+                .map(|(header, metadata)| {
+                    let metadata = metadata
+                        .iter()
+                        .map(|(key, value)| (key.to_string(), value.to_string()))
+                        .collect();
+                    (header.to_string(), metadata)
+                })
+                .collect(),
+            other_sections: self
+                .other_sections
+                .iter()
+                .map(|(header, resolvers)| (header.to_string(), section(resolvers)))
+                .collect(),
+        }
+    }
+}
+
+impl ResolverRef<'_> {
+    /// Copies everything into a [Resolver]
+    pub fn to_owned(&self) -> Resolver {
+        Resolver {
+            id: self.id,
+            annotation: self.annotation.map(str::to_string),
+            search_domains: self
+                .search_domains
+                .iter()
+                .map(|search_domain| SearchDomain {
+                    index: search_domain.index,
+                    domain: search_domain.domain.to_string(),
+                })
+                .collect(),
+            nameservers: self
+                .nameservers
+                .iter()
+                .map(|nameserver| Nameserver {
+                    index: nameserver.index,
+                    address: NameserverAddress {
+                        address: nameserver.address,
+                        scope: nameserver.scope.map(str::to_string),
+                    },
+                    port: nameserver.port,
+                })
+                .collect(),
+            if_index: self.if_index.map(|if_index| InterfaceIndex {
+                index: if_index.index,
+                interface: if_index.interface.map(str::to_string),
+            }),
+            service_identifier: self.service_identifier,
+            flags: match self.flags_value {
+                Some(value) => Some(ResolverFlags::from_value(value)),
+                None => self.flags.as_ref().map(|flags| {
+                    flags
+                        .iter()
+                        .map(|flag| ResolverFlags::from(*flag))
+                        .collect()
+                }),
+            },
+            flags_value: self.flags_value,
+            reach: self.reach.clone(),
+            order: self.order,
+            domain: self.domain.map(str::to_string),
+            timeout: self.timeout,
+            options: self
+                .options
+                .and_then(|options| ResolverOptions::from_str(options).ok()),
+            port: self.port,
+            config_id: self.config_id.map(str::to_string),
+            sortlist: self.sortlist.clone(),
+            extra: Default::default(),
+            raw_lines: None,
+        }
+    }
+}
+
+impl<'a> ResolverRef<'a> {
+    /// Adds a nameserver, keeping them sorted by index and replacing any with the same index
+    fn add_nameserver(&mut self, nameserver: NameserverRef<'a>) {
+        match self
+            .nameservers
+            .binary_search_by_key(&nameserver.index, |ns| ns.index)
+        {
+            Ok(position) => self.nameservers[position] = nameserver,
+            Err(position) => self.nameservers.insert(position, nameserver),
+        }
+    }
+}
+
+/// Which section of the output we're in
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Section<'a> {
+    Unscoped,
+    Scoped,
+    ServiceSpecific,
+    /// A header we don't know about
+    Other(&'a str),
+}
+
+impl<'a> Section<'a> {
+    /// The line that starts the section
+    fn header(&self) -> &'a str {
+        match self {
+            Section::Unscoped => SectionKind::Unscoped.header(),
+            Section::Scoped => SectionKind::Scoped.header(),
+            Section::ServiceSpecific => SectionKind::ServiceSpecific.header(),
+            Section::Other(header) => header,
+        }
+    }
+}
+
+/// Adds the resolver we were reading, if there was one, to the section it's in
+fn finish_resolver<'a>(
+    dns_config: &mut DNSConfigRef<'a>,
+    section: Option<Section<'a>>,
+    resolver: Option<ResolverRef<'a>>,
+) {
+    let (Some(section), Some(resolver)) = (section, resolver) else {
+        return;
+    };
+    match section {
+        Section::Unscoped => dns_config.dns_config.push(resolver),
+        Section::Scoped => dns_config.scoped_dns_config.push(resolver),
+        Section::ServiceSpecific => dns_config.service_specific_dns_config.push(resolver),
+        Section::Other(header) => dns_config
+            .other_sections
+            .entry(header)
+            .or_default()
+            .push(resolver),
+    }
+}
+
+/// Parses the output of `scutil --dns` without copying any text, failing on anything unexpected
+pub fn parse_text_borrowed(input: &str) -> Result<DNSConfigRef<'_>, ParseError> {
+    let mut dns_config = DNSConfigRef::default();
+    let mut section: Option<Section> = None;
+    let mut current_resolver: Option<ResolverRef> = None;
+    // where each resolver was first seen
+    let mut resolver_lines: HashMap<(Section, usize), usize> = HashMap::new();
+    // metadata lines are only expected before the first resolver in a section
+    let mut section_has_resolvers = false;
+
+    for (line_index, line) in strip_bom(input).lines().enumerate() {
+        let line_no = line_index + 1;
+        let trimmed = line.trim();
+        let unexpected = || ParseError::UnexpectedLine {
+            line_no,
+            content: line.to_string(),
+        };
+        let invalid_integer = |source| ParseError::InvalidInteger {
+            line_no,
+            content: line.to_string(),
+            source,
+        };
+
+        let new_section = section_header(line).map(|header| match header {
+            "DNS configuration" => Section::Unscoped,
+            "DNS configuration (for scoped queries)" => Section::Scoped,
+            "DNS configuration (for service-specific queries)" => Section::ServiceSpecific,
+            header => {
+                dns_config.other_sections.entry(header).or_default();
+                Section::Other(header)
+            }
+        });
+        if new_section.is_some() {
+            finish_resolver(&mut dns_config, section, current_resolver.take());
+            section = new_section;
+            section_has_resolvers = false;
+            continue;
+        }
+
+        if is_resolver_header(line) {
+            section_has_resolvers = true;
+            let (id, annotation) = split_resolver_header(line, line_no)?;
+            finish_resolver(&mut dns_config, section, current_resolver.take());
+            let Some(section) = section else {
+                return Err(unexpected());
+            };
+            if let Some(&first_line_no) = resolver_lines.get(&(section, id)) {
+                return Err(ParseError::DuplicateResolver {
+                    line_no,
+                    content: line.to_string(),
+                    id,
+                    first_line_no,
+                });
+            }
+            resolver_lines.insert((section, id), line_no);
+            current_resolver = Some(ResolverRef {
+                id,
+                annotation,
+                ..Default::default()
+            });
+            continue;
+        }
+
+        if trimmed.is_empty() {
+            if section.is_none() {
+                return Err(unexpected());
+            }
+            finish_resolver(&mut dns_config, section, current_resolver.take());
+            continue;
+        }
+
+        let Some(resolver) = current_resolver.as_mut() else {
+            // `key : value` lines before a section's first resolver are kept as metadata
+            let (Some(section), false, Some((key, value))) =
+                (section, section_has_resolvers, trimmed.split_once(':'))
+            else {
+                return Err(unexpected());
+            };
+            dns_config
+                .metadata
+                .entry(section.header())
+                .or_default()
+                .insert(key.trim(), value.trim());
+            continue;
+        };
+        if field_key(line) == Some("nameserver") {
+            let nameserver =
+                NAMESERVER_PARSER
+                    .captures(trimmed)
+                    .ok_or_else(|| ParseError::MissingField {
+                        line_no,
+                        content: line.to_string(),
+                        field: "nameserver index or address",
+                    })?;
+            let index = nameserver["ns_id"].parse().map_err(invalid_integer)?;
+            let value = nameserver.name("nameserver").map_or("", |m| m.as_str());
+            let (address, port) = split_nameserver_port(value);
+            let (address, scope) = match address.split_once('%') {
+                Some((address, scope)) => (address, Some(scope).filter(|s| !s.is_empty())),
+                None => (address, None),
+            };
+            let address = address
+                .parse()
+                .map_err(|source| ParseError::InvalidNameserver {
+                    line_no,
+                    content: line.to_string(),
+                    source,
+                })?;
+            let port = port
+                .map(|port| port.parse().map_err(invalid_integer))
+                .transpose()?;
+            resolver.add_nameserver(NameserverRef {
+                index,
+                address,
+                scope,
+                port,
+            });
+        } else if field_key(line) == Some("search domain") {
+            let search_domain =
+                SEARCH_DOMAIN_PARSER
+                    .captures(trimmed)
+                    .ok_or_else(|| ParseError::MissingField {
+                        line_no,
+                        content: line.to_string(),
+                        field: "search domain",
+                    })?;
+            let index = match search_domain.name("index") {
+                Some(index) => index.as_str().parse().map_err(invalid_integer)?,
+                None => resolver
+                    .search_domains
+                    .last()
+                    .map_or(0, |last| last.index + 1),
+            };
+            let domain = search_domain.name("domain").map_or("", |m| m.as_str());
+            let position = resolver
+                .search_domains
+                .partition_point(|search_domain| search_domain.index <= index);
+            resolver
+                .search_domains
+                .insert(position, SearchDomainRef { index, domain });
+        } else if field_key(line) == Some("if_index") {
+            let (index, interface) =
+                split_interface_index(trimmed).map_err(|message| ParseError::InvalidValue {
+                    line_no,
+                    content: line.to_string(),
+                    message,
+                })?;
+            resolver.if_index = Some(InterfaceIndexRef { index, interface });
+        } else if field_key(line) == Some("service_identifier") {
+            resolver.service_identifier = Some(field_value(line).parse().map_err(invalid_integer)?);
+        } else if field_key(line) == Some("flags") {
+            resolver.flags_value = flags_value(field_value(line))
+                .transpose()
+                .map_err(invalid_integer)?;
+            resolver.flags = Some(
+                field_value(line)
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .collect(),
+            );
+        } else if field_key(line) == Some("port") {
+            resolver.port = Some(field_value(line).parse().map_err(invalid_integer)?);
+        } else if field_key(line) == Some("reach") {
+            resolver.reach = Some(ReachabilityFlags::from_str(field_value(line)).map_err(
+                |message| ParseError::InvalidValue {
+                    line_no,
+                    content: line.to_string(),
+                    message,
+                },
+            )?);
+        } else if field_key(line) == Some("order") {
+            resolver.order = Some(field_value(line).parse().map_err(invalid_integer)?);
+        } else if field_key(line) == Some("timeout") {
+            let secs = field_value(line).parse().map_err(invalid_integer)?;
+            resolver.timeout = Some(Duration::from_secs(secs));
+        } else if field_key(line) == Some("options") {
+            resolver.options = Some(field_value(line));
+        } else if matches!(field_key(line), Some("sortaddr" | "SortList")) {
+            for entry in field_value(line).split_whitespace() {
+                resolver
+                    .sortlist
+                    .push(SortListEntry::from_str(entry).map_err(|message| {
+                        ParseError::InvalidValue {
+                            line_no,
+                            content: line.to_string(),
+                            message,
+                        }
+                    })?);
+            }
+        } else if field_key(line) == Some("config id") {
+            resolver.config_id = Some(field_value(line));
+        } else if field_key(line) == Some("domain") {
+            resolver.domain = Some(field_value(line));
+        } else {
+            return Err(unexpected());
+        }
+    }
+    // the output doesn't always end with a blank line
+    finish_resolver(&mut dns_config, section, current_resolver);
+
+    Ok(dns_config)
+}
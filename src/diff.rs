@@ -0,0 +1,254 @@
+//! Comparing two [DNSConfig] snapshots, eg from polling `scutil --dns`.
+//!
+//! The resolver ids are just their position in the output, so resolvers are matched up by their
+//! `domain` and `if_index` instead, falling back to the order they appear in when several share
+//! those. For the scoped resolvers, which don't have a `domain`, that's the interface.
+
+use std::collections::BTreeMap;
+
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::dns::{DNSConfig, NameserverAddress, Resolver};
+
+/// The differences between two [DNSConfig]s, see [DNSConfig::diff]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct DnsConfigDiff {
+    pub dns_config: SectionDiff,
+    pub scoped_dns_config: SectionDiff,
+    pub service_specific_dns_config: SectionDiff,
+    /// The sections without a field of their own, see [DNSConfig::other_sections], keyed by the
+    /// header line. Only sections with differences are included.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "BTreeMap::is_empty")
+    )]
+    pub other_sections: BTreeMap<String, SectionDiff>,
+}
+
+impl DnsConfigDiff {
+    /// Whether nothing changed
+    pub fn is_empty(&self) -> bool {
+        self.dns_config.is_empty()
+            && self.scoped_dns_config.is_empty()
+            && self.service_specific_dns_config.is_empty()
+            && self.other_sections.is_empty()
+    }
+}
+
+/// The differences in one section of the output
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct SectionDiff {
+    /// Resolvers that are only in the new config
+    pub added: Vec<Resolver>,
+    /// Resolvers that are only in the old config
+    pub removed: Vec<Resolver>,
+    /// Resolvers that are in both, but with different values
+    pub changed: Vec<ResolverDiff>,
+}
+
+impl SectionDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// The changes to a resolver that's in both configs
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct ResolverDiff {
+    /// The resolver's id in the old config
+    pub old_id: usize,
+    /// The resolver's id in the new config
+    pub new_id: usize,
+    pub domain: Option<String>,
+    pub changes: Vec<FieldChange>,
+    /// Nameservers that are only in the new resolver
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub added_nameservers: Vec<NameserverAddress>,
+    /// Nameservers that are only in the old resolver
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub removed_nameservers: Vec<NameserverAddress>,
+}
+
+/// A field that changed, with the values rendered the way scutil prints them, and lists joined
+/// with `, `. `None` means the field wasn't set.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct FieldChange {
+    pub field: String,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+impl DNSConfig {
+    /// Works out what changed between this config and `other`, which is treated as the newer one
+    pub fn diff(&self, other: &DNSConfig) -> DnsConfigDiff {
+        DnsConfigDiff {
+            dns_config: diff_section(&self.dns_config, &other.dns_config),
+            scoped_dns_config: diff_section(&self.scoped_dns_config, &other.scoped_dns_config),
+            service_specific_dns_config: diff_section(
+                &self.service_specific_dns_config,
+                &other.service_specific_dns_config,
+            ),
+            other_sections: self
+                .other_sections
+                .keys()
+                .chain(other.other_sections.keys())
+                .filter_map(|header| {
+                    let section = diff_section(
+                        self.other_section(header).unwrap_or_default(),
+                        other.other_section(header).unwrap_or_default(),
+                    );
+                    (!section.is_empty()).then(|| (header.clone(), section))
+                })
+                .collect(),
+        }
+    }
+}
+
+/// What resolvers are matched up on, see the module docs
+fn resolver_key(resolver: &Resolver) -> (Option<&str>, Option<usize>) {
+    (
+        resolver.domain.as_deref(),
+        resolver.if_index.as_ref().map(|if_index| if_index.index),
+    )
+}
+
+fn diff_section(old: &[Resolver], new: &[Resolver]) -> SectionDiff {
+    let mut diff = SectionDiff::default();
+    let mut matched = vec![false; new.len()];
+
+    for old_resolver in old {
+        let key = resolver_key(old_resolver);
+        let new_index =
+            (0..new.len()).find(|index| !matched[*index] && resolver_key(&new[*index]) == key);
+        let Some(new_index) = new_index else {
+            diff.removed.push(old_resolver.clone());
+            continue;
+        };
+        matched[new_index] = true;
+
+        let new_resolver = &new[new_index];
+        let changes = diff_resolver(old_resolver, new_resolver);
+        if !changes.is_empty() {
+            diff.changed.push(ResolverDiff {
+                old_id: old_resolver.id,
+                new_id: new_resolver.id,
+                domain: old_resolver.domain.clone(),
+                changes,
+                added_nameservers: missing_nameservers(new_resolver, old_resolver),
+                removed_nameservers: missing_nameservers(old_resolver, new_resolver),
+            });
+        }
+    }
+
+    diff.added = new
+        .iter()
+        .zip(matched)
+        .filter(|(_, matched)| !matched)
+        .map(|(resolver, _)| resolver.clone())
+        .collect();
+    diff
+}
+
+fn diff_resolver(old: &Resolver, new: &Resolver) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+    let mut compare = |field: &str, old: Option<String>, new: Option<String>| {
+        if old != new {
+            changes.push(FieldChange {
+                field: field.to_string(),
+                old,
+                new,
+            });
+        }
+    };
+
+    compare(
+        "search_domains",
+        join(&old.search_domains),
+        join(&new.search_domains),
+    );
+    compare(
+        "nameservers",
+        join(&old.nameservers),
+        join(&new.nameservers),
+    );
+    compare("port", display(&old.port), display(&new.port));
+    compare("if_index", display(&old.if_index), display(&new.if_index));
+    compare(
+        "service_identifier",
+        display(&old.service_identifier),
+        display(&new.service_identifier),
+    );
+    compare("options", display(&old.options), display(&new.options));
+    compare(
+        "timeout",
+        display(&old.timeout.map(|timeout| timeout.as_secs())),
+        display(&new.timeout.map(|timeout| timeout.as_secs())),
+    );
+    compare(
+        "flags",
+        join(old.flags.as_deref().unwrap_or_default()),
+        join(new.flags.as_deref().unwrap_or_default()),
+    );
+    compare("reach", display(&old.reach), display(&new.reach));
+    compare("order", display(&old.order), display(&new.order));
+    compare("config_id", old.config_id.clone(), new.config_id.clone());
+    compare("sortlist", join(&old.sortlist), join(&new.sortlist));
+
+    let mut keys: Vec<&String> = old.extra.keys().chain(new.extra.keys()).collect();
+    keys.sort();
+    keys.dedup();
+    for key in keys {
+        compare(
+            &format!("extra.{}", key),
+            old.extra.get(key).cloned(),
+            new.extra.get(key).cloned(),
+        );
+    }
+
+    changes
+}
+
+/// The nameservers in `resolver` that `other` doesn't have, ignoring their indexes
+fn missing_nameservers(resolver: &Resolver, other: &Resolver) -> Vec<NameserverAddress> {
+    resolver
+        .nameservers
+        .iter()
+        .map(|nameserver| &nameserver.address)
+        .filter(|address| {
+            !other
+                .nameservers
+                .iter()
+                .any(|nameserver| nameserver.address == **address)
+        })
+        .cloned()
+        .collect()
+}
+
+fn display<T: std::fmt::Display>(value: &Option<T>) -> Option<String> {
+    value.as_ref().map(|value| value.to_string())
+}
+
+fn join<T: std::fmt::Display>(values: &[T]) -> Option<String> {
+    if values.is_empty() {
+        return None;
+    }
+    let values: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+    Some(values.join(", "))
+}
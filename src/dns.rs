@@ -1,30 +1,221 @@
 use lazy_static::lazy_static;
 use regex::Regex;
-use serde::Serialize;
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use thiserror::Error;
 
-use std::collections::HashMap;
+pub use crate::reachability::{ReachabilityFlag, ReachabilityFlags};
+#[cfg(feature = "system")]
+use crate::runner::{ScutilRunner, SystemError, SystemScutil};
+
+use std::collections::{BTreeMap, HashMap};
+use std::convert::Infallible;
 use std::fmt::{Display, Formatter};
-use std::net::IpAddr;
+use std::io::{self, BufRead};
+use std::net::{AddrParseError, IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV6};
+use std::num::ParseIntError;
 use std::str::FromStr;
+use std::time::Duration;
+
+/// Errors returned by [parse_text], `line_no` is 1-based so it matches what an editor shows
+#[derive(Debug, Error)]
+pub enum ParseError {
+    #[error("line {line_no}: unexpected line: {content:?}")]
+    UnexpectedLine { line_no: usize, content: String },
+    #[error("line {line_no}: missing {field} in {content:?}")]
+    MissingField {
+        line_no: usize,
+        content: String,
+        field: &'static str,
+    },
+    #[error("line {line_no}: invalid nameserver address in {content:?}: {source}")]
+    InvalidNameserver {
+        line_no: usize,
+        content: String,
+        source: AddrParseError,
+    },
+    #[error("line {line_no}: invalid integer in {content:?}: {source}")]
+    InvalidInteger {
+        line_no: usize,
+        content: String,
+        source: ParseIntError,
+    },
+    #[error("line {line_no}: {message}: {content:?}")]
+    InvalidValue {
+        line_no: usize,
+        content: String,
+        message: String,
+    },
+    /// The same `resolver #N` turned up twice in one section
+    #[error("line {line_no}: resolver #{id} appears more than once, first on line {first_line_no}: {content:?}")]
+    DuplicateResolver {
+        line_no: usize,
+        content: String,
+        id: usize,
+        first_line_no: usize,
+    },
+    /// Reading the input failed, only returned by [parse_reader]
+    #[error("line {line_no}: failed to read input: {source}")]
+    Io { line_no: usize, source: io::Error },
+}
+
+impl ParseError {
+    /// The 1-based line number the error occurred on
+    pub fn line_no(&self) -> usize {
+        match self {
+            ParseError::UnexpectedLine { line_no, .. }
+            | ParseError::MissingField { line_no, .. }
+            | ParseError::InvalidNameserver { line_no, .. }
+            | ParseError::InvalidInteger { line_no, .. }
+            | ParseError::InvalidValue { line_no, .. }
+            | ParseError::DuplicateResolver { line_no, .. }
+            | ParseError::Io { line_no, .. } => *line_no,
+        }
+    }
+
+    /// What went wrong, without the line number or content
+    pub(crate) fn reason(&self) -> String {
+        match self {
+            ParseError::UnexpectedLine { .. } => "unexpected line".to_string(),
+            ParseError::MissingField { field, .. } => format!("missing {}", field),
+            ParseError::InvalidNameserver { source, .. } => {
+                format!("invalid nameserver address: {}", source)
+            }
+            ParseError::InvalidInteger { source, .. } => format!("invalid integer: {}", source),
+            ParseError::InvalidValue { message, .. } => message.clone(),
+            ParseError::DuplicateResolver {
+                id, first_line_no, ..
+            } => format!(
+                "resolver #{} appears more than once, first on line {}",
+                id, first_line_no
+            ),
+            ParseError::Io { source, .. } => format!("failed to read input: {}", source),
+        }
+    }
+
+    /// The text of the line the error occurred on, empty for [ParseError::Io]
+    pub fn content(&self) -> &str {
+        match self {
+            ParseError::Io { .. } => "",
+            ParseError::UnexpectedLine { content, .. }
+            | ParseError::MissingField { content, .. }
+            | ParseError::InvalidNameserver { content, .. }
+            | ParseError::InvalidInteger { content, .. }
+            | ParseError::InvalidValue { content, .. }
+            | ParseError::DuplicateResolver { content, .. } => content,
+        }
+    }
+}
+
+/// Well-known public resolvers, used by [DNSConfig::public_resolvers]: Google, Cloudflare, Quad9
+/// and OpenDNS
+pub const PUBLIC_RESOLVERS: &[IpAddr] = &[
+    IpAddr::V4(Ipv4Addr::new(8, 8, 8, 8)),
+    IpAddr::V4(Ipv4Addr::new(8, 8, 4, 4)),
+    IpAddr::V6(Ipv6Addr::new(0x2001, 0x4860, 0x4860, 0, 0, 0, 0, 0x8888)),
+    IpAddr::V6(Ipv6Addr::new(0x2001, 0x4860, 0x4860, 0, 0, 0, 0, 0x8844)),
+    IpAddr::V4(Ipv4Addr::new(1, 1, 1, 1)),
+    IpAddr::V4(Ipv4Addr::new(1, 0, 0, 1)),
+    IpAddr::V6(Ipv6Addr::new(0x2606, 0x4700, 0x4700, 0, 0, 0, 0, 0x1111)),
+    IpAddr::V6(Ipv6Addr::new(0x2606, 0x4700, 0x4700, 0, 0, 0, 0, 0x1001)),
+    IpAddr::V4(Ipv4Addr::new(9, 9, 9, 9)),
+    IpAddr::V4(Ipv4Addr::new(149, 112, 112, 112)),
+    IpAddr::V6(Ipv6Addr::new(0x2620, 0xfe, 0, 0, 0, 0, 0, 0xfe)),
+    IpAddr::V6(Ipv6Addr::new(0x2620, 0xfe, 0, 0, 0, 0, 0, 0x9)),
+    IpAddr::V4(Ipv4Addr::new(208, 67, 222, 222)),
+    IpAddr::V4(Ipv4Addr::new(208, 67, 220, 220)),
+];
 
-#[derive(Clone, Debug, Serialize)]
+/// The resolver flags from `dnsinfo.h`
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub enum ResolverFlags {
     RequestARecords,
     RequestAAAARecords,
     Scoped,
+    /// `DNS_RESOLVER_FLAGS_SERVICE_SPECIFIC`
+    ServiceSpecific,
+    /// `DNS_RESOLVER_FLAGS_SUPPLEMENTAL`, eg the split-DNS resolvers pushed by a VPN
+    Supplemental,
+    /// A flag we don't know about, stored verbatim
+    Unknown(String),
+}
+
+/// Unrecognised flags become [ResolverFlags::Unknown], so this can't fail
+impl From<&str> for ResolverFlags {
+    fn from(s: &str) -> Self {
+        match s {
+            "Request A records" => ResolverFlags::RequestARecords,
+            "Request AAAA records" => ResolverFlags::RequestAAAARecords,
+            "Scoped" => ResolverFlags::Scoped,
+            "Service-specific" => ResolverFlags::ServiceSpecific,
+            "Supplemental" => ResolverFlags::Supplemental,
+            _ => ResolverFlags::Unknown(s.to_string()),
+        }
+    }
 }
 
 impl FromStr for ResolverFlags {
-    type Err = String;
+    type Err = Infallible;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "Request A records" => Ok(ResolverFlags::RequestARecords),
-            "Request AAAA records" => Ok(ResolverFlags::RequestAAAARecords),
-            "Scoped" => Ok(ResolverFlags::Scoped),
-            _ => Err(format!("Invalid resolver flag: {}", s)),
+        Ok(ResolverFlags::from(s))
+    }
+}
+
+impl ResolverFlags {
+    // the DNS_RESOLVER_FLAGS_* bits from dnsinfo.h
+    pub const SCOPED: u32 = 0x1;
+    pub const SERVICE_SPECIFIC: u32 = 0x2;
+    pub const SUPPLEMENTAL: u32 = 0x4;
+    pub const REQUEST_A_RECORDS: u32 = 0x2000;
+    pub const REQUEST_AAAA_RECORDS: u32 = 0x4000;
+
+    /// The `dnsinfo.h` bit for this flag, `0` for flags we don't know about
+    pub fn bit(&self) -> u32 {
+        match self {
+            ResolverFlags::RequestARecords => Self::REQUEST_A_RECORDS,
+            ResolverFlags::RequestAAAARecords => Self::REQUEST_AAAA_RECORDS,
+            ResolverFlags::Scoped => Self::SCOPED,
+            ResolverFlags::ServiceSpecific => Self::SERVICE_SPECIFIC,
+            ResolverFlags::Supplemental => Self::SUPPLEMENTAL,
+            ResolverFlags::Unknown(_) => 0,
         }
     }
+
+    /// Decodes a numeric `flags` value, in the order scutil prints the names. Bits we don't know
+    /// about become [ResolverFlags::Unknown] with the bit in hex, eg `0x100`.
+    pub fn from_value(value: u32) -> Vec<Self> {
+        let known = [
+            ResolverFlags::Scoped,
+            ResolverFlags::ServiceSpecific,
+            ResolverFlags::Supplemental,
+            ResolverFlags::RequestARecords,
+            ResolverFlags::RequestAAAARecords,
+        ];
+        let known_bits = known.iter().fold(0, |bits, flag| bits | flag.bit());
+        let unknown = (0..u32::BITS)
+            .map(|shift| 1 << shift)
+            .filter(|bit| value & bit & !known_bits != 0)
+            .map(|bit| ResolverFlags::Unknown(format!("{:#x}", bit)));
+        known
+            .into_iter()
+            .filter(|flag| value & flag.bit() != 0)
+            .chain(unknown)
+            .collect()
+    }
+}
+
+/// The value of a numeric `flags` line, eg `0x00000007`, `None` if it's the names instead
+pub(crate) fn flags_value(value: &str) -> Option<Result<u32, ParseIntError>> {
+    let value = value.split_whitespace().next()?;
+    let hex = value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))?;
+    Some(u32::from_str_radix(hex, 16))
 }
 
 impl Display for ResolverFlags {
@@ -33,49 +224,412 @@ impl Display for ResolverFlags {
             ResolverFlags::RequestARecords => write!(f, "Request A records"),
             ResolverFlags::RequestAAAARecords => write!(f, "Request AAAA records"),
             ResolverFlags::Scoped => write!(f, "Scoped"),
+            ResolverFlags::ServiceSpecific => write!(f, "Service-specific"),
+            ResolverFlags::Supplemental => write!(f, "Supplemental"),
+            ResolverFlags::Unknown(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+/// A single token from a resolver's `options` line
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub enum ResolverOption {
+    /// Multicast DNS
+    Mdns,
+    /// Private DNS
+    Pdns,
+    /// A `key=value` option, eg `timeout=5`
+    Value { key: String, value: String },
+    /// An option we don't know about, stored verbatim
+    Unknown(String),
+}
+
+impl FromStr for ResolverOption {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "mdns" => ResolverOption::Mdns,
+            "pdns" => ResolverOption::Pdns,
+            _ => match s.split_once('=') {
+                Some((key, value)) => ResolverOption::Value {
+                    key: key.to_string(),
+                    value: value.to_string(),
+                },
+                None => ResolverOption::Unknown(s.to_string()),
+            },
+        })
+    }
+}
+
+impl Display for ResolverOption {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            ResolverOption::Mdns => write!(f, "mdns"),
+            ResolverOption::Pdns => write!(f, "pdns"),
+            ResolverOption::Value { key, value } => write!(f, "{}={}", key, value),
+            ResolverOption::Unknown(s) => write!(f, "{}", s),
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize)]
+/// A parsed `options` line, eg `mdns` or `mdns, timeout=5`, which serializes as an array
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct ResolverOptions(pub Vec<ResolverOption>);
+
+impl ResolverOptions {
+    pub fn contains(&self, option: &ResolverOption) -> bool {
+        self.0.contains(option)
+    }
+
+    /// Returns the value of a `key=value` option
+    pub fn value(&self, key: &str) -> Option<&str> {
+        self.0.iter().find_map(|option| match option {
+            ResolverOption::Value { key: k, value } if k == key => Some(value.as_str()),
+            _ => None,
+        })
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, ResolverOption> {
+        self.0.iter()
+    }
+}
+
+impl FromStr for ResolverOptions {
+    type Err = Infallible;
+
+    /// Splits the options on commas and whitespace
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|token| !token.is_empty())
+            .map(ResolverOption::from_str)
+            .collect::<Result<Vec<ResolverOption>, Infallible>>()
+            .map(Self)
+    }
+}
+
+impl Display for ResolverOptions {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        let tokens: Vec<String> = self.0.iter().map(|option| option.to_string()).collect();
+        write!(f, "{}", tokens.join(", "))
+    }
+}
+
+/// An `if_index` line, eg `if_index : 15 (en0)`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct InterfaceIndex {
     pub index: usize,
-    pub interface: String,
+    /// The interface name from inside the parentheses, scutil leaves it out for some resolvers,
+    /// eg `if_index : 0`
+    pub interface: Option<String>,
 }
 
 impl FromStr for InterfaceIndex {
     type Err = String;
 
+    /// Accepts either the whole `if_index : 15 (en0)` line or just the value
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut split = s.split(' ');
-        let index: usize = split.nth(2).unwrap().parse().unwrap();
-        let mut interface = split.last().unwrap_or("");
-        if interface.starts_with('(') {
-            interface = interface.strip_prefix('(').unwrap();
-        }
-        if interface.ends_with(')') {
-            interface = interface.strip_suffix(')').unwrap();
+        let (index, interface) = split_interface_index(s)?;
+        Ok(Self {
+            index,
+            interface: interface.map(str::to_string),
+        })
+    }
+}
+
+/// Splits an `if_index` line or value into the index and the interface name, if there is one
+pub(crate) fn split_interface_index(s: &str) -> Result<(usize, Option<&str>), String> {
+    let value = s.split_once(':').map_or(s, |(_, value)| value).trim();
+    let (index, interface) = value.split_once(char::is_whitespace).unwrap_or((value, ""));
+    if index.is_empty() {
+        return Err("Missing interface index".to_string());
+    }
+    let index: usize = index
+        .parse()
+        .map_err(|err| format!("Invalid interface index {:?}: {}", index, err))?;
+    let interface = match interface.trim() {
+        "" => None,
+        interface => Some(
+            interface
+                .strip_prefix('(')
+                .and_then(|interface| interface.strip_suffix(')'))
+                .filter(|interface| !interface.trim().is_empty())
+                .ok_or_else(|| {
+                    format!(
+                        "Interface name {:?} should be inside parentheses",
+                        interface
+                    )
+                })?
+                .trim(),
+        ),
+    };
+    Ok((index, interface))
+}
+
+impl Display for InterfaceIndex {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        match &self.interface {
+            Some(interface) => write!(f, "{} ({})", self.index, interface),
+            None => write!(f, "{}", self.index),
         }
+    }
+}
 
+/// A nameserver address, which for IPv6 link-local addresses can include a zone, eg
+/// `fe80::1%en0`. Serializes to the same string form it was parsed from.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct NameserverAddress {
+    pub address: IpAddr,
+    /// The zone/scope id after the `%`, if there was one
+    pub scope: Option<String>,
+}
+
+impl FromStr for NameserverAddress {
+    type Err = AddrParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (address, scope) = match s.split_once('%') {
+            Some((address, scope)) => (address, Some(scope.to_string())),
+            None => (s, None),
+        };
         Ok(Self {
-            index,
-            interface: interface.to_string(),
+            address: address.parse()?,
+            scope: scope.filter(|scope| !scope.is_empty()),
+        })
+    }
+}
+
+impl Display for NameserverAddress {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        match &self.scope {
+            Some(scope) => write!(f, "{}%{}", self.address, scope),
+            None => write!(f, "{}", self.address),
+        }
+    }
+}
+
+impl From<IpAddr> for NameserverAddress {
+    fn from(address: IpAddr) -> Self {
+        Self {
+            address,
+            scope: None,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for NameserverAddress {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for NameserverAddress {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// A plain string, as that's how it's serialized
+#[cfg(feature = "schemars")]
+impl JsonSchema for NameserverAddress {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "NameserverAddress".into()
+    }
+
+    fn json_schema(_generator: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        schemars::json_schema!({
+            "type": "string",
+            "description": "An IP address, with a `%zone` for IPv6 link-local addresses"
+        })
+    }
+}
+
+/// (De)serializes an optional [Duration] as a whole number of seconds
+#[cfg(feature = "serde")]
+mod duration_secs {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(
+        value: &Option<Duration>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(duration) => serializer.serialize_some(&duration.as_secs()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Duration>, D::Error> {
+        Ok(Option::<u64>::deserialize(deserializer)?.map(Duration::from_secs))
+    }
+}
+
+/// A `nameserver[N]` line
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct Nameserver {
+    /// The `N` from `nameserver[N]`
+    pub index: usize,
+    pub address: NameserverAddress,
+    /// Set when the address included a port, eg `10.0.0.1:5353`
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub port: Option<u16>,
+}
+
+impl Display for Nameserver {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        match (self.port, self.address.address) {
+            (Some(port), IpAddr::V6(_)) => write!(f, "[{}]:{}", self.address, port),
+            (Some(port), IpAddr::V4(_)) => write!(f, "{}:{}", self.address, port),
+            (None, _) => write!(f, "{}", self.address),
+        }
+    }
+}
+
+/// A `search domain[N]` line
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct SearchDomain {
+    /// The `N` from `search domain[N]`, or one more than the previous index if it was left out
+    pub index: usize,
+    pub domain: String,
+}
+
+impl Display for SearchDomain {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        write!(f, "{}", self.domain)
+    }
+}
+
+/// A `sortaddr[N]` line, or one of the entries on a `SortList` line, eg `10.0.0.0/255.0.0.0`.
+/// Like `sortlist` in resolv.conf, answers in these networks are preferred.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct SortListEntry {
+    pub address: IpAddr,
+    /// The netmask after the `/`, which can be left out to use the address's natural mask
+    pub mask: Option<IpAddr>,
+}
+
+impl FromStr for SortListEntry {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (address, mask) = match s.trim().split_once('/') {
+            Some((address, mask)) => (address, Some(mask)),
+            None => (s.trim(), None),
+        };
+        let parse = |value: &str| {
+            IpAddr::from_str(value.trim())
+                .map_err(|err| format!("Invalid sort list address {:?}: {}", value, err))
+        };
+        Ok(SortListEntry {
+            address: parse(address)?,
+            mask: mask.map(parse).transpose()?,
         })
     }
 }
 
-#[derive(Clone, Debug, Default, Serialize)]
+impl Display for SortListEntry {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        match self.mask {
+            Some(mask) => write!(f, "{}/{}", self.address, mask),
+            None => write!(f, "{}", self.address),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct Resolver {
     pub id: usize,
-    pub search_domains: Vec<String>,
-    pub nameservers: HashMap<usize, IpAddr>,
+    /// Anything after the `#N` on the `resolver #N` line, eg `(Default)`
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub annotation: Option<String>,
+    /// Sorted by index, so the order of the array is the order they're searched in
+    pub search_domains: Vec<SearchDomain>,
+    /// Sorted by index, which is the order they're queried in
+    pub nameservers: Vec<Nameserver>,
     pub if_index: Option<InterfaceIndex>,
-    pub flags: Vec<ResolverFlags>,
-    pub reach: Option<String>,
+    /// Identifies the network service a service-specific resolver belongs to
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub service_identifier: Option<u32>,
+    /// `None` if there wasn't a `flags` line, and empty if there was one with nothing after the
+    /// colon
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub flags: Option<Vec<ResolverFlags>>,
+    /// The raw value when the `flags` line is a number, eg `0x00000007`, which is decoded into
+    /// [Resolver::flags]
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub flags_value: Option<u32>,
+    pub reach: Option<ReachabilityFlags>,
     pub order: Option<usize>,
+    /// The domain a supplemental resolver answers for. scutil prints one per resolver, so a VPN
+    /// with several match domains shows up as several resolvers.
     pub domain: Option<String>,
-    pub timeout: Option<usize>,
-    pub options: Option<String>,
+    /// scutil reports this in whole seconds, so it's serialized as `timeout_secs`
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "timeout_secs", default, with = "duration_secs")
+    )]
+    #[cfg_attr(feature = "schemars", schemars(with = "Option<u64>"))]
+    pub timeout: Option<Duration>,
+    pub options: Option<ResolverOptions>,
+    /// From the `port` line, which applies to nameservers that don't have their own port
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub port: Option<u16>,
+    /// From the `config id` line, set when the resolver was installed by a configuration profile
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub config_id: Option<String>,
+    /// From the `sortaddr[N]` or `SortList` lines, in the order they were printed
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub sortlist: Vec<SortListEntry>,
+    /// Fields we don't know about, only filled in lenient mode. The [crate::windows] parsers
+    /// also keep the interface name here when there's no index for [Resolver::if_index].
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "BTreeMap::is_empty")
+    )]
+    pub extra: BTreeMap<String, String>,
+    /// The lines the resolver was parsed from, starting with `resolver #N`, if
+    /// [ParseOptions::keep_raw_lines] was set
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub raw_lines: Option<Vec<String>>,
 }
 
 impl Resolver {
@@ -85,226 +639,1961 @@ impl Resolver {
             ..Default::default()
         }
     }
-}
 
-#[derive(Debug, Serialize)]
-pub struct DNSConfig {
-    pub dns_config: Vec<Resolver>,
-    pub scoped_dns_config: Vec<Resolver>,
-}
+    /// Starts building the resolver printed as `resolver #id`, see [ResolverBuilder]
+    pub fn builder(id: usize) -> ResolverBuilder {
+        ResolverBuilder::new(id)
+    }
 
-#[derive(Debug, Clone)]
-enum ParserState {
-    DnsConfig,
-    ScopedDnsConfig,
-    Resolver,
-    ScopedResolver,
-    Idle,
-}
+    /// The search domains in the order they're searched
+    pub fn search_domain_names(&self) -> Vec<&str> {
+        self.search_domains
+            .iter()
+            .map(|search_domain| search_domain.domain.as_str())
+            .collect()
+    }
 
-pub fn parse_text(input: &str) -> Result<DNSConfig, String> {
-    let mut dns_config = DNSConfig {
-        dns_config: Vec::new(),
-        scoped_dns_config: Vec::new(),
-    };
+    /// Whether the `flags` line includes `flag`
+    pub fn has_flag(&self, flag: &ResolverFlags) -> bool {
+        self.flags
+            .as_ref()
+            .is_some_and(|flags| flags.contains(flag))
+    }
 
-    let mut parent_state: ParserState = ParserState::Idle;
-    let mut state: ParserState = ParserState::Idle;
-    let mut current_resolver: Resolver = Resolver::new(0);
+    /// Whether this is one of the multicast DNS placeholder resolvers, either from the `mdns`
+    /// option or because it's for `local` or a reverse zone and has no nameservers to ask
+    pub fn is_mdns(&self) -> bool {
+        if self
+            .options
+            .as_ref()
+            .is_some_and(|options| options.contains(&ResolverOption::Mdns))
+        {
+            return true;
+        }
+        let Some(domain) = &self.domain else {
+            return false;
+        };
+        let domain = domain.trim_end_matches('.').to_ascii_lowercase();
+        !self.has_nameservers()
+            && ["local", "in-addr.arpa", "ip6.arpa"].iter().any(|zone| {
+                domain == *zone
+                    || domain
+                        .strip_suffix(zone)
+                        .is_some_and(|rest| rest.ends_with('.'))
+            })
+    }
 
-    let mut line_index = 0;
-    let lines: Vec<String> = input.lines().map(|s| s.to_string()).collect();
+    /// Whether this is bound to an interface, from the `Scoped` flag
+    pub fn is_scoped(&self) -> bool {
+        self.has_flag(&ResolverFlags::Scoped)
+    }
 
-    while line_index < lines.len() {
-        let line = match lines.get(line_index) {
-            Some(line) => line,
-            None => {
-                return Err(format!("Failed to get line index {}", line_index));
-            }
-        };
+    /// Whether this only answers for its `domain` rather than being a default resolver, from the
+    /// `Supplemental` flag, or for output without it, having a `domain` but no `if_index`
+    pub fn is_supplemental(&self) -> bool {
+        self.has_flag(&ResolverFlags::Supplemental)
+            || (self.domain.is_some() && self.if_index.is_none())
+    }
 
-        #[cfg(test)]
-        eprintln!("Parsing line: '{}'", line);
-        if line.trim() == "DNS configuration" {
-            #[cfg(test)]
-            eprintln!("Setting state to ParserState::DnsConfig");
-            state = ParserState::DnsConfig;
-            parent_state = ParserState::DnsConfig;
-            line_index += 2;
-            continue;
-        } else if line.trim() == "DNS configuration (for scoped queries)" {
-            #[cfg(test)]
-            eprintln!("Setting state to ParserState::ScopedDnsConfig");
-            state = ParserState::ScopedDnsConfig;
-            parent_state = ParserState::ScopedDnsConfig;
-            line_index += 2;
-            continue;
-        }
+    /// Whether there are any `nameserver[n]` lines
+    pub fn has_nameservers(&self) -> bool {
+        !self.nameservers.is_empty()
+    }
 
-        if line.starts_with("resolver") {
-            let resolver_index: usize = line
-                .split(' ')
-                .last()
-                .unwrap()
-                .strip_prefix('#')
-                .expect("Couldn't strip prefix off resolver line")
-                .parse()
-                .expect("Couldn't parse resolver index");
+    /// The nameserver addresses in index order, without any zone or port
+    pub fn nameserver_addresses(&self) -> Vec<IpAddr> {
+        self.nameservers
+            .iter()
+            .map(|nameserver| nameserver.address.address)
+            .collect()
+    }
 
-            match state {
-                ParserState::DnsConfig => {
-                    state = ParserState::Resolver;
-                    #[cfg(test)]
-                    eprintln!("Starting new resolver index {}", resolver_index);
-                    current_resolver = Resolver::new(resolver_index);
-                    line_index += 1;
-                    continue;
-                }
-                ParserState::ScopedDnsConfig => {
-                    state = ParserState::ScopedResolver;
-                    #[cfg(test)]
-                    eprintln!("Starting new scoped resolver index {}", resolver_index);
-                    current_resolver = Resolver::new(resolver_index);
-                    line_index += 1;
-                    continue;
-                }
-                _ => {
-                    return Err(format!(
-                        "Unexpected resolver line: {} (state: {:?})",
-                        line, state
-                    ));
-                }
+    /// The addresses that are listed more than once, eg with different ports or zones, each one
+    /// once in index order
+    pub fn duplicate_nameservers(&self) -> Vec<IpAddr> {
+        let addresses = self.nameserver_addresses();
+        let mut duplicates: Vec<IpAddr> = Vec::new();
+        for (position, address) in addresses.iter().enumerate() {
+            if addresses[..position].contains(address) && !duplicates.contains(address) {
+                duplicates.push(*address);
             }
         }
+        duplicates
+    }
 
-        if line.trim().starts_with("nameserver") {
-            #[cfg(test)]
-            eprintln!("Handling nameserver");
-            let nameserver = NAMESERVER_PARSER.captures(line.trim()).unwrap();
-            let ns_id: usize = nameserver
-                .name("ns_id")
-                .unwrap()
-                .as_str()
-                .parse()
-                .expect("Couldn't parse nameserver ID");
-            let nameserver: IpAddr = nameserver
-                .name("nameserver")
-                .unwrap()
-                .as_str()
-                .parse()
-                .expect("Couldn't parse nameserver IP");
-            #[cfg(test)]
-            eprintln!("Adding nameserver {} - {}", ns_id, nameserver);
-            current_resolver.nameservers.insert(ns_id, nameserver);
-        } else if line.trim().starts_with("search domain") {
-            let search_domain = line.split(' ').last().map(|s| s.to_string());
-            if let Some(search_domain) = search_domain {
-                #[cfg(test)]
-                eprintln!("Set search domain to {:?}", search_domain);
-                current_resolver.search_domains.push(search_domain);
-            }
-        } else if line.trim().starts_with("if_index") {
-            current_resolver.if_index = Some(InterfaceIndex::from_str(line.trim())?);
-        } else if line.trim().starts_with("flags") {
-            if let Some(flags) = line.trim().split(':').last().and_then(|l| {
-                l.split(',')
-                    .map(|s| ResolverFlags::from_str(s.trim()))
-                    .collect::<Result<Vec<ResolverFlags>, String>>()
-                    .ok()
-            }) {
-                current_resolver.flags = flags;
-            };
-        } else if line.trim().starts_with("reach") {
-            let reach = line.trim().split(':').last().unwrap().trim();
-            #[cfg(test)]
-            eprintln!("Set reach to {}", reach);
-            current_resolver.reach = Some(reach.to_string());
-        } else if line.trim().starts_with("order") {
-            let order = line.trim().split(':').last().unwrap().trim();
-            let order: usize = order.parse::<usize>().map_err(|err| err.to_string())?;
-            #[cfg(test)]
-            eprintln!("Set order to {}", order);
-            current_resolver.order = Some(order);
-        } else if line.trim().starts_with("timeout") {
-            let timeout = line.trim().split(':').last().unwrap().trim();
-            let timeout: usize = timeout.parse::<usize>().map_err(|err| err.to_string())?;
-            current_resolver.timeout = Some(timeout);
-            #[cfg(test)]
-            eprintln!("Set timeout to {}", timeout);
-        } else if line.trim().starts_with("options") {
-            let options = line.trim().split(':').last().unwrap().trim().to_string();
-            #[cfg(test)]
-            eprintln!("Set options to {}", options);
-            current_resolver.options = Some(options);
-        } else if line.trim().starts_with("domain") {
-            let domain = line.trim().split(':').last().unwrap().trim().to_string();
-            #[cfg(test)]
-            eprintln!("Set domain to {}", domain);
-            current_resolver.domain = Some(domain);
-        } else if line.trim() == "" {
-            match state {
-                ParserState::Resolver => {
-                    state = parent_state.clone();
-                    #[cfg(test)]
-                    eprintln!(
-                        "Finished resolver index {} - {:?}",
-                        current_resolver.id, current_resolver
-                    );
-                    dns_config.dns_config.push(current_resolver.clone());
-                    line_index += 1;
-                    continue;
-                }
-                ParserState::ScopedResolver => {
-                    state = parent_state.clone();
-                    #[cfg(test)]
-                    eprintln!("Finished scoped resolver index {}", current_resolver.id);
-                    dns_config.scoped_dns_config.push(current_resolver.clone());
-                    line_index += 1;
-                    continue;
-                }
-                ParserState::DnsConfig | ParserState::ScopedDnsConfig => {}
-                _ => {
-                    return Err(format!(
-                        "Unexpected empty line: {} state: {:?}",
-                        line, state
-                    ));
+    pub fn has_duplicate_nameservers(&self) -> bool {
+        !self.duplicate_nameservers().is_empty()
+    }
+
+    /// The socket addresses of the nameservers, using the nameserver's own port, then the
+    /// resolver's `port`, then 53.
+    ///
+    /// IPv6 zones are only carried over when they're numeric, as turning an interface name into
+    /// an index needs the system that the output came from.
+    pub fn nameserver_socket_addrs(&self) -> Vec<SocketAddr> {
+        self.nameservers
+            .iter()
+            .map(|nameserver| {
+                let port = nameserver.port.or(self.port).unwrap_or(53);
+                match nameserver.address.address {
+                    IpAddr::V4(address) => SocketAddr::new(address.into(), port),
+                    IpAddr::V6(address) => {
+                        let scope_id = nameserver
+                            .address
+                            .scope
+                            .as_deref()
+                            .and_then(|scope| scope.parse().ok())
+                            .unwrap_or(0);
+                        SocketAddr::V6(SocketAddrV6::new(address, port, 0, scope_id))
+                    }
                 }
-            }
-        } else {
-            return Err(format!("Unexpected line: {}", line));
+            })
+            .collect()
+    }
+
+    /// Adds a nameserver, keeping them sorted by index and replacing any with the same index
+    pub fn add_nameserver(&mut self, nameserver: Nameserver) {
+        match self
+            .nameservers
+            .binary_search_by_key(&nameserver.index, |ns| ns.index)
+        {
+            Ok(position) => self.nameservers[position] = nameserver,
+            Err(position) => self.nameservers.insert(position, nameserver),
         }
-        line_index += 1;
     }
-    // final cleanup bit
-    // dbg!(&current_resolver);
-    // dbg!(&state);
+}
 
-    match state {
-        ParserState::Resolver => {
-            if current_resolver.id != 0
-                && current_resolver.nameservers.is_empty()
-                && current_resolver.if_index.is_some()
-            {
-                dns_config.dns_config.push(current_resolver)
-            }
+/// Builds a [Resolver] one field at a time, for tests and configs that weren't parsed:
+///
+/// ```
+/// use scutil_parser::dns::{Resolver, ResolverFlags};
+///
+/// let resolver = Resolver::builder(1)
+///     .search_domain("example.com")
+///     .nameserver("10.0.0.1".parse::<std::net::IpAddr>().unwrap())
+///     .flag(ResolverFlags::RequestARecords)
+///     .order(200000)
+///     .build()
+///     .unwrap();
+/// assert_eq!(resolver.search_domain_names(), vec!["example.com"]);
+/// assert_eq!(resolver.nameservers[0].index, 0);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ResolverBuilder {
+    resolver: Resolver,
+    allow_mdns_nameservers: bool,
+}
+
+/// Why [ResolverBuilder::build] or [DNSConfigBuilder::build] rejected a resolver
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum BuildError {
+    /// scutil doesn't print nameservers for mdns resolvers, see
+    /// [ResolverBuilder::allow_mdns_nameservers]
+    #[error("resolver #{0} has the mdns option and nameservers")]
+    MdnsWithNameservers(usize),
+    /// The `Scoped` flag without an `if_index` to say what it's scoped to
+    #[error("resolver #{0} is scoped but doesn't have an if_index")]
+    ScopedWithoutInterface(usize),
+}
+
+impl ResolverBuilder {
+    pub fn new(id: usize) -> Self {
+        Self {
+            resolver: Resolver::new(id),
+            ..Default::default()
         }
-        ParserState::ScopedResolver => {
-            if current_resolver.id != 0
-                && !current_resolver.nameservers.is_empty()
-                && current_resolver.if_index.is_some()
-            {
-                dns_config.scoped_dns_config.push(current_resolver)
-            }
+    }
+
+    /// See [Resolver::annotation]
+    pub fn annotation(mut self, annotation: impl Into<String>) -> Self {
+        self.resolver.annotation = Some(annotation.into());
+        self
+    }
+
+    /// Adds a search domain after the ones already added
+    pub fn search_domain(mut self, domain: impl Into<String>) -> Self {
+        self.resolver.search_domains.push(SearchDomain {
+            index: self.resolver.search_domains.len(),
+            domain: domain.into(),
+        });
+        self
+    }
+
+    /// Adds a nameserver at the index after the last one
+    pub fn nameserver(self, address: impl Into<NameserverAddress>) -> Self {
+        let index = self
+            .resolver
+            .nameservers
+            .last()
+            .map_or(0, |nameserver| nameserver.index + 1);
+        self.nameserver_at(index, address)
+    }
+
+    /// Adds `nameserver[index]`, replacing any nameserver already at that index
+    pub fn nameserver_at(mut self, index: usize, address: impl Into<NameserverAddress>) -> Self {
+        self.resolver.add_nameserver(Nameserver {
+            index,
+            address: address.into(),
+            port: None,
+        });
+        self
+    }
+
+    pub fn if_index(mut self, index: usize, interface: Option<&str>) -> Self {
+        self.resolver.if_index = Some(InterfaceIndex {
+            index,
+            interface: interface.map(str::to_string),
+        });
+        self
+    }
+
+    pub fn service_identifier(mut self, service_identifier: u32) -> Self {
+        self.resolver.service_identifier = Some(service_identifier);
+        self
+    }
+
+    /// Adds a flag after the ones already added
+    pub fn flag(mut self, flag: ResolverFlags) -> Self {
+        self.resolver.flags.get_or_insert_with(Vec::new).push(flag);
+        self
+    }
+
+    /// Adds the `Scoped` flag, if it isn't there already. Scoped resolvers also need an
+    /// [ResolverBuilder::if_index].
+    pub fn scoped(self) -> Self {
+        if self.resolver.is_scoped() {
+            return self;
         }
-        ParserState::ScopedDnsConfig => {}
-        ParserState::DnsConfig => {}
-        ParserState::Idle => {}
+        self.flag(ResolverFlags::Scoped)
+    }
+
+    /// Sets the flags from a numeric value, replacing any already added, see
+    /// [Resolver::flags_value]
+    pub fn flags_value(mut self, value: u32) -> Self {
+        self.resolver.flags_value = Some(value);
+        self.resolver.flags = Some(ResolverFlags::from_value(value));
+        self
+    }
+
+    pub fn reach(mut self, reach: ReachabilityFlags) -> Self {
+        self.resolver.reach = Some(reach);
+        self
+    }
+
+    pub fn order(mut self, order: usize) -> Self {
+        self.resolver.order = Some(order);
+        self
+    }
+
+    pub fn domain(mut self, domain: impl Into<String>) -> Self {
+        self.resolver.domain = Some(domain.into());
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.resolver.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the timeout in whole seconds, which is how scutil prints it
+    pub fn timeout_secs(self, secs: u64) -> Self {
+        self.timeout(Duration::from_secs(secs))
+    }
+
+    /// Adds an option after the ones already added
+    pub fn option(mut self, option: ResolverOption) -> Self {
+        self.resolver
+            .options
+            .get_or_insert_with(Default::default)
+            .0
+            .push(option);
+        self
+    }
+
+    pub fn port(mut self, port: u16) -> Self {
+        self.resolver.port = Some(port);
+        self
     }
 
-    Ok(dns_config)
+    pub fn config_id(mut self, config_id: impl Into<String>) -> Self {
+        self.resolver.config_id = Some(config_id.into());
+        self
+    }
+
+    /// Adds a sort list entry after the ones already added
+    pub fn sortlist_entry(mut self, entry: SortListEntry) -> Self {
+        self.resolver.sortlist.push(entry);
+        self
+    }
+
+    /// Lets [ResolverBuilder::build] accept an mdns resolver with nameservers
+    pub fn allow_mdns_nameservers(mut self, allow: bool) -> Self {
+        self.allow_mdns_nameservers = allow;
+        self
+    }
+
+    /// Builds the resolver, checking it's one scutil could print: mdns resolvers don't have
+    /// nameservers, and scoped resolvers have an `if_index`
+    pub fn build(self) -> Result<Resolver, BuildError> {
+        let resolver = self.resolver;
+        let mdns_option = resolver
+            .options
+            .as_ref()
+            .is_some_and(|options| options.contains(&ResolverOption::Mdns));
+        if mdns_option && resolver.has_nameservers() && !self.allow_mdns_nameservers {
+            return Err(BuildError::MdnsWithNameservers(resolver.id));
+        }
+        if resolver.is_scoped() && resolver.if_index.is_none() {
+            return Err(BuildError::ScopedWithoutInterface(resolver.id));
+        }
+        Ok(resolver)
+    }
+
+    /// Builds the resolver without any checks, for configs that are broken on purpose
+    pub fn build_unchecked(self) -> Resolver {
+        self.resolver
+    }
+}
+
+/// Builds a [DNSConfig] from [ResolverBuilder]s, numbering the resolvers in each section from 1
+/// in the order they're added:
+///
+/// ```
+/// use std::net::IpAddr;
+///
+/// use scutil_parser::dns::{DNSConfig, ResolverBuilder};
+///
+/// let config = DNSConfig::builder()
+///     .resolver(ResolverBuilder::default().nameserver(IpAddr::from([10, 0, 0, 1])))
+///     .resolver(ResolverBuilder::default().domain("local").timeout_secs(5))
+///     .scoped_resolver(
+///         ResolverBuilder::default()
+///             .nameserver(IpAddr::from([10, 0, 0, 1]))
+///             .if_index(15, Some("en0")),
+///     )
+///     .build()
+///     .unwrap();
+/// assert_eq!(config.dns_config[1].id, 2);
+/// assert!(config.scoped_dns_config[0].is_scoped());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct DNSConfigBuilder {
+    sections: [Vec<ResolverBuilder>; 3],
+}
+
+impl DNSConfigBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a resolver to [DNSConfig::dns_config], replacing its id with the next one
+    pub fn resolver(self, resolver: ResolverBuilder) -> Self {
+        self.add(SectionKind::Unscoped, resolver)
+    }
+
+    /// Adds a resolver to [DNSConfig::scoped_dns_config], replacing its id with the next one and
+    /// adding the `Scoped` flag
+    pub fn scoped_resolver(self, resolver: ResolverBuilder) -> Self {
+        self.add(SectionKind::Scoped, resolver.scoped())
+    }
+
+    /// Adds a resolver to [DNSConfig::service_specific_dns_config], replacing its id with the
+    /// next one
+    pub fn service_specific_resolver(self, resolver: ResolverBuilder) -> Self {
+        self.add(SectionKind::ServiceSpecific, resolver)
+    }
+
+    fn add(mut self, kind: SectionKind, mut resolver: ResolverBuilder) -> Self {
+        let section = match kind {
+            SectionKind::Unscoped => &mut self.sections[0],
+            SectionKind::Scoped => &mut self.sections[1],
+            SectionKind::ServiceSpecific => &mut self.sections[2],
+        };
+        resolver.resolver.id = section.len() + 1;
+        section.push(resolver);
+        self
+    }
+
+    /// Builds the config, checking every resolver with [ResolverBuilder::build]
+    pub fn build(self) -> Result<DNSConfig, BuildError> {
+        self.finish(ResolverBuilder::build)
+    }
+
+    /// Builds the config without checking the resolvers, see [ResolverBuilder::build_unchecked]
+    pub fn build_unchecked(self) -> DNSConfig {
+        self.finish(|resolver| Ok(resolver.build_unchecked()))
+            .unwrap_or_else(|err: Infallible| match err {})
+    }
+
+    fn finish<E>(
+        self,
+        build: impl Fn(ResolverBuilder) -> Result<Resolver, E>,
+    ) -> Result<DNSConfig, E> {
+        let [dns_config, scoped_dns_config, service_specific_dns_config] =
+            self.sections.map(|section| {
+                section
+                    .into_iter()
+                    .map(&build)
+                    .collect::<Result<Vec<Resolver>, E>>()
+            });
+        Ok(DNSConfig {
+            dns_config: dns_config?,
+            scoped_dns_config: scoped_dns_config?,
+            service_specific_dns_config: service_specific_dns_config?,
+            metadata: BTreeMap::new(),
+            other_sections: BTreeMap::new(),
+        })
+    }
+}
+
+/// Renders the resolver block in the same layout as `scutil --dns`, without a trailing blank
+/// line. Fields which are `None` are left out, as is [Resolver::extra] so the output can be
+/// parsed strictly.
+impl Display for Resolver {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        match &self.annotation {
+            Some(annotation) => writeln!(f, "resolver #{} {}", self.id, annotation)?,
+            None => writeln!(f, "resolver #{}", self.id)?,
+        }
+        if let Some(domain) = &self.domain {
+            writeln!(f, "  domain   : {}", domain)?;
+        }
+        for search_domain in &self.search_domains {
+            writeln!(
+                f,
+                "  search domain[{}] : {}",
+                search_domain.index, search_domain
+            )?;
+        }
+        for nameserver in &self.nameservers {
+            writeln!(f, "  nameserver[{}] : {}", nameserver.index, nameserver)?;
+        }
+        if let Some(port) = self.port {
+            writeln!(f, "  port     : {}", port)?;
+        }
+        if let Some(if_index) = &self.if_index {
+            writeln!(f, "  if_index : {}", if_index)?;
+        }
+        if let Some(service_identifier) = self.service_identifier {
+            writeln!(f, "  service_identifier : {}", service_identifier)?;
+        }
+        if let Some(options) = &self.options {
+            writeln!(f, "  options  : {}", options)?;
+        }
+        if let Some(timeout) = self.timeout {
+            writeln!(f, "  timeout  : {}", timeout.as_secs())?;
+        }
+        if let Some(value) = self.flags_value {
+            writeln!(f, "  flags    : {:#010x}", value)?;
+        } else if let Some(flags) = &self.flags {
+            let flags: Vec<String> = flags.iter().map(|flag| flag.to_string()).collect();
+            writeln!(f, "  flags    : {}", flags.join(", "))?;
+        }
+        if let Some(reach) = &self.reach {
+            writeln!(f, "  reach    : {}", reach)?;
+        }
+        if let Some(order) = self.order {
+            writeln!(f, "  order    : {}", order)?;
+        }
+        if let Some(config_id) = &self.config_id {
+            writeln!(f, "  config id : {}", config_id)?;
+        }
+        for (index, entry) in self.sortlist.iter().enumerate() {
+            writeln!(f, "  sortaddr[{}] : {}", index, entry)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct DNSConfig {
+    pub dns_config: Vec<Resolver>,
+    pub scoped_dns_config: Vec<Resolver>,
+    /// From the `DNS configuration (for service-specific queries)` section, which only shows up
+    /// with some VPN and profile setups
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub service_specific_dns_config: Vec<Resolver>,
+    /// `key : value` lines between a section's header and its first resolver, keyed by the
+    /// header line, see [DNSConfig::section_metadata]
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "BTreeMap::is_empty")
+    )]
+    pub metadata: BTreeMap<String, BTreeMap<String, String>>,
+    /// Sections with headers we don't know about, eg `DNS configuration (for mdns queries)`,
+    /// keyed by the header line
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "BTreeMap::is_empty")
+    )]
+    pub other_sections: BTreeMap<String, Vec<Resolver>>,
+}
+
+/// A resolver that uses a nameserver, see [DNSConfig::all_nameservers_with_sources]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NameserverSource<'a> {
+    /// The header of the section the resolver is in, eg `DNS configuration (for scoped queries)`
+    pub section: &'a str,
+    pub resolver: &'a Resolver,
+}
+
+/// The resolvers that apply to queries on one interface, see [DNSConfig::effective_for_interface]
+#[derive(Clone, Debug, PartialEq)]
+pub struct EffectiveConfig<'a> {
+    /// The scoped resolvers bound to the interface, which stand in for the default resolver
+    pub scoped: Vec<&'a Resolver>,
+    /// Every resolver that applies, in the order they're considered. The unscoped resolvers with
+    /// a `domain` come first, lowest `order` first, then the scoped resolvers, or the unscoped
+    /// resolvers without a `domain` if the interface doesn't have any.
+    pub resolvers: Vec<&'a Resolver>,
+}
+
+/// The sections of `scutil --dns` output
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub enum SectionKind {
+    /// `DNS configuration`, [DNSConfig::dns_config]
+    Unscoped,
+    /// `DNS configuration (for scoped queries)`, [DNSConfig::scoped_dns_config]
+    Scoped,
+    /// `DNS configuration (for service-specific queries)`, [DNSConfig::service_specific_dns_config]
+    ServiceSpecific,
+}
+
+impl SectionKind {
+    /// The line that starts the section
+    pub fn header(&self) -> &'static str {
+        match self {
+            SectionKind::Unscoped => "DNS configuration",
+            SectionKind::Scoped => "DNS configuration (for scoped queries)",
+            SectionKind::ServiceSpecific => "DNS configuration (for service-specific queries)",
+        }
+    }
+}
+
+/// Renders the config in the same layout as `scutil --dns`, which [parse_text] accepts
+impl Display for DNSConfig {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        let mut write_section = |header: &str, resolvers: &[Resolver]| {
+            writeln!(f, "{}", header)?;
+            for (key, value) in self.section_metadata(header).into_iter().flatten() {
+                writeln!(f, "{} : {}", key, value)?;
+            }
+            writeln!(f)?;
+            for resolver in resolvers {
+                writeln!(f, "{}", resolver)?;
+            }
+            Ok(())
+        };
+        for (kind, resolvers) in self.sections() {
+            // scutil always prints the first section, the others only when there's something in
+            // them
+            if kind == SectionKind::Unscoped
+                || !resolvers.is_empty()
+                || self.section_metadata(kind.header()).is_some()
+            {
+                write_section(kind.header(), resolvers)?;
+            }
+        }
+        for (header, resolvers) in &self.other_sections {
+            write_section(header, resolvers)?;
+        }
+        Ok(())
+    }
+}
+
+/// A summary of a [DNSConfig] for reading in a terminal, see [DNSConfig::summary]
+pub struct ConfigSummary<'a>(&'a DNSConfig);
+
+impl Display for ConfigSummary<'_> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        let sections = [
+            ("DNS configuration", &self.0.dns_config),
+            ("Scoped", &self.0.scoped_dns_config),
+            ("Service-specific", &self.0.service_specific_dns_config),
+        ];
+        let other_sections = self
+            .0
+            .other_sections
+            .iter()
+            .map(|(header, resolvers)| (header.as_str(), resolvers));
+        for (title, section) in sections.into_iter().chain(other_sections) {
+            if section.is_empty() {
+                continue;
+            }
+            writeln!(f, "{}", title)?;
+            for resolver in section {
+                let nameservers: Vec<String> = resolver
+                    .nameservers
+                    .iter()
+                    .map(|nameserver| nameserver.to_string())
+                    .collect();
+                let flags: Vec<String> = resolver
+                    .flags
+                    .iter()
+                    .flatten()
+                    .map(|flag| flag.to_string())
+                    .collect();
+                let or_dash = |value: String| {
+                    if value.is_empty() {
+                        "-".to_string()
+                    } else {
+                        value
+                    }
+                };
+
+                writeln!(f, "  resolver #{}", resolver.id)?;
+                if let Some(domain) = &resolver.domain {
+                    writeln!(f, "    {:<14} {}", "domain", domain)?;
+                }
+                writeln!(
+                    f,
+                    "    {:<14} {}",
+                    "nameservers",
+                    or_dash(nameservers.join("  "))
+                )?;
+                writeln!(
+                    f,
+                    "    {:<14} {}",
+                    "search domains",
+                    or_dash(resolver.search_domain_names().join("  "))
+                )?;
+                writeln!(f, "    {:<14} {}", "flags", or_dash(flags.join(", ")))?;
+                writeln!(
+                    f,
+                    "    {:<14} {}",
+                    "order",
+                    or_dash(
+                        resolver
+                            .order
+                            .map(|order| order.to_string())
+                            .unwrap_or_default()
+                    )
+                )?;
+                writeln!(
+                    f,
+                    "    {:<14} {}",
+                    "interface",
+                    or_dash(
+                        resolver
+                            .if_index
+                            .as_ref()
+                            .map(|if_index| if_index.to_string())
+                            .unwrap_or_default()
+                    )
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl DNSConfig {
+    /// Starts building a config, see [DNSConfigBuilder]
+    pub fn builder() -> DNSConfigBuilder {
+        DNSConfigBuilder::new()
+    }
+
+    /// Renders the config back into `scutil --dns` text, see the [Display] implementation
+    pub fn to_scutil_string(&self) -> String {
+        self.to_string()
+    }
+
+    /// The JSON Schema for a serialized [DNSConfig], eg for validating output collected from
+    /// lots of machines
+    #[cfg(feature = "schemars")]
+    pub fn schema() -> schemars::Schema {
+        schemars::schema_for!(DNSConfig)
+    }
+
+    /// A summary for people rather than parsers, with the nameservers, search domains, flags,
+    /// order and interface of each resolver on aligned lines. Use [DNSConfig::to_scutil_string]
+    /// for something that can be parsed again.
+    pub fn summary(&self) -> ConfigSummary<'_> {
+        ConfigSummary(self)
+    }
+
+    /// The resolvers in one section
+    pub fn section(&self, kind: SectionKind) -> &[Resolver] {
+        match kind {
+            SectionKind::Unscoped => &self.dns_config,
+            SectionKind::Scoped => &self.scoped_dns_config,
+            SectionKind::ServiceSpecific => &self.service_specific_dns_config,
+        }
+    }
+
+    /// Every section with its resolvers, in the order scutil prints them
+    pub fn sections(&self) -> impl Iterator<Item = (SectionKind, &[Resolver])> {
+        [
+            SectionKind::Unscoped,
+            SectionKind::Scoped,
+            SectionKind::ServiceSpecific,
+        ]
+        .into_iter()
+        .map(move |kind| (kind, self.section(kind)))
+    }
+
+    /// The resolvers in a section [SectionKind] doesn't cover, by its header line
+    pub fn other_section(&self, header: &str) -> Option<&[Resolver]> {
+        self.other_sections.get(header.trim()).map(Vec::as_slice)
+    }
+
+    /// The `key : value` lines between a section's header and its first resolver, eg
+    /// `config.section_metadata(SectionKind::Unscoped.header())`
+    pub fn section_metadata(&self, header: &str) -> Option<&BTreeMap<String, String>> {
+        self.metadata.get(header.trim())
+    }
+
+    /// How many resolvers there are in all the sections, including [DNSConfig::other_sections]
+    pub fn resolver_count(&self) -> usize {
+        self.sections()
+            .map(|(_, resolvers)| resolvers.len())
+            .sum::<usize>()
+            + self.other_sections.values().map(Vec::len).sum::<usize>()
+    }
+
+    /// The nameservers the system uses by default, in index order, from [DNSConfig::default_resolver]
+    pub fn system_nameservers(&self) -> Vec<IpAddr> {
+        self.default_resolver()
+            .map(Resolver::nameserver_addresses)
+            .unwrap_or_default()
+    }
+
+    /// Every nameserver address in every section, each one once in the order they're first seen
+    pub fn all_nameservers(&self) -> Vec<IpAddr> {
+        self.all_nameservers_with_sources()
+            .into_iter()
+            .map(|(address, _)| address)
+            .collect()
+    }
+
+    /// Whether any resolver in any section uses `address` as a nameserver
+    pub fn uses_resolver(&self, address: IpAddr) -> bool {
+        self.all_nameservers().contains(&address)
+    }
+
+    /// The nameservers that are well-known public resolvers from [PUBLIC_RESOLVERS], eg for
+    /// checking a managed machine isn't using them
+    pub fn public_resolvers(&self) -> Vec<IpAddr> {
+        self.public_resolvers_from(PUBLIC_RESOLVERS)
+    }
+
+    /// Like [DNSConfig::public_resolvers], checking against `known` instead of the built-in list
+    pub fn public_resolvers_from(&self, known: &[IpAddr]) -> Vec<IpAddr> {
+        self.all_nameservers()
+            .into_iter()
+            .filter(|address| known.contains(address))
+            .collect()
+    }
+
+    /// Like [DNSConfig::all_nameservers], with the resolvers that use each address, eg the
+    /// default resolver and its scoped copy
+    pub fn all_nameservers_with_sources(&self) -> Vec<(IpAddr, Vec<NameserverSource<'_>>)> {
+        let mut nameservers: Vec<(IpAddr, Vec<NameserverSource>)> = Vec::new();
+        for (section, resolvers) in self.headed_sections() {
+            for resolver in resolvers {
+                for nameserver in &resolver.nameservers {
+                    let address = nameserver.address.address;
+                    let source = NameserverSource { section, resolver };
+                    match nameservers.iter_mut().find(|(seen, _)| *seen == address) {
+                        // a resolver can list the same address twice, eg with different ports
+                        Some((_, sources)) if sources.contains(&source) => {}
+                        Some((_, sources)) => sources.push(source),
+                        None => nameservers.push((address, vec![source])),
+                    }
+                }
+            }
+        }
+        nameservers
+    }
+
+    /// The resolvers in every section that list the same address more than once, with the
+    /// addresses, see [Resolver::duplicate_nameservers]
+    pub fn duplicate_nameservers(&self) -> Vec<(NameserverSource<'_>, Vec<IpAddr>)> {
+        self.headed_sections()
+            .flat_map(|(section, resolvers)| {
+                resolvers.iter().filter_map(move |resolver| {
+                    let duplicates = resolver.duplicate_nameservers();
+                    (!duplicates.is_empty())
+                        .then_some((NameserverSource { section, resolver }, duplicates))
+                })
+            })
+            .collect()
+    }
+
+    /// Every section with its header, including [DNSConfig::other_sections]
+    fn headed_sections(&self) -> impl Iterator<Item = (&str, &[Resolver])> {
+        self.sections()
+            .map(|(kind, resolvers)| (kind.header(), resolvers))
+            .chain(
+                self.other_sections
+                    .iter()
+                    .map(|(header, resolvers)| (header.as_str(), resolvers.as_slice())),
+            )
+    }
+
+    /// The unscoped resolver printed as `resolver #id`.
+    ///
+    /// Strict parsing rejects an index that's used twice in a section, if a lenient parse kept
+    /// both this returns the first one.
+    pub fn resolver(&self, id: usize) -> Option<&Resolver> {
+        self.dns_config.iter().find(|resolver| resolver.id == id)
+    }
+
+    /// The scoped resolver printed as `resolver #id`, see [DNSConfig::resolver]
+    pub fn scoped_resolver(&self, id: usize) -> Option<&Resolver> {
+        self.scoped_dns_config
+            .iter()
+            .find(|resolver| resolver.id == id)
+    }
+
+    /// The resolvers from every section that were pushed by a configuration profile, ie the ones
+    /// with a `config id`
+    pub fn managed_resolvers(&self) -> Vec<&Resolver> {
+        self.dns_config
+            .iter()
+            .chain(self.scoped_dns_config.iter())
+            .chain(self.service_specific_dns_config.iter())
+            .chain(self.other_sections.values().flatten())
+            .filter(|resolver| resolver.config_id.is_some())
+            .collect()
+    }
+
+    /// The resolver that would be used to look up `host`, eg `db.corp.example.com`.
+    ///
+    /// Like macOS, this picks the unscoped resolver with the longest `domain` that `host` is in,
+    /// using `order` to break ties, and falls back to the default resolver. Search domains only
+    /// change how short names are expanded, not where queries go, so they aren't considered.
+    pub fn resolver_for_domain(&self, host: &str) -> Option<&Resolver> {
+        self.resolvers_for_domain(host).into_iter().next()
+    }
+
+    /// Every resolver [DNSConfig::resolver_for_domain] could have picked for `host`, best first,
+    /// for working out why it picked the one it did.
+    ///
+    /// The resolvers whose `domain` matches come first, longest domain then lowest `order`,
+    /// followed by the default resolver if it isn't already in the list.
+    pub fn resolvers_for_domain(&self, host: &str) -> Vec<&Resolver> {
+        let host = host.trim_end_matches('.').to_ascii_lowercase();
+        let mut matching: Vec<(usize, &Resolver)> = self
+            .dns_config
+            .iter()
+            .filter_map(|resolver| {
+                let domain = resolver.domain.as_deref()?.trim_end_matches('.');
+                let domain = domain.to_ascii_lowercase();
+                let matches = host == domain
+                    || host
+                        .strip_suffix(&domain)
+                        .is_some_and(|prefix| prefix.ends_with('.'));
+                matches.then_some((domain.len(), resolver))
+            })
+            .collect();
+        // stable, so ties stay in the order they were printed
+        matching.sort_by_key(|(length, resolver)| {
+            (
+                std::cmp::Reverse(*length),
+                resolver.order.is_none(),
+                resolver.order,
+            )
+        });
+
+        let mut resolvers: Vec<&Resolver> =
+            matching.into_iter().map(|(_, resolver)| resolver).collect();
+        if let Some(default) = self.default_resolver() {
+            if !resolvers
+                .iter()
+                .any(|resolver| std::ptr::eq(*resolver, default))
+            {
+                resolvers.push(default);
+            }
+        }
+        resolvers
+    }
+
+    /// Sorts the resolvers in each section by `order`, ie the order they're queried in.
+    ///
+    /// Resolvers without an `order` go last, and ones with the same `order` stay in the order
+    /// they were in. The `id`s aren't changed, so they still match the original output.
+    pub fn sort_by_order(&mut self) {
+        for section in self.sections_mut() {
+            section.sort_by_key(|resolver| (resolver.order.is_none(), resolver.order));
+        }
+    }
+
+    /// Puts everything in a fixed order so two captures of the same configuration serialize the
+    /// same way, for comparing them with `diff` or keeping them in git.
+    ///
+    /// The resolvers in each section are sorted by `id`, and their nameservers and search domains
+    /// by index. [Resolver::raw_lines] are dropped. Use [DNSConfig::strip_reach] as well to leave
+    /// out the reachability, which changes with the network.
+    pub fn normalize(&mut self) {
+        for section in self.sections_mut() {
+            section.sort_by_key(|resolver| resolver.id);
+            for resolver in section.iter_mut() {
+                resolver
+                    .nameservers
+                    .sort_by_key(|nameserver| nameserver.index);
+                resolver
+                    .search_domains
+                    .sort_by_key(|search_domain| search_domain.index);
+                resolver.raw_lines = None;
+            }
+        }
+    }
+
+    /// Removes the `reach` of every resolver
+    pub fn strip_reach(&mut self) {
+        for section in self.sections_mut() {
+            for resolver in section.iter_mut() {
+                resolver.reach = None;
+            }
+        }
+    }
+
+    /// Every section, including [DNSConfig::other_sections]
+    fn sections_mut(&mut self) -> impl Iterator<Item = &mut Vec<Resolver>> {
+        [
+            &mut self.dns_config,
+            &mut self.scoped_dns_config,
+            &mut self.service_specific_dns_config,
+        ]
+        .into_iter()
+        .chain(self.other_sections.values_mut())
+    }
+
+    /// The resolver the system uses for names that don't match a more specific `domain`.
+    ///
+    /// This is the unscoped resolver with no `domain`, picking the one with the lowest `order` if
+    /// there are several (resolvers without an `order` sort last, and ties go to the first one in
+    /// the output, usually resolver #1).
+    pub fn default_resolver(&self) -> Option<&Resolver> {
+        self.dns_config
+            .iter()
+            .filter(|resolver| resolver.domain.is_none())
+            .min_by_key(|resolver| (resolver.order.is_none(), resolver.order))
+    }
+
+    /// The view of the config for queries on the interface called `name`, eg `en0`: its scoped
+    /// resolvers overlaid on the unscoped ones, see [EffectiveConfig]
+    pub fn effective_for_interface(&self, name: &str) -> EffectiveConfig<'_> {
+        self.effective_for(|if_index| if_index.interface.as_deref() == Some(name))
+    }
+
+    /// Like [DNSConfig::effective_for_interface], matching the interface by its index, which
+    /// works even when scutil couldn't print the name
+    pub fn effective_for_interface_index(&self, index: usize) -> EffectiveConfig<'_> {
+        self.effective_for(|if_index| if_index.index == index)
+    }
+
+    fn effective_for(&self, matches: impl Fn(&InterfaceIndex) -> bool) -> EffectiveConfig<'_> {
+        let scoped: Vec<&Resolver> = self
+            .scoped_dns_config
+            .iter()
+            .filter(|resolver| resolver.if_index.as_ref().is_some_and(&matches))
+            .collect();
+
+        let (mut resolvers, mut defaults): (Vec<&Resolver>, Vec<&Resolver>) = self
+            .dns_config
+            .iter()
+            .partition(|resolver| resolver.domain.is_some());
+        // stable, so ties stay in the order they were printed
+        resolvers.sort_by_key(|resolver| (resolver.order.is_none(), resolver.order));
+        if scoped.is_empty() {
+            defaults.sort_by_key(|resolver| (resolver.order.is_none(), resolver.order));
+            resolvers.extend(defaults);
+        } else {
+            resolvers.extend(scoped.iter().copied());
+        }
+
+        EffectiveConfig { scoped, resolvers }
+    }
+
+    /// Runs `scutil --dns` and parses the output strictly
+    #[cfg(feature = "system")]
+    pub fn from_system() -> Result<Self, SystemError> {
+        Self::from_runner(&SystemScutil::default())
+    }
+
+    /// Like `DNSConfig::from_system`, with scutil run by `runner`
+    #[cfg(feature = "system")]
+    pub fn from_runner(runner: &dyn ScutilRunner) -> Result<Self, SystemError> {
+        Self::from_runner_with_options(runner, &ParseOptions::default())
+            .map(|outcome| outcome.config)
+    }
+
+    /// Runs `scutil --dns` with `runner` and parses the output, see [parse_text_with_options]
+    #[cfg(feature = "system")]
+    pub fn from_runner_with_options(
+        runner: &dyn ScutilRunner,
+        options: &ParseOptions,
+    ) -> Result<ParseOutcome, SystemError> {
+        Ok(parse_text_with_options(&runner.run_dns()?, options)?)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum ParserState {
+    DnsConfig,
+    ScopedDnsConfig,
+    ServiceSpecificDnsConfig,
+    /// A section with a header we don't know about, which is kept in [DNSConfig::other_sections]
+    OtherDnsConfig(String),
+    Resolver,
+    ScopedResolver,
+    ServiceSpecificResolver,
+    OtherResolver(String),
+    Idle,
+}
+
+impl ParserState {
+    /// The state for a section header line, if it is one
+    fn from_header(line: &str) -> Option<Self> {
+        let header = section_header(line)?;
+        Some(match header {
+            "DNS configuration" => ParserState::DnsConfig,
+            "DNS configuration (for scoped queries)" => ParserState::ScopedDnsConfig,
+            "DNS configuration (for service-specific queries)" => {
+                ParserState::ServiceSpecificDnsConfig
+            }
+            header => ParserState::OtherDnsConfig(header.to_string()),
+        })
+    }
+
+    /// The state for a resolver block in this section, `None` if this isn't a section
+    fn resolver(&self) -> Option<Self> {
+        match self {
+            ParserState::DnsConfig => Some(ParserState::Resolver),
+            ParserState::ScopedDnsConfig => Some(ParserState::ScopedResolver),
+            ParserState::ServiceSpecificDnsConfig => Some(ParserState::ServiceSpecificResolver),
+            ParserState::OtherDnsConfig(header) => Some(ParserState::OtherResolver(header.clone())),
+            _ => None,
+        }
+    }
+
+    /// The header line of this section, `None` if this isn't a section
+    fn header(&self) -> Option<&str> {
+        match self {
+            ParserState::DnsConfig => Some(SectionKind::Unscoped.header()),
+            ParserState::ScopedDnsConfig => Some(SectionKind::Scoped.header()),
+            ParserState::ServiceSpecificDnsConfig => Some(SectionKind::ServiceSpecific.header()),
+            ParserState::OtherDnsConfig(header) => Some(header),
+            _ => None,
+        }
+    }
+
+    fn is_resolver(&self) -> bool {
+        matches!(
+            self,
+            ParserState::Resolver
+                | ParserState::ScopedResolver
+                | ParserState::ServiceSpecificResolver
+                | ParserState::OtherResolver(_)
+        )
+    }
+}
+
+/// Controls how [parse_text_with_options] handles input it doesn't expect.
+///
+/// The defaults match [parse_text]. New options may be added, so build it with the methods:
+///
+/// ```
+/// use scutil_parser::dns::ParseOptions;
+///
+/// let options = ParseOptions::new().lenient(true).skip_mdns(true);
+/// assert!(options.lenient);
+/// ```
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct ParseOptions {
+    /// When a resolver block can't be parsed, leave it out and record a [ParseWarning], then skip
+    /// ahead to the next block instead of returning an error. Unexpected lines outside resolver
+    /// blocks are skipped the same way.
+    pub recover: bool,
+    /// Store `None` with a [ParseWarning] for values which can't be parsed, keep unknown
+    /// `key : value` lines in [Resolver::extra], keep resolvers whose index was already used in
+    /// the section and skip other unexpected lines, instead of returning an error
+    pub lenient: bool,
+    /// Leave multicast DNS resolvers out of the result, see [Resolver::is_mdns]
+    pub skip_mdns: bool,
+    /// Return an error for flags we don't know about, instead of keeping them as
+    /// [ResolverFlags::Unknown]
+    pub reject_unknown_flags: bool,
+    /// Keep the text of each resolver block in [Resolver::raw_lines], for comparing what was
+    /// parsed with where it came from
+    pub keep_raw_lines: bool,
+}
+
+impl ParseOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// See [ParseOptions::recover]
+    pub fn recover(mut self, recover: bool) -> Self {
+        self.recover = recover;
+        self
+    }
+
+    /// See [ParseOptions::lenient]
+    pub fn lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
+    }
+
+    /// See [ParseOptions::skip_mdns]
+    pub fn skip_mdns(mut self, skip_mdns: bool) -> Self {
+        self.skip_mdns = skip_mdns;
+        self
+    }
+
+    /// See [ParseOptions::reject_unknown_flags]
+    pub fn reject_unknown_flags(mut self, reject_unknown_flags: bool) -> Self {
+        self.reject_unknown_flags = reject_unknown_flags;
+        self
+    }
+
+    /// See [ParseOptions::keep_raw_lines]
+    pub fn keep_raw_lines(mut self, keep_raw_lines: bool) -> Self {
+        self.keep_raw_lines = keep_raw_lines;
+        self
+    }
+}
+
+/// What sort of problem a [ParseWarning] is about
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub enum WarningCategory {
+    /// A line that was skipped, see [ParseOptions::recover] and [ParseOptions::lenient]
+    UnexpectedLine,
+    /// A `key : value` line we don't know about, which is kept in [Resolver::extra]
+    UnknownField,
+    /// A value that couldn't be parsed and was left out, see [ParseOptions::lenient]
+    InvalidValue,
+    /// A flag we don't know about, which is kept as [ResolverFlags::Unknown]
+    UnknownFlag,
+    /// The same `resolver #N` turned up twice in one section, see [ParseError::DuplicateResolver]
+    DuplicateResolver,
+    /// A resolver with no nameservers, which isn't an mdns resolver
+    NoNameservers,
+    /// A resolver block that couldn't be parsed and was left out, see [ParseOptions::recover]
+    SkippedResolver,
+    /// A field that should only appear once in a resolver turned up again, the last value is
+    /// kept
+    DuplicateField,
+}
+
+/// Something that didn't stop the parse, but that the caller might want to know about
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct ParseWarning {
+    /// 1-based line number
+    pub line_no: usize,
+    pub content: String,
+    pub category: WarningCategory,
+    pub message: String,
+}
+
+impl Display for ParseWarning {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        write!(
+            f,
+            "line {}: {}: {:?}",
+            self.line_no, self.message, self.content
+        )
+    }
+}
+
+/// The result of [parse_text_with_options]
+#[derive(Debug)]
+pub struct ParseOutcome {
+    pub config: DNSConfig,
+    pub warnings: Vec<ParseWarning>,
+}
+
+/// Parses the output of `scutil --dns`, failing on anything unexpected
+pub fn parse_text(input: &str) -> Result<DNSConfig, ParseError> {
+    parse_text_with_options(input, &ParseOptions::default()).map(|outcome| outcome.config)
+}
+
+/// Parses the output of `scutil --dns` on a best-effort basis, see [ParseOptions::lenient]
+pub fn parse_text_lenient(input: &str) -> Result<DNSConfig, ParseError> {
+    parse_text_with_options(input, &ParseOptions::new().lenient(true)).map(|outcome| outcome.config)
+}
+
+/// Parses the output of `scutil --dns` on a best-effort basis like [parse_text_lenient], also
+/// returning a [ParseWarning] for each line that wasn't fully understood
+pub fn parse_text_with_warnings(input: &str) -> Result<(DNSConfig, Vec<ParseWarning>), ParseError> {
+    parse_text_with_options(input, &ParseOptions::new().lenient(true))
+        .map(|outcome| (outcome.config, outcome.warnings))
+}
+
+/// Strips the UTF-8 byte order mark that some Windows tools put at the start of a file
+pub(crate) fn strip_bom(input: &str) -> &str {
+    input.strip_prefix('\u{feff}').unwrap_or(input)
+}
+
+/// Splits a port off a nameserver address, as in `10.0.0.1:5353` or `[fe80::1%en0]:5353`
+pub(crate) fn split_nameserver_port(value: &str) -> (&str, Option<&str>) {
+    if let Some(bracketed) = value.strip_prefix('[') {
+        if let Some((address, port)) = bracketed.split_once("]:") {
+            return (address, Some(port));
+        }
+        return (bracketed.strip_suffix(']').unwrap_or(bracketed), None);
+    }
+    // more than one colon means it's an IPv6 address without a port
+    match value.split_once(':') {
+        Some((address, port)) if !port.contains(':') => (address, Some(port)),
+        _ => (value, None),
+    }
+}
+
+/// The header if the line starts a section, like `DNS configuration (for scoped queries)`.
+///
+/// Apple adds sections now and then, so anything that mentions `configuration` and isn't a
+/// `key : value` or `resolver #N` line counts.
+pub(crate) fn section_header(line: &str) -> Option<&str> {
+    let header = line.trim();
+    let is_header =
+        header.contains("configuration") && !header.contains(':') && !is_resolver_header(header);
+    is_header.then_some(header)
+}
+
+/// Whether the line starts a resolver block, like `resolver #1`, rather than being a
+/// `key : value` line that happens to start with "resolver"
+pub(crate) fn is_resolver_header(line: &str) -> bool {
+    let Some(rest) = line.trim().strip_prefix("resolver") else {
+        return false;
+    };
+    rest.trim_start().starts_with('#') || !rest.contains(':')
+}
+
+/// Splits a `resolver #N` line into the index and anything after it, eg `(Default)`
+pub(crate) fn split_resolver_header(
+    line: &str,
+    line_no: usize,
+) -> Result<(usize, Option<&str>), ParseError> {
+    let header = RESOLVER_HEADER_PARSER
+        .captures(line.trim())
+        .ok_or_else(|| ParseError::MissingField {
+            line_no,
+            content: line.to_string(),
+            field: "resolver index",
+        })?;
+    let index = header["index"]
+        .parse()
+        .map_err(|source| ParseError::InvalidInteger {
+            line_no,
+            content: line.to_string(),
+            source,
+        })?;
+    let annotation = header
+        .name("annotation")
+        .map(|annotation| annotation.as_str());
+    Ok((index, annotation))
+}
+
+/// Returns the value from a `key : value` line, splitting on the first colon so values can
+/// contain colons of their own
+pub(crate) fn field_value(line: &str) -> &str {
+    line.trim()
+        .split_once(':')
+        .map(|(_, value)| value)
+        .unwrap_or_default()
+        .trim()
+}
+
+/// Keys that have an index, like `nameserver[0]`
+const INDEXED_FIELDS: [&str; 3] = ["nameserver", "search domain", "sortaddr"];
+
+/// Returns the key from a `key : value` line, without the index of keys like `nameserver[0]`.
+///
+/// Keys are compared whole, so one that Apple adds later like `ordering` isn't mistaken for
+/// `order`.
+pub(crate) fn field_key(line: &str) -> Option<&str> {
+    let (key, _) = line.trim().split_once(':')?;
+    let key = key.trim_end();
+    // the index itself is checked when the line's parsed
+    match key.split_once('[') {
+        Some((name, index)) if index.ends_with(']') && INDEXED_FIELDS.contains(&name) => Some(name),
+        _ => Some(key),
+    }
+}
+
+/// Parses the integer value from a `key : value` line, in lenient mode an invalid value is stored
+/// as `None` with a warning
+fn parse_integer_field(
+    line: &str,
+    line_no: usize,
+    options: &ParseOptions,
+    warnings: &mut Vec<ParseWarning>,
+) -> Result<Option<usize>, ParseError> {
+    match field_value(line).parse::<usize>() {
+        Ok(value) => Ok(Some(value)),
+        Err(source) if options.lenient => {
+            warnings.push(ParseWarning {
+                line_no,
+                content: line.to_string(),
+                category: WarningCategory::InvalidValue,
+                message: format!("invalid integer, ignoring it: {}", source),
+            });
+            Ok(None)
+        }
+        Err(source) => Err(ParseError::InvalidInteger {
+            line_no,
+            content: line.to_string(),
+            source,
+        }),
+    }
+}
+
+/// Returns `Ok` if we're recovering from errors, recording the error as a warning. If we were in
+/// a resolver block it's abandoned, and the caller should skip to the next block.
+fn recover_or_fail(
+    options: &ParseOptions,
+    warnings: &mut Vec<ParseWarning>,
+    state: &mut ParserState,
+    parent_state: &ParserState,
+    current_resolver: &mut Resolver,
+    err: ParseError,
+) -> Result<(), ParseError> {
+    if !options.recover {
+        return Err(err);
+    }
+    let (category, message) = match state {
+        state if state.is_resolver() => {
+            let abandoned = std::mem::take(current_resolver);
+            *state = parent_state.clone();
+            (
+                WarningCategory::SkippedResolver,
+                format!("{}, skipping resolver #{}", err.reason(), abandoned.id),
+            )
+        }
+        _ => match err {
+            ParseError::UnexpectedLine { .. } => (
+                WarningCategory::UnexpectedLine,
+                "unexpected line, skipping to the next block".to_string(),
+            ),
+            _ => (
+                WarningCategory::SkippedResolver,
+                format!("{}, skipping to the next block", err.reason()),
+            ),
+        },
+    };
+    warnings.push(ParseWarning {
+        line_no: err.line_no(),
+        content: err.content().to_string(),
+        category,
+        message,
+    });
+    Ok(())
+}
+
+/// If we're in a resolver block, adds the resolver to the section it belongs to and goes back
+/// to the section's state
+fn finish_resolver(
+    dns_config: &mut DNSConfig,
+    state: &mut ParserState,
+    parent_state: &ParserState,
+    current_resolver: &mut Resolver,
+    options: &ParseOptions,
+    warnings: &mut Vec<ParseWarning>,
+    resolver_header: &(usize, String),
+) {
+    let section = match state {
+        ParserState::Resolver => &mut dns_config.dns_config,
+        ParserState::ScopedResolver => &mut dns_config.scoped_dns_config,
+        ParserState::ServiceSpecificResolver => &mut dns_config.service_specific_dns_config,
+        ParserState::OtherResolver(header) => {
+            dns_config.other_sections.entry(header.clone()).or_default()
+        }
+        _ => return,
+    };
+    log::debug!(
+        "Finished {:?} index {} - {:?}",
+        state,
+        current_resolver.id,
+        current_resolver
+    );
+    let resolver = std::mem::take(current_resolver);
+    let (line_no, content) = resolver_header;
+    if !resolver.has_nameservers() && !resolver.is_mdns() {
+        warnings.push(ParseWarning {
+            line_no: *line_no,
+            content: content.clone(),
+            category: WarningCategory::NoNameservers,
+            message: format!(
+                "resolver #{} has no nameservers and isn't mdns",
+                resolver.id
+            ),
+        });
+    }
+    if !(options.skip_mdns && resolver.is_mdns()) {
+        section.push(resolver);
+    }
+    *state = parent_state.clone();
+}
+
+pub fn parse_text_with_options(
+    input: &str,
+    options: &ParseOptions,
+) -> Result<ParseOutcome, ParseError> {
+    parse_lines(input.lines().map(Ok), options)
+}
+
+/// Parses the output of `scutil --dns` as it's read, failing on anything unexpected
+pub fn parse_reader<R: BufRead>(reader: R) -> Result<DNSConfig, ParseError> {
+    parse_reader_with_options(reader, &ParseOptions::default()).map(|outcome| outcome.config)
+}
+
+/// Parses the output of `scutil --dns` as it's read, see [parse_text_with_options]
+pub fn parse_reader_with_options<R: BufRead>(
+    reader: R,
+    options: &ParseOptions,
+) -> Result<ParseOutcome, ParseError> {
+    parse_lines(reader.lines(), options)
+}
+
+/// The state machine behind the `parse_*` functions, which only looks at one line at a time
+fn parse_lines<I, S>(lines: I, options: &ParseOptions) -> Result<ParseOutcome, ParseError>
+where
+    I: IntoIterator<Item = Result<S, io::Error>>,
+    S: AsRef<str>,
+{
+    let mut warnings: Vec<ParseWarning> = Vec::new();
+    // set when we're skipping lines after an unexpected one
+    let mut resyncing = false;
+
+    let mut dns_config = DNSConfig {
+        dns_config: Vec::new(),
+        scoped_dns_config: Vec::new(),
+        service_specific_dns_config: Vec::new(),
+        metadata: BTreeMap::new(),
+        other_sections: BTreeMap::new(),
+    };
+
+    let mut parent_state: ParserState = ParserState::Idle;
+    let mut state: ParserState = ParserState::Idle;
+    let mut current_resolver: Resolver = Resolver::new(0);
+    // the line number and text of the current resolver's `resolver #N` line
+    let mut resolver_header: (usize, String) = (0, String::new());
+    // where each resolver was first seen, keyed by the section's state and the resolver's index
+    let mut resolver_lines: HashMap<(ParserState, usize), usize> = HashMap::new();
+    // metadata lines are only expected before the first resolver in a section
+    let mut section_has_resolvers = false;
+
+    for (line_index, line) in lines.into_iter().enumerate() {
+        let line_no = line_index + 1;
+        let line = line.map_err(|source| ParseError::Io { line_no, source })?;
+        let line = line.as_ref();
+        let line = if line_index == 0 {
+            strip_bom(line)
+        } else {
+            line
+        };
+
+        log::trace!("Parsing line {}: '{}'", line_no, line);
+        if resyncing {
+            if line.trim().is_empty() || section_header(line).is_some() {
+                resyncing = false;
+            } else if is_resolver_header(line) {
+                // the block we were skipping didn't end with a blank line
+                resyncing = false;
+            } else {
+                continue;
+            }
+        }
+        if let Some(section) = ParserState::from_header(line) {
+            finish_resolver(
+                &mut dns_config,
+                &mut state,
+                &parent_state,
+                &mut current_resolver,
+                options,
+                &mut warnings,
+                &resolver_header,
+            );
+            log::debug!("Setting state to {:?}", section);
+            if let ParserState::OtherDnsConfig(header) = &section {
+                // keep the section even if it doesn't have any resolvers
+                dns_config.other_sections.entry(header.clone()).or_default();
+            }
+            state = section.clone();
+            parent_state = section;
+            section_has_resolvers = false;
+            continue;
+        }
+
+        if is_resolver_header(line) {
+            section_has_resolvers = true;
+            // a new block finishes the previous one, even without a blank line between them
+            finish_resolver(
+                &mut dns_config,
+                &mut state,
+                &parent_state,
+                &mut current_resolver,
+                options,
+                &mut warnings,
+                &resolver_header,
+            );
+
+            let (resolver_index, annotation) = match split_resolver_header(line, line_no) {
+                Ok(header) => header,
+                Err(err) => {
+                    recover_or_fail(
+                        options,
+                        &mut warnings,
+                        &mut state,
+                        &parent_state,
+                        &mut current_resolver,
+                        err,
+                    )?;
+                    resyncing = true;
+                    continue;
+                }
+            };
+            if let Some(&first_line_no) = resolver_lines.get(&(state.clone(), resolver_index)) {
+                let err = ParseError::DuplicateResolver {
+                    line_no,
+                    content: line.to_string(),
+                    id: resolver_index,
+                    first_line_no,
+                };
+                if options.lenient {
+                    warnings.push(ParseWarning {
+                        line_no,
+                        content: line.to_string(),
+                        category: WarningCategory::DuplicateResolver,
+                        message: err.reason(),
+                    });
+                } else {
+                    recover_or_fail(
+                        options,
+                        &mut warnings,
+                        &mut state,
+                        &parent_state,
+                        &mut current_resolver,
+                        err,
+                    )?;
+                    resyncing = true;
+                    continue;
+                }
+            } else {
+                resolver_lines.insert((state.clone(), resolver_index), line_no);
+            }
+            let Some(resolver_state) = state.resolver() else {
+                recover_or_fail(
+                    options,
+                    &mut warnings,
+                    &mut state,
+                    &parent_state,
+                    &mut current_resolver,
+                    ParseError::UnexpectedLine {
+                        line_no,
+                        content: line.to_string(),
+                    },
+                )?;
+                resyncing = true;
+                continue;
+            };
+            log::debug!("Starting new {:?} index {}", resolver_state, resolver_index);
+            state = resolver_state;
+            current_resolver = Resolver::new(resolver_index);
+            current_resolver.annotation = annotation.map(str::to_string);
+            if options.keep_raw_lines {
+                current_resolver.raw_lines = Some(vec![line.to_string()]);
+            }
+            resolver_header = (line_no, line.to_string());
+            continue;
+        }
+
+        let in_resolver = state.is_resolver();
+        if let (true, false, Some(raw_lines)) = (
+            in_resolver,
+            line.trim().is_empty(),
+            current_resolver.raw_lines.as_mut(),
+        ) {
+            raw_lines.push(line.to_string());
+        }
+        if let (false, Some(header), Some((key, value))) =
+            (in_resolver, state.header(), line.split_once(':'))
+        {
+            if !section_has_resolvers {
+                dns_config
+                    .metadata
+                    .entry(header.to_string())
+                    .or_default()
+                    .insert(key.trim().to_string(), value.trim().to_string());
+                continue;
+            }
+            // a field that's been split from its resolver by a blank line
+            let err = ParseError::UnexpectedLine {
+                line_no,
+                content: line.to_string(),
+            };
+            if options.lenient {
+                warnings.push(ParseWarning {
+                    line_no,
+                    content: line.to_string(),
+                    category: WarningCategory::UnexpectedLine,
+                    message: "field outside a resolver block, skipping it".to_string(),
+                });
+            } else {
+                recover_or_fail(
+                    options,
+                    &mut warnings,
+                    &mut state,
+                    &parent_state,
+                    &mut current_resolver,
+                    err,
+                )?;
+                resyncing = true;
+            }
+            continue;
+        }
+        let field = match in_resolver {
+            true => {
+                parse_resolver_field(line, line_no, &mut current_resolver, options, &mut warnings)
+            }
+            false => Ok(false),
+        };
+        match field {
+            Ok(true) => continue,
+            Ok(false) => {}
+            Err(err) => {
+                recover_or_fail(
+                    options,
+                    &mut warnings,
+                    &mut state,
+                    &parent_state,
+                    &mut current_resolver,
+                    err,
+                )?;
+                resyncing = true;
+                continue;
+            }
+        }
+        if line.trim() == "" {
+            match state {
+                ref state_now if state_now.is_resolver() => {
+                    finish_resolver(
+                        &mut dns_config,
+                        &mut state,
+                        &parent_state,
+                        &mut current_resolver,
+                        options,
+                        &mut warnings,
+                        &resolver_header,
+                    );
+                    continue;
+                }
+                ref state_now if state_now.resolver().is_some() => {}
+                _ => {
+                    recover_or_fail(
+                        options,
+                        &mut warnings,
+                        &mut state,
+                        &parent_state,
+                        &mut current_resolver,
+                        ParseError::UnexpectedLine {
+                            line_no,
+                            content: line.to_string(),
+                        },
+                    )?;
+                    resyncing = true;
+                    continue;
+                }
+            }
+        } else if options.lenient {
+            match (state.is_resolver(), line.split_once(':')) {
+                (true, Some((key, value))) => {
+                    log::debug!("Storing unknown field {:?}", key.trim());
+                    current_resolver
+                        .extra
+                        .insert(key.trim().to_string(), value.trim().to_string());
+                    warnings.push(ParseWarning {
+                        line_no,
+                        content: line.to_string(),
+                        category: WarningCategory::UnknownField,
+                        message: format!("unknown field {:?}, keeping it in extra", key.trim()),
+                    });
+                }
+                _ => warnings.push(ParseWarning {
+                    line_no,
+                    content: line.to_string(),
+                    category: WarningCategory::UnexpectedLine,
+                    message: "unexpected line, ignoring it".to_string(),
+                }),
+            }
+        } else {
+            recover_or_fail(
+                options,
+                &mut warnings,
+                &mut state,
+                &parent_state,
+                &mut current_resolver,
+                ParseError::UnexpectedLine {
+                    line_no,
+                    content: line.to_string(),
+                },
+            )?;
+            resyncing = true;
+        }
+    }
+    // the output doesn't always end with a blank line
+    finish_resolver(
+        &mut dns_config,
+        &mut state,
+        &parent_state,
+        &mut current_resolver,
+        options,
+        &mut warnings,
+        &resolver_header,
+    );
+
+    Ok(ParseOutcome {
+        config: dns_config,
+        warnings,
+    })
+}
+
+/// Handles a `key : value` line inside a resolver block, returning `false` if it isn't a field
+/// we know about
+fn parse_resolver_field(
+    line: &str,
+    line_no: usize,
+    resolver: &mut Resolver,
+    options: &ParseOptions,
+    warnings: &mut Vec<ParseWarning>,
+) -> Result<bool, ParseError> {
+    if field_key(line) == Some("nameserver") {
+        log::trace!("Handling nameserver");
+        let nameserver =
+            NAMESERVER_PARSER
+                .captures(line.trim())
+                .ok_or_else(|| ParseError::MissingField {
+                    line_no,
+                    content: line.to_string(),
+                    field: "nameserver index or address",
+                })?;
+        let ns_id: usize =
+            nameserver["ns_id"]
+                .parse()
+                .map_err(|source| ParseError::InvalidInteger {
+                    line_no,
+                    content: line.to_string(),
+                    source,
+                })?;
+        let (address, port) = split_nameserver_port(&nameserver["nameserver"]);
+        let address: NameserverAddress =
+            address
+                .parse()
+                .map_err(|source| ParseError::InvalidNameserver {
+                    line_no,
+                    content: line.to_string(),
+                    source,
+                })?;
+        let port: Option<u16> = port
+            .map(|port| {
+                port.parse().map_err(|source| ParseError::InvalidInteger {
+                    line_no,
+                    content: line.to_string(),
+                    source,
+                })
+            })
+            .transpose()?;
+        log::trace!("Adding nameserver {} - {} port {:?}", ns_id, address, port);
+        resolver.add_nameserver(Nameserver {
+            index: ns_id,
+            address,
+            port,
+        });
+    } else if field_key(line) == Some("search domain") {
+        let search_domain =
+            SEARCH_DOMAIN_PARSER
+                .captures(line.trim())
+                .ok_or_else(|| ParseError::MissingField {
+                    line_no,
+                    content: line.to_string(),
+                    field: "search domain",
+                })?;
+        // unindexed search domains go after everything we've seen so far
+        let index = match search_domain.name("index") {
+            Some(index) => index
+                .as_str()
+                .parse()
+                .map_err(|source| ParseError::InvalidInteger {
+                    line_no,
+                    content: line.to_string(),
+                    source,
+                })?,
+            None => resolver
+                .search_domains
+                .last()
+                .map_or(0, |last| last.index + 1),
+        };
+        let position = resolver
+            .search_domains
+            .partition_point(|search_domain| search_domain.index <= index);
+        log::trace!(
+            "Set search domain {} to {:?}",
+            index,
+            &search_domain["domain"]
+        );
+        resolver.search_domains.insert(
+            position,
+            SearchDomain {
+                index,
+                domain: search_domain["domain"].to_string(),
+            },
+        );
+    } else if field_key(line) == Some("if_index") {
+        resolver.if_index = Some(InterfaceIndex::from_str(line.trim()).map_err(|message| {
+            ParseError::InvalidValue {
+                line_no,
+                content: line.to_string(),
+                message,
+            }
+        })?);
+    } else if field_key(line) == Some("service_identifier") {
+        let service_identifier: u32 =
+            field_value(line)
+                .parse()
+                .map_err(|source| ParseError::InvalidInteger {
+                    line_no,
+                    content: line.to_string(),
+                    source,
+                })?;
+        resolver.service_identifier = Some(service_identifier);
+    } else if field_key(line) == Some("flags") {
+        let flags: Vec<ResolverFlags> = match flags_value(field_value(line)) {
+            Some(Ok(value)) => {
+                resolver.flags_value = Some(value);
+                ResolverFlags::from_value(value)
+            }
+            Some(Err(source)) if options.lenient => {
+                warnings.push(ParseWarning {
+                    line_no,
+                    content: line.to_string(),
+                    category: WarningCategory::InvalidValue,
+                    message: format!("invalid flags value, ignoring it: {}", source),
+                });
+                return Ok(true);
+            }
+            Some(Err(source)) => {
+                return Err(ParseError::InvalidInteger {
+                    line_no,
+                    content: line.to_string(),
+                    source,
+                })
+            }
+            // an empty line gives an empty list, which is different to there being no line
+            None => field_value(line)
+                .split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(ResolverFlags::from)
+                .collect(),
+        };
+        for flag in &flags {
+            let ResolverFlags::Unknown(flag) = flag else {
+                continue;
+            };
+            if options.reject_unknown_flags {
+                return Err(ParseError::InvalidValue {
+                    line_no,
+                    content: line.to_string(),
+                    message: format!("Unknown flag {:?}", flag),
+                });
+            }
+            warnings.push(ParseWarning {
+                line_no,
+                content: line.to_string(),
+                category: WarningCategory::UnknownFlag,
+                message: format!("unknown flag {:?}", flag),
+            });
+        }
+        resolver.flags = Some(flags);
+    } else if field_key(line) == Some("port") {
+        let port: u16 = field_value(line)
+            .parse()
+            .map_err(|source| ParseError::InvalidInteger {
+                line_no,
+                content: line.to_string(),
+                source,
+            })?;
+        log::trace!("Set port to {}", port);
+        resolver.port = Some(port);
+    } else if field_key(line) == Some("reach") {
+        let reach = field_value(line);
+        log::trace!("Set reach to {}", reach);
+        resolver.reach = Some(ReachabilityFlags::from_str(reach).map_err(|message| {
+            ParseError::InvalidValue {
+                line_no,
+                content: line.to_string(),
+                message,
+            }
+        })?);
+    } else if field_key(line) == Some("order") {
+        resolver.order = parse_integer_field(line, line_no, options, warnings)?;
+        log::trace!("Set order to {:?}", resolver.order);
+    } else if field_key(line) == Some("timeout") {
+        resolver.timeout = parse_integer_field(line, line_no, options, warnings)?
+            .map(|secs| Duration::from_secs(secs as u64));
+        log::trace!("Set timeout to {:?}", resolver.timeout);
+    } else if field_key(line) == Some("options") {
+        let options = field_value(line);
+        log::trace!("Set options to {}", options);
+        resolver.options = ResolverOptions::from_str(options).ok();
+    } else if matches!(field_key(line), Some("sortaddr" | "SortList")) {
+        // sortaddr[N] has one entry, SortList can have several like resolv.conf
+        for entry in field_value(line).split_whitespace() {
+            let entry =
+                SortListEntry::from_str(entry).map_err(|message| ParseError::InvalidValue {
+                    line_no,
+                    content: line.to_string(),
+                    message,
+                })?;
+            log::trace!("Adding sort list entry {}", entry);
+            resolver.sortlist.push(entry);
+        }
+    } else if field_key(line) == Some("config id") {
+        let config_id = field_value(line).to_string();
+        log::trace!("Set config id to {}", config_id);
+        resolver.config_id = Some(config_id);
+    } else if field_key(line) == Some("domain") {
+        let domain = field_value(line).to_string();
+        log::trace!("Set domain to {}", domain);
+        if let Some(previous) = &resolver.domain {
+            warnings.push(ParseWarning {
+                line_no,
+                content: line.to_string(),
+                category: WarningCategory::DuplicateField,
+                message: format!(
+                    "resolver #{} already has domain {}, replacing it",
+                    resolver.id, previous
+                ),
+            });
+        }
+        resolver.domain = Some(domain);
+    } else {
+        return Ok(false);
+    }
+    Ok(true)
 }
 
 lazy_static! {
-    static ref NAMESERVER_PARSER: Regex =
+    static ref RESOLVER_HEADER_PARSER: Regex =
+        Regex::new(r"^resolver\s*#(?P<index>\S+)(\s+(?P<annotation>\S.*))?$").unwrap();
+    pub(crate) static ref NAMESERVER_PARSER: Regex =
         Regex::new(r"nameserver\[(?P<ns_id>\d+)\]\s+:\s+(?P<nameserver>\S+)").unwrap();
+    pub(crate) static ref SEARCH_DOMAIN_PARSER: Regex =
+        Regex::new(r"^search\s+domain(\[(?P<index>\d+)\])?\s*:\s*(?P<domain>\S.*)$").unwrap();
 }
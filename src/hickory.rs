@@ -0,0 +1,146 @@
+//! Converting the parsed config into [hickory_resolver] config, behind the `hickory` feature, to
+//! do lookups with the same nameservers macOS is using.
+//!
+//! ```no_run
+//! use hickory_resolver::config::ResolverConfig;
+//!
+//! let output = std::fs::read_to_string("scutil-dns.txt").unwrap();
+//! let config = scutil_parser::dns::parse_text(&output).unwrap();
+//! let resolver_config = ResolverConfig::try_from(&config).unwrap();
+//! ```
+//!
+//! hickory doesn't have anywhere to put the zone of an IPv6 nameserver, so a nameserver like
+//! `fe80::1%en0` is rejected with [ConversionError::ScopedNameserver] rather than being turned
+//! into one that can't be reached.
+
+use std::str::FromStr;
+
+use hickory_resolver::config::{NameServerConfig, ResolverConfig, ResolverOpts};
+use hickory_resolver::proto::rr::Name;
+use hickory_resolver::proto::ProtoError;
+use thiserror::Error;
+
+use crate::dns::{DNSConfig, NameserverAddress, Resolver};
+
+/// Why the config couldn't be turned into a [ResolverConfig]
+#[derive(Debug, Error)]
+pub enum ConversionError {
+    /// There's no default resolver, or no resolvers were passed to [resolver_config]
+    #[error("there isn't a resolver to take the nameservers from")]
+    NoResolver,
+    /// None of the resolvers have any nameservers, eg because they're all mdns resolvers
+    #[error("resolver #{0} has no nameservers")]
+    NoNameservers(usize),
+    /// An IPv6 nameserver with a zone, see the module docs
+    #[error("nameserver {0} has a zone, which hickory can't use")]
+    ScopedNameserver(NameserverAddress),
+    #[error("search domain {domain:?} isn't a valid name: {source}")]
+    InvalidSearchDomain {
+        domain: String,
+        #[source]
+        source: ProtoError,
+    },
+}
+
+/// Uses the nameservers and search domains of [DNSConfig::default_resolver]
+impl TryFrom<&DNSConfig> for ResolverConfig {
+    type Error = ConversionError;
+
+    fn try_from(config: &DNSConfig) -> Result<Self, Self::Error> {
+        let resolver = config
+            .default_resolver()
+            .ok_or(ConversionError::NoResolver)?;
+        resolver_config(&[resolver])
+    }
+}
+
+impl TryFrom<&Resolver> for ResolverConfig {
+    type Error = ConversionError;
+
+    fn try_from(resolver: &Resolver) -> Result<Self, Self::Error> {
+        resolver_config(&[resolver])
+    }
+}
+
+/// Builds a [ResolverConfig] from several resolvers, eg from [DNSConfig::resolvers_for_domain] or
+/// [crate::dns::EffectiveConfig::resolvers].
+///
+/// The nameservers are taken from each resolver in turn, leaving out any that were already
+/// added, and the search domains come from the first resolver that has some. Each nameserver
+/// uses its own port, then the resolver's `port`, then 53.
+pub fn resolver_config(resolvers: &[&Resolver]) -> Result<ResolverConfig, ConversionError> {
+    let first = resolvers.first().ok_or(ConversionError::NoResolver)?;
+
+    let mut name_servers: Vec<NameServerConfig> = Vec::new();
+    for resolver in resolvers {
+        for nameserver in &resolver.nameservers {
+            if nameserver.address.scope.is_some() {
+                return Err(ConversionError::ScopedNameserver(
+                    nameserver.address.clone(),
+                ));
+            }
+            let ip = nameserver.address.address;
+            let port = nameserver.port.or(resolver.port).unwrap_or(53);
+            let duplicate = name_servers.iter().any(|name_server| {
+                name_server.ip == ip
+                    && name_server
+                        .connections
+                        .iter()
+                        .all(|connection| connection.port == port)
+            });
+            if duplicate {
+                continue;
+            }
+            let mut name_server = NameServerConfig::udp_and_tcp(ip);
+            for connection in &mut name_server.connections {
+                connection.port = port;
+            }
+            name_servers.push(name_server);
+        }
+    }
+    if name_servers.is_empty() {
+        return Err(ConversionError::NoNameservers(first.id));
+    }
+
+    let search = resolvers
+        .iter()
+        .map(|resolver| resolver.search_domain_names())
+        .find(|domains| !domains.is_empty())
+        .unwrap_or_default()
+        .into_iter()
+        .map(|domain| {
+            Name::from_str(domain).map_err(|source| ConversionError::InvalidSearchDomain {
+                domain: domain.to_string(),
+                source,
+            })
+        })
+        .collect::<Result<Vec<Name>, ConversionError>>()?;
+
+    Ok(ResolverConfig::from_parts(None, search, name_servers))
+}
+
+/// The resolver's `timeout`, and `ndots` and `attempts` from its options if it has them, with
+/// hickory's defaults for everything else. The search domains are part of the
+/// [ResolverConfig].
+impl From<&Resolver> for ResolverOpts {
+    fn from(resolver: &Resolver) -> Self {
+        let mut opts = ResolverOpts::default();
+        if let Some(timeout) = resolver.timeout {
+            opts.timeout = timeout;
+        }
+        let option = |key| {
+            resolver
+                .options
+                .as_ref()
+                .and_then(|options| options.value(key))
+                .and_then(|value| value.parse().ok())
+        };
+        if let Some(ndots) = option("ndots") {
+            opts.ndots = ndots;
+        }
+        if let Some(attempts) = option("attempts") {
+            opts.attempts = attempts;
+        }
+        opts
+    }
+}
@@ -0,0 +1,71 @@
+//! The three macOS host names, from `scutil --get ComputerName`, `LocalHostName` and `HostName`.
+//!
+//! `HostName` usually isn't set, in which case scutil prints `HostName: not set` and exits with a
+//! failure status. That's returned as `None` rather than an error.
+
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "system")]
+use crate::runner::{CommandFailed, ScutilRunner, SystemError, SystemScutil};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct Hostnames {
+    /// The name shown in the Sharing settings, eg `Jane's MacBook Pro`
+    pub computer_name: Option<String>,
+    /// The Bonjour name, without `.local`
+    pub local_host_name: Option<String>,
+    /// The name set with `scutil --set HostName`, if there is one
+    pub host_name: Option<String>,
+}
+
+impl Hostnames {
+    /// Runs `scutil --get` for each of the names
+    #[cfg(feature = "system")]
+    pub fn from_system() -> Result<Self, SystemError> {
+        Self::from_runner(&SystemScutil::default())
+    }
+
+    /// Like `Hostnames::from_system`, with scutil run by `runner`
+    #[cfg(feature = "system")]
+    pub fn from_runner(runner: &dyn ScutilRunner) -> Result<Self, SystemError> {
+        let get = |name: &str| match runner.run_get(name) {
+            Ok(output) => Ok(parse_name(&output)),
+            Err(err) if CommandFailed::from_io_error(&err).is_some_and(is_not_set) => Ok(None),
+            Err(err) => Err(SystemError::from(err)),
+        };
+        Ok(Self {
+            computer_name: get("ComputerName")?,
+            local_host_name: get("LocalHostName")?,
+            host_name: get("HostName")?,
+        })
+    }
+}
+
+/// Builds [Hostnames] from the output of `scutil --get ComputerName`, `LocalHostName` and
+/// `HostName`, in that order. Output saying the name is `not set`, or empty output, is `None`.
+pub fn parse_text(computer_name: &str, local_host_name: &str, host_name: &str) -> Hostnames {
+    Hostnames {
+        computer_name: parse_name(computer_name),
+        local_host_name: parse_name(local_host_name),
+        host_name: parse_name(host_name),
+    }
+}
+
+/// The name from the output of `scutil --get`, which is just the name and a newline
+fn parse_name(output: &str) -> Option<String> {
+    let name = output.trim_end_matches(['\r', '\n']);
+    if name.trim().is_empty() || name.ends_with(": not set") {
+        return None;
+    }
+    Some(name.to_string())
+}
+
+#[cfg(feature = "system")]
+fn is_not_set(failed: &CommandFailed) -> bool {
+    failed.stderr.ends_with("not set")
+}
@@ -1,4 +1,19 @@
+#[cfg(feature = "arbitrary")]
+mod arbitrary;
+pub mod borrowed;
+pub mod diff;
 pub mod dns;
+#[cfg(feature = "hickory")]
+pub mod hickory;
+pub mod hostnames;
+pub mod nc;
+pub mod nwi;
+pub mod proxy;
+pub mod reachability;
+pub mod resolvconf;
+pub mod runner;
+pub mod scstore;
+pub mod windows;
 
 #[cfg(test)]
 mod test;
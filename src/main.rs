@@ -1,35 +1,496 @@
-use scutil_parser::dns::parse_text;
+use std::path::PathBuf;
 
-use clap::{Parser, Subcommand};
+use scutil_parser::diff::{DnsConfigDiff, SectionDiff};
+use scutil_parser::dns::{parse_text, parse_text_with_options, DNSConfig, ParseOptions, Resolver};
+use scutil_parser::nc::{VpnService, VpnStatus};
+use scutil_parser::nwi::{NetworkInfo, NwiInterface};
+use scutil_parser::proxy::{ProxyConfig, ProxyServer};
+use scutil_parser::reachability::Reachability;
+use scutil_parser::runner::{ScutilRunner, SystemError, SystemScutil};
+use scutil_parser::scstore::StoreValue;
+
+use clap::{Parser, Subcommand, ValueEnum};
+use serde::Serialize;
 
 #[derive(Subcommand)]
 enum Commands {
     /// Runs 'scutil --dns' and parses the output
-    Dns,
+    Dns {
+        /// Read the output of 'scutil --dns' from stdin instead of running it
+        #[arg(long)]
+        stdin: bool,
+        /// Read a saved copy of the output of 'scutil --dns' instead of running it
+        #[arg(short, long, conflicts_with = "stdin")]
+        file: Option<PathBuf>,
+        /// Skip lines that can't be parsed with a warning, instead of failing
+        #[arg(long)]
+        lenient: bool,
+        /// Sort the resolvers, nameservers and search domains, so captures can be compared
+        #[arg(long)]
+        normalize: bool,
+        /// Leave out the reach of each resolver, which changes with the network
+        #[arg(long)]
+        strip_reach: bool,
+    },
+    /// Runs 'scutil --proxy' and parses the output
+    Proxy,
+    /// Runs 'scutil --nwi' and parses the output
+    Nwi,
+    /// Runs 'scutil --nc' and parses the output
+    Nc {
+        #[command(subcommand)]
+        command: NcCommands,
+    },
+    /// Runs 'scutil -r <host>' and parses the output
+    Reach {
+        /// The host name or address to check
+        host: String,
+    },
+    /// Compares two saved copies of the output of 'scutil --dns' and prints what changed
+    Diff {
+        /// The older output
+        old: PathBuf,
+        /// The newer output
+        new: PathBuf,
+    },
+    /// Parses saved Windows DNS client output, from 'netsh interface ip show dnsservers' or 'Get-DnsClientServerAddress | ConvertTo-Csv'
+    Windows {
+        /// The file containing the saved output
+        #[arg(short, long)]
+        input: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum NcCommands {
+    /// Runs 'scutil --nc list' and parses the output
+    List,
+    /// Runs 'scutil --nc status <service>' and parses the output
+    Status {
+        /// The name or service ID of the connection
+        service: String,
+    },
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    #[default]
+    Json,
+    Yaml,
+    /// A short summary for reading in a terminal
+    Text,
 }
 
 #[derive(Parser)]
+#[command(after_help = "Set SCUTIL_PATH to run a different scutil binary, eg a wrapper script")]
 struct CliOpts {
+    /// Don't print status messages to stderr, only the result on stdout
+    #[arg(short, long, global = true)]
+    quiet: bool,
+
+    /// How to print the result
+    #[arg(long, value_enum, global = true, default_value_t = OutputFormat::Json)]
+    format: OutputFormat,
+
+    /// Print JSON on a single line instead of pretty-printing it
+    #[arg(long, global = true)]
+    compact: bool,
+
     #[command(subcommand)]
     command: Commands,
 }
 
-fn rundns(_opts: CliOpts) {
-    println!("Running DNS");
-    // run scutil --dns and grab the result
-    let output = std::process::Command::new("scutil")
-        .arg("--dns")
-        .output()
-        .expect("failed to execute process");
-    let output_string: String = std::str::from_utf8(&output.stdout).unwrap().to_string();
-    let res = parse_text(&output_string).expect("Failed to parse result!");
-    println!("{}", serde_json::to_string_pretty(&res).unwrap());
+/// How a result is printed with `--format text`
+trait TextOutput {
+    fn print_text(&self);
+}
+
+impl TextOutput for DNSConfig {
+    fn print_text(&self) {
+        print!("{}", self.summary());
+    }
+}
+
+/// One line describing a resolver, leaving out anything that isn't set
+fn resolver_summary(resolver: &Resolver) -> String {
+    let mut parts = vec![format!("#{}", resolver.id)];
+    if let Some(domain) = &resolver.domain {
+        parts.push(format!("domain {}", domain));
+    }
+    if !resolver.nameservers.is_empty() {
+        let nameservers: Vec<String> = resolver
+            .nameservers
+            .iter()
+            .map(|ns| ns.to_string())
+            .collect();
+        parts.push(format!("nameservers {}", nameservers.join(" ")));
+    }
+    if !resolver.search_domains.is_empty() {
+        parts.push(format!(
+            "search {}",
+            resolver.search_domain_names().join(" ")
+        ));
+    }
+    if let Some(if_index) = &resolver.if_index {
+        parts.push(format!("if_index {}", if_index));
+    }
+    if let Some(order) = resolver.order {
+        parts.push(format!("order {}", order));
+    }
+    if resolver.is_mdns() {
+        parts.push("mdns".to_string());
+    }
+    parts.join(", ")
+}
+
+impl TextOutput for ProxyConfig {
+    fn print_text(&self) {
+        let server = |name: &str, server: &ProxyServer| {
+            let address = match (&server.host, server.port) {
+                (Some(host), Some(port)) => format!("{}:{}", host, port),
+                (Some(host), None) => host.clone(),
+                (None, _) => "-".to_string(),
+            };
+            let state = if server.enabled {
+                "enabled"
+            } else {
+                "disabled"
+            };
+            println!("{:<7}{} ({})", name, address, state);
+        };
+        server("HTTP", &self.http);
+        server("HTTPS", &self.https);
+        server("FTP", &self.ftp);
+        server("SOCKS", &self.socks);
+        if let Some(url) = &self.auto_config_url {
+            let state = if self.auto_config_enabled {
+                "enabled"
+            } else {
+                "disabled"
+            };
+            println!("PAC    {} ({})", url, state);
+        }
+        if !self.exceptions_list.is_empty() {
+            println!("Bypass {}", self.exceptions_list.join(" "));
+        }
+        for (interface, config) in &self.scoped {
+            println!("{}", interface);
+            config.print_text();
+        }
+    }
+}
+
+impl TextOutput for NetworkInfo {
+    fn print_text(&self) {
+        let interface = |interface: &NwiInterface| {
+            let addresses: Vec<String> = interface
+                .addresses
+                .iter()
+                .map(|address| address.to_string())
+                .collect();
+            let mut line = format!(
+                "  {:<7}{} ({})",
+                interface.name,
+                addresses.join(" "),
+                interface.flags.flags.join(",")
+            );
+            if let Some(vpn_server) = interface.vpn_server {
+                line.push_str(&format!(" via {}", vpn_server));
+            }
+            println!("{}", line);
+        };
+        for (title, interfaces) in [("IPv4", &self.ipv4), ("IPv6", &self.ipv6)] {
+            println!("{}", title);
+            if interfaces.is_empty() {
+                println!("  -");
+            }
+            interfaces.iter().for_each(interface);
+        }
+        println!("Interfaces {}", self.interfaces.join(" "));
+    }
+}
+
+impl TextOutput for Vec<VpnService> {
+    fn print_text(&self) {
+        for service in self {
+            let marker = if service.enabled { "*" } else { " " };
+            println!(
+                "{} {:<14}{:<7}{}",
+                marker,
+                service.state.to_string(),
+                service.interface_type,
+                service.name
+            );
+        }
+    }
+}
+
+impl TextOutput for VpnStatus {
+    fn print_text(&self) {
+        println!("{}", self.state);
+        print_store_value("", &self.extended);
+    }
+}
+
+/// Prints each string in the value on its own line, with the path to it
+fn print_store_value(path: &str, value: &StoreValue) {
+    let child = |key: &str| match path {
+        "" => key.to_string(),
+        path => format!("{}.{}", path, key),
+    };
+    match value {
+        StoreValue::String(value) => println!("  {} : {}", path, value),
+        StoreValue::Array(values) => {
+            for (index, value) in values.iter().enumerate() {
+                print_store_value(&child(&index.to_string()), value);
+            }
+        }
+        StoreValue::Dictionary(entries) => {
+            for (key, value) in entries {
+                print_store_value(&child(key), value);
+            }
+        }
+    }
+}
+
+impl TextOutput for Reachability {
+    fn print_text(&self) {
+        let state = if self.is_reachable() {
+            "reachable"
+        } else {
+            "not reachable"
+        };
+        match &self.host {
+            Some(host) => println!("{}: {} {}", host, state, self.reach),
+            None => println!("{} {}", state, self.reach),
+        }
+    }
+}
+
+impl TextOutput for DnsConfigDiff {
+    fn print_text(&self) {
+        for (title, section) in [
+            ("DNS configuration", &self.dns_config),
+            (
+                "DNS configuration (for scoped queries)",
+                &self.scoped_dns_config,
+            ),
+            (
+                "DNS configuration (for service-specific queries)",
+                &self.service_specific_dns_config,
+            ),
+        ] {
+            print_section_diff(title, section);
+        }
+        for (title, section) in &self.other_sections {
+            print_section_diff(title, section);
+        }
+    }
+}
+
+fn print_section_diff(title: &str, section: &SectionDiff) {
+    if section.is_empty() {
+        return;
+    }
+    println!("{}", title);
+    for resolver in &section.removed {
+        println!("- {}", resolver_summary(resolver));
+    }
+    for resolver in &section.added {
+        println!("+ {}", resolver_summary(resolver));
+    }
+    for resolver in &section.changed {
+        println!("~ #{} -> #{}", resolver.old_id, resolver.new_id);
+        for change in &resolver.changes {
+            println!(
+                "    {}: {} -> {}",
+                change.field,
+                change.old.as_deref().unwrap_or("-"),
+                change.new.as_deref().unwrap_or("-")
+            );
+        }
+    }
+}
+
+/// Prints the result to stdout in the format from `--format`
+fn print_output<T: Serialize + TextOutput>(opts: &CliOpts, value: &T) {
+    match opts.format {
+        OutputFormat::Json if opts.compact => println!("{}", serde_json::to_string(value).unwrap()),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(value).unwrap()),
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(value).unwrap()),
+        OutputFormat::Text => value.print_text(),
+    }
+}
+
+/// Reads a file, with the path in the error message
+fn read_file(path: &PathBuf) -> Result<String, String> {
+    std::fs::read_to_string(path)
+        .map_err(|err| format!("Failed to read {}: {}", path.display(), err))
+}
+
+fn rundns(
+    opts: &CliOpts,
+    runner: &dyn ScutilRunner,
+    stdin: bool,
+    file: Option<&PathBuf>,
+    lenient: bool,
+    normalize: bool,
+    strip_reach: bool,
+) -> Result<(), String> {
+    let options = ParseOptions::new().lenient(lenient);
+    let parse_error = |err| format!("Failed to parse DNS configuration: {}", err);
+    let mut res = if stdin {
+        let output_string = std::io::read_to_string(std::io::stdin())
+            .map_err(|err| format!("Failed to read stdin: {}", err))?;
+        parse_text_with_options(&output_string, &options).map_err(parse_error)?
+    } else if let Some(file) = file {
+        parse_text_with_options(&read_file(file)?, &options).map_err(parse_error)?
+    } else {
+        if !opts.quiet {
+            eprintln!("Running DNS");
+        }
+        DNSConfig::from_runner_with_options(runner, &options).map_err(|err| match err {
+            SystemError::Parse(err) => parse_error(err),
+            err => format!("Failed to run scutil: {}", err),
+        })?
+    };
+    if !opts.quiet {
+        for warning in &res.warnings {
+            eprintln!("Warning: {}", warning);
+        }
+    }
+    if normalize {
+        res.config.normalize();
+    }
+    if strip_reach {
+        res.config.strip_reach();
+    }
+    print_output(opts, &res.config);
+    Ok(())
+}
+
+fn runproxy(opts: &CliOpts, runner: &dyn ScutilRunner) -> Result<(), String> {
+    if !opts.quiet {
+        eprintln!("Running proxy");
+    }
+    let output_string = runner
+        .run_proxy()
+        .map_err(|err| format!("Failed to run scutil: {}", err))?;
+    let res = scutil_parser::proxy::parse_text(&output_string)
+        .map_err(|err| format!("Failed to parse proxy configuration: {}", err))?;
+    print_output(opts, &res);
+    Ok(())
+}
+
+fn runnwi(opts: &CliOpts, runner: &dyn ScutilRunner) -> Result<(), String> {
+    if !opts.quiet {
+        eprintln!("Running network information");
+    }
+    let output_string = runner
+        .run_nwi()
+        .map_err(|err| format!("Failed to run scutil: {}", err))?;
+    let res = scutil_parser::nwi::parse_text(&output_string)
+        .map_err(|err| format!("Failed to parse network information: {}", err))?;
+    print_output(opts, &res);
+    Ok(())
+}
+
+fn runnclist(opts: &CliOpts, runner: &dyn ScutilRunner) -> Result<(), String> {
+    if !opts.quiet {
+        eprintln!("Running network connection list");
+    }
+    let output_string = runner
+        .run_nc_list()
+        .map_err(|err| format!("Failed to run scutil: {}", err))?;
+    let res = scutil_parser::nc::parse_text(&output_string)
+        .map_err(|err| format!("Failed to parse network connections: {}", err))?;
+    print_output(opts, &res);
+    Ok(())
+}
+
+fn runncstatus(opts: &CliOpts, runner: &dyn ScutilRunner, service: &str) -> Result<(), String> {
+    if !opts.quiet {
+        eprintln!("Running network connection status for {}", service);
+    }
+    let output_string = runner
+        .run_nc_status(service)
+        .map_err(|err| format!("Failed to run scutil: {}", err))?;
+    let res = scutil_parser::nc::parse_status_text(&output_string)
+        .map_err(|err| format!("Failed to parse network connection status: {}", err))?;
+    print_output(opts, &res);
+    Ok(())
+}
+
+fn runreach(opts: &CliOpts, runner: &dyn ScutilRunner, host: &str) -> Result<(), String> {
+    if !opts.quiet {
+        eprintln!("Running reachability check for {}", host);
+    }
+    let output_string = runner
+        .run_reachability(host)
+        .map_err(|err| format!("Failed to run scutil: {}", err))?;
+    let mut res = scutil_parser::reachability::parse_text(&output_string)
+        .map_err(|err| format!("Failed to parse reachability: {}", err))?;
+    res.host = Some(host.to_string());
+    print_output(opts, &res);
+    Ok(())
+}
+
+fn rundiff(opts: &CliOpts, old: &PathBuf, new: &PathBuf) -> Result<(), String> {
+    let parse = |path: &PathBuf| {
+        parse_text(&read_file(path)?)
+            .map_err(|err| format!("Failed to parse {}: {}", path.display(), err))
+    };
+    let old = parse(old)?;
+    let new = parse(new)?;
+    print_output(opts, &old.diff(&new));
+    Ok(())
+}
+
+fn runwindows(opts: &CliOpts, input: &PathBuf) -> Result<(), String> {
+    let res = scutil_parser::windows::parse_text(&read_file(input)?)
+        .map_err(|err| format!("Failed to parse {}: {}", input.display(), err))?;
+    print_output(opts, &res);
+    Ok(())
 }
 
 fn main() {
     let opts = CliOpts::parse();
+    let runner = match std::env::var_os("SCUTIL_PATH") {
+        Some(program) if !program.is_empty() => SystemScutil::with_program(program),
+        _ => SystemScutil::default(),
+    };
 
-    match opts.command {
-        Commands::Dns => rundns(opts),
+    let res = match opts.command {
+        Commands::Dns {
+            stdin,
+            ref file,
+            lenient,
+            normalize,
+            strip_reach,
+        } => rundns(
+            &opts,
+            &runner,
+            stdin,
+            file.as_ref(),
+            lenient,
+            normalize,
+            strip_reach,
+        ),
+        Commands::Proxy => runproxy(&opts, &runner),
+        Commands::Nwi => runnwi(&opts, &runner),
+        Commands::Nc {
+            command: NcCommands::List,
+        } => runnclist(&opts, &runner),
+        Commands::Nc {
+            command: NcCommands::Status { ref service },
+        } => runncstatus(&opts, &runner, service),
+        Commands::Reach { ref host } => runreach(&opts, &runner, host),
+        Commands::Diff { ref old, ref new } => rundiff(&opts, old, new),
+        Commands::Windows { ref input } => runwindows(&opts, input),
+    };
+    // errors are always printed, even with --quiet, so scripts checking the exit code can say why
+    if let Err(err) = res {
+        eprintln!("{}", err);
+        std::process::exit(1);
     }
 }
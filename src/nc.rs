@@ -0,0 +1,218 @@
+//! Parsers for the output of `scutil --nc list`, which lists the VPN and other network
+//! connection services in the current network set, and `scutil --nc status <service>`.
+
+use std::convert::Infallible;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::dns::ParseError;
+use crate::scstore::{self, StoreValue};
+
+/// The state in parentheses after the enabled marker, see `SCNetworkConnectionStatus`
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub enum ConnectionState {
+    Invalid,
+    Disconnected,
+    Connecting,
+    Connected,
+    Disconnecting,
+    /// A state we don't know about, stored verbatim
+    Unknown(String),
+}
+
+/// Unrecognised states become [ConnectionState::Unknown], so this can't fail
+impl From<&str> for ConnectionState {
+    fn from(s: &str) -> Self {
+        match s {
+            "Invalid" => ConnectionState::Invalid,
+            "Disconnected" => ConnectionState::Disconnected,
+            "Connecting" => ConnectionState::Connecting,
+            "Connected" => ConnectionState::Connected,
+            "Disconnecting" => ConnectionState::Disconnecting,
+            _ => ConnectionState::Unknown(s.to_string()),
+        }
+    }
+}
+
+impl FromStr for ConnectionState {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(ConnectionState::from(s))
+    }
+}
+
+impl Display for ConnectionState {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            ConnectionState::Invalid => write!(f, "Invalid"),
+            ConnectionState::Disconnected => write!(f, "Disconnected"),
+            ConnectionState::Connecting => write!(f, "Connecting"),
+            ConnectionState::Connected => write!(f, "Connected"),
+            ConnectionState::Disconnecting => write!(f, "Disconnecting"),
+            ConnectionState::Unknown(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+/// One line of `scutil --nc list`, eg
+/// `* (Connected)      8F3A...  VPN (com.wireguard.macos) "Home"  [VPN:com.wireguard.macos]`
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct VpnService {
+    /// Whether the line starts with `*`
+    pub enabled: bool,
+    /// The service ID, used to refer to it with `scutil --nc start` and friends
+    pub guid: String,
+    pub state: ConnectionState,
+    /// The first word of the interface description, eg `PPP`, `VPN` or `IPSec`
+    pub interface_type: String,
+    /// The display name, without the quotes
+    pub name: String,
+    /// What's in the brackets at the end of the line, eg `PPP:L2TP`
+    pub protocol: Option<String>,
+}
+
+impl VpnService {
+    pub fn is_connected(&self) -> bool {
+        self.state == ConnectionState::Connected
+    }
+}
+
+/// Parses the output of `scutil --nc list`
+pub fn parse_text(input: &str) -> Result<Vec<VpnService>, ParseError> {
+    let mut services = Vec::new();
+    for (line_index, line) in input.lines().enumerate() {
+        if line.trim().is_empty() || line.starts_with("Available network connection services") {
+            continue;
+        }
+        services.push(parse_service(line, line_index + 1)?);
+    }
+    Ok(services)
+}
+
+/// Splits a service line into its parts.
+///
+/// Names can contain spaces, brackets, parentheses and even quotes, so the name is taken to run
+/// from the first quote after the interface description to the last quote on the line, which
+/// can't be in the protocol.
+fn parse_service(line: &str, line_no: usize) -> Result<VpnService, ParseError> {
+    let missing = |field| ParseError::MissingField {
+        line_no,
+        content: line.to_string(),
+        field,
+    };
+
+    let (enabled, rest) = match line.strip_prefix('*') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    let (state, rest) = rest
+        .trim_start()
+        .strip_prefix('(')
+        .and_then(|rest| rest.split_once(')'))
+        .ok_or_else(|| missing("connection state"))?;
+    let (guid, rest) = rest
+        .trim_start()
+        .split_once(char::is_whitespace)
+        .ok_or_else(|| missing("service ID"))?;
+
+    let name_start = rest.find('"').ok_or_else(|| missing("quoted name"))?;
+    let name_end = rest.rfind('"').filter(|end| *end > name_start);
+    let name_end = name_end.ok_or_else(|| ParseError::InvalidValue {
+        line_no,
+        content: line.to_string(),
+        message: "Name is missing its closing quote".to_string(),
+    })?;
+    let interface_type = rest[..name_start]
+        .split_whitespace()
+        .next()
+        .ok_or_else(|| missing("interface type"))?;
+    let name = &rest[name_start + 1..name_end];
+
+    let protocol = match rest[name_end + 1..].trim() {
+        "" => None,
+        protocol => Some(
+            protocol
+                .strip_prefix('[')
+                .and_then(|protocol| protocol.strip_suffix(']'))
+                .ok_or_else(|| ParseError::UnexpectedLine {
+                    line_no,
+                    content: line.to_string(),
+                })?
+                .to_string(),
+        ),
+    };
+
+    Ok(VpnService {
+        enabled,
+        guid: guid.to_string(),
+        state: ConnectionState::from(state.trim()),
+        interface_type: interface_type.to_string(),
+        name: name.to_string(),
+        protocol,
+    })
+}
+
+/// The output of `scutil --nc status <service>`
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct VpnStatus {
+    pub state: ConnectionState,
+    /// The `Extended Status` dictionary, eg with the `IPv4` addresses and the `ConnectTime`.
+    /// Disconnected services often leave it out, which is an empty dictionary.
+    pub extended: StoreValue,
+}
+
+impl VpnStatus {
+    pub fn is_connected(&self) -> bool {
+        self.state == ConnectionState::Connected
+    }
+}
+
+/// Parses the output of `scutil --nc status <service>`, a line with the state and then the
+/// `Extended Status <dictionary> {` block
+pub fn parse_status_text(input: &str) -> Result<VpnStatus, ParseError> {
+    let mut lines = input.lines().enumerate();
+    let Some((state_index, state)) = lines.find(|(_, line)| !line.trim().is_empty()) else {
+        return Err(ParseError::MissingField {
+            line_no: 1,
+            content: input.to_string(),
+            field: "connection state",
+        });
+    };
+    if state.trim().starts_with("Extended Status") {
+        return Err(ParseError::MissingField {
+            line_no: state_index + 1,
+            content: state.to_string(),
+            field: "connection state",
+        });
+    }
+
+    // blank out the state and drop the label, so the dictionary parser's line numbers still match
+    let dictionary: Vec<&str> = input
+        .lines()
+        .enumerate()
+        .map(|(line_index, line)| match line_index {
+            line_index if line_index <= state_index => "",
+            _ => line
+                .trim_start()
+                .strip_prefix("Extended Status")
+                .unwrap_or(line),
+        })
+        .collect();
+
+    Ok(VpnStatus {
+        state: ConnectionState::from(state.trim()),
+        extended: scstore::parse_text(&dictionary.join("\n"))?,
+    })
+}
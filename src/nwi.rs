@@ -0,0 +1,231 @@
+//! Parser for the output of `scutil --nwi`, which summarises the interfaces the system is using
+//! for IPv4 and IPv6, most preferred first.
+
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use std::str::FromStr;
+
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::dns::ParseError;
+use crate::reachability::ReachabilityFlags;
+
+/// A parsed `flags` line, eg `0x5 (IPv4,DNS)`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct NwiFlags {
+    /// The raw flags value
+    pub value: u32,
+    /// The descriptors from inside the parentheses, as scutil printed them
+    pub flags: Vec<String>,
+}
+
+impl NwiFlags {
+    // the bits from network_information.h
+    pub const HAS_IPV4: u32 = 1 << 0;
+    pub const HAS_IPV6: u32 = 1 << 1;
+    pub const HAS_DNS: u32 = 1 << 2;
+    pub const NOT_IN_LIST: u32 = 1 << 3;
+
+    pub fn has_ipv4(&self) -> bool {
+        self.value & Self::HAS_IPV4 != 0
+    }
+
+    pub fn has_ipv6(&self) -> bool {
+        self.value & Self::HAS_IPV6 != 0
+    }
+
+    pub fn has_dns(&self) -> bool {
+        self.value & Self::HAS_DNS != 0
+    }
+
+    /// Set for interfaces which are up but aren't used for the default route
+    pub fn not_in_list(&self) -> bool {
+        self.value & Self::NOT_IN_LIST != 0
+    }
+}
+
+impl FromStr for NwiFlags {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (value, descriptors) = match s.split_once(' ') {
+            Some((value, descriptors)) => (value, descriptors.trim()),
+            None => (s, ""),
+        };
+        let value = u32::from_str_radix(value.trim_start_matches("0x"), 16)
+            .map_err(|err| format!("Invalid flags value {:?}: {}", value, err))?;
+        let flags = descriptors
+            .strip_prefix('(')
+            .and_then(|d| d.strip_suffix(')'))
+            .unwrap_or(descriptors)
+            .split(',')
+            .map(str::trim)
+            .filter(|d| !d.is_empty())
+            .map(str::to_string)
+            .collect();
+        Ok(Self { value, flags })
+    }
+}
+
+/// One interface in the IPv4 or IPv6 list
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct NwiInterface {
+    /// The interface name, eg `en0`
+    pub name: String,
+    /// Where the interface is in its list, `0` is the primary interface
+    pub rank: usize,
+    pub flags: NwiFlags,
+    pub addresses: Vec<IpAddr>,
+    /// The `VPN server` line, for tunnel interfaces
+    pub vpn_server: Option<IpAddr>,
+    pub reach: Option<ReachabilityFlags>,
+    /// Lines we don't know about, keyed by the name before the colon
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "BTreeMap::is_empty")
+    )]
+    pub extra: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct NetworkInfo {
+    pub ipv4: Vec<NwiInterface>,
+    pub ipv6: Vec<NwiInterface>,
+    /// The overall reachability of IPv4, from the `REACH` line after the list
+    pub ipv4_reach: Option<ReachabilityFlags>,
+    /// The overall reachability of IPv6, from the `REACH` line after the list
+    pub ipv6_reach: Option<ReachabilityFlags>,
+    /// The `Network interfaces` line, every interface that's up in the order they're used
+    pub interfaces: Vec<String>,
+    /// The generation from the `Network information (generation ...)` line, which changes each
+    /// time the state is updated. Older versions of macOS don't print it.
+    pub generated_at: Option<String>,
+}
+
+impl NetworkInfo {
+    /// The interface IPv4 traffic goes out of by default
+    pub fn primary_ipv4(&self) -> Option<&NwiInterface> {
+        self.ipv4.first()
+    }
+
+    /// The interface IPv6 traffic goes out of by default
+    pub fn primary_ipv6(&self) -> Option<&NwiInterface> {
+        self.ipv6.first()
+    }
+}
+
+/// Which list of interfaces we're in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Section {
+    Ipv4,
+    Ipv6,
+}
+
+/// Parses the output of `scutil --nwi`
+pub fn parse_text(input: &str) -> Result<NetworkInfo, ParseError> {
+    let mut info = NetworkInfo::default();
+    let mut section: Option<Section> = None;
+
+    for (line_index, line) in input.lines().enumerate() {
+        let line_no = line_index + 1;
+        let trimmed = line.trim();
+        let unexpected = || ParseError::UnexpectedLine {
+            line_no,
+            content: line.to_string(),
+        };
+        let invalid_value = |message: String| ParseError::InvalidValue {
+            line_no,
+            content: line.to_string(),
+            message,
+        };
+
+        if trimmed.is_empty() {
+            continue;
+        }
+        if let Some(header) = trimmed.strip_prefix("Network information") {
+            info.generated_at = header
+                .trim()
+                .strip_prefix("(generation ")
+                .and_then(|generation| generation.split_whitespace().next())
+                .map(|generation| generation.trim_end_matches(')').to_string());
+            continue;
+        }
+        if let Some(interfaces) = trimmed.strip_prefix("Network interfaces:") {
+            info.interfaces = interfaces.split_whitespace().map(str::to_string).collect();
+            section = None;
+            continue;
+        }
+        match trimmed {
+            "IPv4 network interface information" => {
+                section = Some(Section::Ipv4);
+                continue;
+            }
+            "IPv6 network interface information" => {
+                section = Some(Section::Ipv6);
+                continue;
+            }
+            "No IPv4 states found" | "No IPv6 states found" => continue,
+            _ => {}
+        }
+
+        let Some(section) = section else {
+            return Err(unexpected());
+        };
+        let (interfaces, reach) = match section {
+            Section::Ipv4 => (&mut info.ipv4, &mut info.ipv4_reach),
+            Section::Ipv6 => (&mut info.ipv6, &mut info.ipv6_reach),
+        };
+        let (key, value) = trimmed.split_once(" : ").ok_or_else(unexpected)?;
+        let (key, value) = (key.trim(), value.trim());
+
+        if key == "REACH" {
+            let value = value.strip_prefix("flags").ok_or_else(unexpected)?;
+            *reach = Some(ReachabilityFlags::from_str(value).map_err(invalid_value)?);
+            continue;
+        }
+        // an interface starts with `en0 : flags : 0x5 (IPv4,DNS)`
+        if let Some(("flags", flags)) = value
+            .split_once(" : ")
+            .map(|(field, flags)| (field.trim(), flags))
+        {
+            interfaces.push(NwiInterface {
+                name: key.to_string(),
+                rank: interfaces.len(),
+                flags: NwiFlags::from_str(flags).map_err(invalid_value)?,
+                ..Default::default()
+            });
+            continue;
+        }
+
+        let Some(interface) = interfaces.last_mut() else {
+            return Err(unexpected());
+        };
+        let address = |value: &str| {
+            value
+                .parse::<IpAddr>()
+                .map_err(|err| invalid_value(format!("Invalid address {:?}: {}", value, err)))
+        };
+        match key {
+            "address" => interface.addresses.push(address(value)?),
+            "VPN server" => interface.vpn_server = Some(address(value)?),
+            "reach" => {
+                interface.reach = Some(ReachabilityFlags::from_str(value).map_err(invalid_value)?)
+            }
+            _ => {
+                interface.extra.insert(key.to_string(), value.to_string());
+            }
+        }
+    }
+
+    Ok(info)
+}
@@ -0,0 +1,174 @@
+//! Parser for the output of `scutil --proxy`, which prints the system proxy settings as a
+//! dictionary, with per-interface settings under `__SCOPED__`. The dictionary is read with
+//! [crate::scstore].
+
+use std::collections::BTreeMap;
+
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::dns::ParseError;
+use crate::scstore::{parse_dictionary, Entry, Value};
+
+/// The settings for one kind of proxy, eg `HTTPEnable`, `HTTPProxy` and `HTTPPort`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct ProxyServer {
+    pub enabled: bool,
+    pub host: Option<String>,
+    pub port: Option<u16>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct ProxyConfig {
+    pub http: ProxyServer,
+    pub https: ProxyServer,
+    pub ftp: ProxyServer,
+    pub socks: ProxyServer,
+    /// Hosts and networks which bypass the proxies, from `ExceptionsList`
+    pub exceptions_list: Vec<String>,
+    pub exclude_simple_hostnames: bool,
+    pub ftp_passive: bool,
+    /// `ProxyAutoConfigEnable`
+    pub auto_config_enabled: bool,
+    /// `ProxyAutoConfigURLString`, the location of the PAC file
+    pub auto_config_url: Option<String>,
+    /// `ProxyAutoDiscoveryEnable`, ie WPAD
+    pub auto_discovery_enabled: bool,
+    /// Per-interface settings from `__SCOPED__`, keyed by interface name
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "BTreeMap::is_empty")
+    )]
+    pub scoped: BTreeMap<String, ProxyConfig>,
+    /// Keys we don't know about, eg `GopherEnable`
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "BTreeMap::is_empty")
+    )]
+    pub extra: BTreeMap<String, String>,
+}
+
+/// Parses the output of `scutil --proxy`
+pub fn parse_text(input: &str) -> Result<ProxyConfig, ParseError> {
+    proxy_config(parse_dictionary(input)?)
+}
+
+fn proxy_config(entries: Vec<Entry>) -> Result<ProxyConfig, ParseError> {
+    let mut config = ProxyConfig::default();
+
+    for Entry {
+        line_no,
+        content,
+        key,
+        value,
+    } in entries
+    {
+        let value = match value {
+            Value::String(value) => value,
+            Value::Array(items) if key == "ExceptionsList" => {
+                for item in items {
+                    match item.value {
+                        Value::String(value) => config.exceptions_list.push(value),
+                        _ => {
+                            return Err(ParseError::UnexpectedLine {
+                                line_no: item.line_no,
+                                content: item.content,
+                            })
+                        }
+                    }
+                }
+                continue;
+            }
+            Value::Dictionary(interfaces) if key == "__SCOPED__" => {
+                for interface in interfaces {
+                    match interface.value {
+                        Value::Dictionary(entries) => {
+                            config.scoped.insert(interface.key, proxy_config(entries)?);
+                        }
+                        _ => {
+                            return Err(ParseError::UnexpectedLine {
+                                line_no: interface.line_no,
+                                content: interface.content,
+                            })
+                        }
+                    }
+                }
+                continue;
+            }
+            // nested values we don't know about can't go in `extra`
+            Value::Array(_) | Value::Dictionary(_) => continue,
+        };
+
+        let (server, setting) = match key.as_str() {
+            "ExcludeSimpleHostnames" => {
+                config.exclude_simple_hostnames = parse_bool(&value, line_no, &content)?;
+                continue;
+            }
+            "FTPPassive" => {
+                config.ftp_passive = parse_bool(&value, line_no, &content)?;
+                continue;
+            }
+            "ProxyAutoConfigEnable" => {
+                config.auto_config_enabled = parse_bool(&value, line_no, &content)?;
+                continue;
+            }
+            "ProxyAutoConfigURLString" => {
+                config.auto_config_url = Some(value);
+                continue;
+            }
+            "ProxyAutoDiscoveryEnable" => {
+                config.auto_discovery_enabled = parse_bool(&value, line_no, &content)?;
+                continue;
+            }
+            name => match ["HTTPS", "HTTP", "FTP", "SOCKS"]
+                .into_iter()
+                .find_map(|prefix| Some((prefix, name.strip_prefix(prefix)?)))
+            {
+                Some(("HTTPS", setting)) => (&mut config.https, setting),
+                Some(("HTTP", setting)) => (&mut config.http, setting),
+                Some(("FTP", setting)) => (&mut config.ftp, setting),
+                Some((_, setting)) => (&mut config.socks, setting),
+                None => {
+                    config.extra.insert(key, value);
+                    continue;
+                }
+            },
+        };
+        match setting {
+            "Enable" => server.enabled = parse_bool(&value, line_no, &content)?,
+            "Proxy" => server.host = Some(value),
+            "Port" => {
+                server.port = Some(value.parse().map_err(|source| ParseError::InvalidInteger {
+                    line_no,
+                    content,
+                    source,
+                })?)
+            }
+            // eg `HTTPUser`
+            _ => {
+                config.extra.insert(key, value);
+            }
+        }
+    }
+
+    Ok(config)
+}
+
+/// scutil prints booleans as `0` or `1`
+fn parse_bool(value: &str, line_no: usize, content: &str) -> Result<bool, ParseError> {
+    match value {
+        "0" => Ok(false),
+        "1" => Ok(true),
+        _ => Err(ParseError::InvalidValue {
+            line_no,
+            content: content.to_string(),
+            message: format!("Expected 0 or 1, got {:?}", value),
+        }),
+    }
+}
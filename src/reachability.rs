@@ -0,0 +1,355 @@
+//! Reachability flags, as printed in the `reach` line of `scutil --dns` and by `scutil -r <host>`
+
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::ser::SerializeStruct;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize, Serializer};
+
+use std::convert::Infallible;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::dns::ParseError;
+#[cfg(feature = "system")]
+use crate::runner::{ScutilRunner, SystemError, SystemScutil};
+
+/// The descriptors scutil prints in parentheses after a `reach` value, see `SCNetworkReachabilityFlags`
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub enum ReachabilityFlag {
+    TransientConnection,
+    Reachable,
+    ConnectionRequired,
+    ConnectionOnTraffic,
+    InterventionRequired,
+    ConnectionOnDemand,
+    LocalAddress,
+    DirectlyReachableAddress,
+    WWAN,
+    NotReachable,
+    /// A descriptor we don't know about, stored verbatim
+    Unknown(String),
+}
+
+impl FromStr for ReachabilityFlag {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "Transient Connection" => ReachabilityFlag::TransientConnection,
+            "Reachable" => ReachabilityFlag::Reachable,
+            "Connection Required" => ReachabilityFlag::ConnectionRequired,
+            "Automatic Connection On Traffic" => ReachabilityFlag::ConnectionOnTraffic,
+            "Intervention Required" => ReachabilityFlag::InterventionRequired,
+            "Automatic Connection On Demand" => ReachabilityFlag::ConnectionOnDemand,
+            "Local Address" => ReachabilityFlag::LocalAddress,
+            "Directly Reachable Address" => ReachabilityFlag::DirectlyReachableAddress,
+            "WWAN" => ReachabilityFlag::WWAN,
+            "Not Reachable" => ReachabilityFlag::NotReachable,
+            _ => ReachabilityFlag::Unknown(s.to_string()),
+        })
+    }
+}
+
+impl Display for ReachabilityFlag {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            ReachabilityFlag::TransientConnection => write!(f, "Transient Connection"),
+            ReachabilityFlag::Reachable => write!(f, "Reachable"),
+            ReachabilityFlag::ConnectionRequired => write!(f, "Connection Required"),
+            ReachabilityFlag::ConnectionOnTraffic => write!(f, "Automatic Connection On Traffic"),
+            ReachabilityFlag::InterventionRequired => write!(f, "Intervention Required"),
+            ReachabilityFlag::ConnectionOnDemand => write!(f, "Automatic Connection On Demand"),
+            ReachabilityFlag::LocalAddress => write!(f, "Local Address"),
+            ReachabilityFlag::DirectlyReachableAddress => write!(f, "Directly Reachable Address"),
+            ReachabilityFlag::WWAN => write!(f, "WWAN"),
+            ReachabilityFlag::NotReachable => write!(f, "Not Reachable"),
+            ReachabilityFlag::Unknown(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+impl ReachabilityFlag {
+    /// The descriptors we know about, as scutil prints them
+    pub const KNOWN: [&'static str; 10] = [
+        "Transient Connection",
+        "Reachable",
+        "Connection Required",
+        "Automatic Connection On Traffic",
+        "Intervention Required",
+        "Automatic Connection On Demand",
+        "Local Address",
+        "Directly Reachable Address",
+        "WWAN",
+        "Not Reachable",
+    ];
+
+    /// The `SCNetworkReachabilityFlags` bit for this descriptor, `0` for
+    /// [ReachabilityFlag::NotReachable] and descriptors we don't know about
+    pub fn bit(&self) -> u32 {
+        match self {
+            ReachabilityFlag::TransientConnection => ReachabilityFlags::TRANSIENT_CONNECTION,
+            ReachabilityFlag::Reachable => ReachabilityFlags::REACHABLE,
+            ReachabilityFlag::ConnectionRequired => ReachabilityFlags::CONNECTION_REQUIRED,
+            ReachabilityFlag::ConnectionOnTraffic => ReachabilityFlags::CONNECTION_ON_TRAFFIC,
+            ReachabilityFlag::InterventionRequired => ReachabilityFlags::INTERVENTION_REQUIRED,
+            ReachabilityFlag::ConnectionOnDemand => ReachabilityFlags::CONNECTION_ON_DEMAND,
+            ReachabilityFlag::LocalAddress => ReachabilityFlags::IS_LOCAL_ADDRESS,
+            ReachabilityFlag::DirectlyReachableAddress => ReachabilityFlags::IS_DIRECT,
+            ReachabilityFlag::WWAN => ReachabilityFlags::IS_WWAN,
+            ReachabilityFlag::NotReachable | ReachabilityFlag::Unknown(_) => 0,
+        }
+    }
+}
+
+/// A parsed `reach` line, eg `0x00020002 (Reachable,Directly Reachable Address)`.
+///
+/// Serializes as an object with the raw `value`, the `flags` descriptors and a `reachable` bool.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+pub struct ReachabilityFlags {
+    /// The raw flags value
+    pub value: u32,
+    /// The decoded descriptors from inside the parentheses
+    pub flags: Vec<ReachabilityFlag>,
+}
+
+impl ReachabilityFlags {
+    // the bits from SCNetworkReachabilityFlags
+    pub const TRANSIENT_CONNECTION: u32 = 1 << 0;
+    pub const REACHABLE: u32 = 1 << 1;
+    pub const CONNECTION_REQUIRED: u32 = 1 << 2;
+    pub const CONNECTION_ON_TRAFFIC: u32 = 1 << 3;
+    pub const INTERVENTION_REQUIRED: u32 = 1 << 4;
+    pub const CONNECTION_ON_DEMAND: u32 = 1 << 5;
+    pub const IS_LOCAL_ADDRESS: u32 = 1 << 16;
+    pub const IS_DIRECT: u32 = 1 << 17;
+    pub const IS_WWAN: u32 = 1 << 18;
+
+    /// Builds the flags from descriptors alone, working out the value from their bits
+    pub fn from_flags(flags: Vec<ReachabilityFlag>) -> Self {
+        Self {
+            value: flags.iter().fold(0, |value, flag| value | flag.bit()),
+            flags,
+        }
+    }
+
+    fn has(&self, bit: u32) -> bool {
+        self.value & bit != 0
+    }
+
+    pub fn is_reachable(&self) -> bool {
+        self.has(Self::REACHABLE)
+    }
+
+    pub fn is_transient_connection(&self) -> bool {
+        self.has(Self::TRANSIENT_CONNECTION)
+    }
+
+    pub fn connection_required(&self) -> bool {
+        self.has(Self::CONNECTION_REQUIRED)
+    }
+
+    pub fn connection_on_traffic(&self) -> bool {
+        self.has(Self::CONNECTION_ON_TRAFFIC)
+    }
+
+    pub fn intervention_required(&self) -> bool {
+        self.has(Self::INTERVENTION_REQUIRED)
+    }
+
+    pub fn connection_on_demand(&self) -> bool {
+        self.has(Self::CONNECTION_ON_DEMAND)
+    }
+
+    pub fn is_local_address(&self) -> bool {
+        self.has(Self::IS_LOCAL_ADDRESS)
+    }
+
+    pub fn is_direct(&self) -> bool {
+        self.has(Self::IS_DIRECT)
+    }
+
+    pub fn is_wwan(&self) -> bool {
+        self.has(Self::IS_WWAN)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for ReachabilityFlags {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("ReachabilityFlags", 3)?;
+        state.serialize_field("value", &self.value)?;
+        state.serialize_field("flags", &self.flags)?;
+        state.serialize_field("reachable", &self.is_reachable())?;
+        state.end()
+    }
+}
+
+/// Matches the [Serialize] implementation, with `reachable` worked out from the value
+#[cfg(feature = "schemars")]
+impl JsonSchema for ReachabilityFlags {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "ReachabilityFlags".into()
+    }
+
+    fn json_schema(generator: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        schemars::json_schema!({
+            "type": "object",
+            "properties": {
+                "value": generator.subschema_for::<u32>(),
+                "flags": generator.subschema_for::<Vec<ReachabilityFlag>>(),
+                "reachable": generator.subschema_for::<bool>(),
+            },
+            "required": ["value", "flags", "reachable"],
+        })
+    }
+}
+
+impl FromStr for ReachabilityFlags {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (value, descriptors) = match s.split_once(' ') {
+            Some((value, descriptors)) => (value, descriptors.trim()),
+            None => (s, ""),
+        };
+        let value = u32::from_str_radix(value.trim_start_matches("0x"), 16)
+            .map_err(|err| format!("Invalid reach value {:?}: {}", value, err))?;
+
+        let descriptors = descriptors
+            .strip_prefix('(')
+            .and_then(|d| d.strip_suffix(')'))
+            .unwrap_or(descriptors);
+        let flags = descriptors
+            .split(',')
+            .map(str::trim)
+            .filter(|d| !d.is_empty())
+            .filter_map(|d| ReachabilityFlag::from_str(d).ok())
+            .collect();
+
+        Ok(Self { value, flags })
+    }
+}
+
+impl Display for ReachabilityFlags {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        write!(f, "{:#010x}", self.value)?;
+        if !self.flags.is_empty() {
+            let flags: Vec<String> = self.flags.iter().map(|flag| flag.to_string()).collect();
+            write!(f, " ({})", flags.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+/// The result of `scutil -r <host>`
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct Reachability {
+    /// The host that was checked, which isn't part of the output so it's up to the caller to fill
+    /// it in
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub host: Option<String>,
+    pub reach: ReachabilityFlags,
+}
+
+impl Reachability {
+    /// Runs `scutil -r <host>`, which takes a host name or an address
+    #[cfg(feature = "system")]
+    pub fn from_system(host: &str) -> Result<Self, SystemError> {
+        Self::from_runner(&SystemScutil::default(), host)
+    }
+
+    /// Like `Reachability::from_system`, with scutil run by `runner`
+    #[cfg(feature = "system")]
+    pub fn from_runner(runner: &dyn ScutilRunner, host: &str) -> Result<Self, SystemError> {
+        let output = runner.run_reachability(host)?;
+        let mut reachability = parse_text(&output)?;
+        reachability.host = Some(host.to_string());
+        Ok(reachability)
+    }
+
+    pub fn is_reachable(&self) -> bool {
+        self.reach.is_reachable()
+    }
+}
+
+/// Splits descriptors separated by commas, or by spaces as some versions of scutil print them.
+///
+/// Descriptors can be several words long, so without commas the longest one in
+/// [ReachabilityFlag::KNOWN] is matched at each word, and words that don't start one become
+/// [ReachabilityFlag::Unknown].
+fn split_descriptors(s: &str) -> Vec<ReachabilityFlag> {
+    if s.contains(',') {
+        return s
+            .split(',')
+            .map(str::trim)
+            .filter(|d| !d.is_empty())
+            .filter_map(|d| ReachabilityFlag::from_str(d).ok())
+            .collect();
+    }
+
+    let words: Vec<&str> = s.split_whitespace().collect();
+    let mut flags = Vec::new();
+    let mut word_index = 0;
+    while word_index < words.len() {
+        let length = (1..=words.len() - word_index)
+            .rev()
+            .find(|length| {
+                let candidate = words[word_index..word_index + length].join(" ");
+                ReachabilityFlag::KNOWN.contains(&candidate.as_str())
+            })
+            .unwrap_or(1);
+        let descriptor = words[word_index..word_index + length].join(" ");
+        flags.extend(ReachabilityFlag::from_str(&descriptor).ok());
+        word_index += length;
+    }
+    flags
+}
+
+/// Parses the output of `scutil -r <host>`, eg `Reachable,Directly Reachable Address` or
+/// `Reachable Directly Reachable Address`.
+///
+/// scutil only prints the descriptors, so the raw value is worked out from them. Output which
+/// includes the value, as in a `reach` line, is accepted too.
+pub fn parse_text(input: &str) -> Result<Reachability, ParseError> {
+    let mut lines = input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty());
+    let Some((line_index, line)) = lines.next() else {
+        return Err(ParseError::MissingField {
+            line_no: 1,
+            content: input.to_string(),
+            field: "reachability flags",
+        });
+    };
+    if let Some((line_index, line)) = lines.next() {
+        return Err(ParseError::UnexpectedLine {
+            line_no: line_index + 1,
+            content: line.to_string(),
+        });
+    }
+
+    let trimmed = line.trim();
+    let reach = if trimmed.starts_with("0x") {
+        ReachabilityFlags::from_str(trimmed).map_err(|message| ParseError::InvalidValue {
+            line_no: line_index + 1,
+            content: line.to_string(),
+            message,
+        })?
+    } else {
+        ReachabilityFlags::from_flags(split_descriptors(trimmed))
+    };
+
+    Ok(Reachability { host: None, reach })
+}
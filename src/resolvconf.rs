@@ -0,0 +1,275 @@
+//! Parser for `/etc/resolv.conf`, and comparing it with [DNSConfig].
+//!
+//! macOS rewrites resolv.conf from the default resolver, usually `resolver #1`, so the two
+//! should agree. When they don't, something like a VPN client has probably written to the file
+//! directly, and the tools that read it (`dig`, `nslookup`, anything not using the system
+//! resolver) will see different nameservers to everything else.
+
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use std::str::FromStr;
+
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::dns::{DNSConfig, NameserverAddress, ParseError, Resolver};
+
+/// How many `nameserver` lines the resolver in glibc and musl uses, the rest are ignored
+pub const MAX_NAMESERVERS: usize = 3;
+/// How many search domains older resolvers use, glibc before 2.26 ignores the rest
+pub const MAX_SEARCH_DOMAINS: usize = 6;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct ResolvConf {
+    /// The `nameserver` lines, in the order they're queried
+    pub nameservers: Vec<NameserverAddress>,
+    /// The `search` line, if there was one. Only the last one counts.
+    pub search: Vec<String>,
+    /// The `domain` line, which is used as the search list when there's no `search` line
+    pub domain: Option<String>,
+    /// The `options` lines, eg `ndots:2`
+    pub options: Vec<String>,
+    /// Lines with a keyword we don't know about, eg `sortlist`, keyed by the keyword
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "BTreeMap::is_empty")
+    )]
+    pub extra: BTreeMap<String, String>,
+}
+
+impl ResolvConf {
+    /// The domains used to expand short names, from `search` or falling back to `domain`
+    pub fn search_domains(&self) -> Vec<&str> {
+        match (self.search.is_empty(), &self.domain) {
+            (true, Some(domain)) => vec![domain.as_str()],
+            _ => self.search.iter().map(String::as_str).collect(),
+        }
+    }
+}
+
+/// A difference between resolv.conf and the default resolver, see
+/// [DNSConfig::matches_resolv_conf]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub enum Mismatch {
+    /// The default resolver uses this nameserver, but resolv.conf doesn't list it
+    MissingNameserver(IpAddr),
+    /// resolv.conf lists this nameserver, but the default resolver doesn't use it
+    ExtraNameserver(IpAddr),
+    /// The search domains are different, or in a different order
+    SearchDomains {
+        scutil: Vec<String>,
+        resolv_conf: Vec<String>,
+    },
+}
+
+/// Parses the contents of `/etc/resolv.conf`
+pub fn parse_text(input: &str) -> Result<ResolvConf, ParseError> {
+    let mut resolv_conf = ResolvConf::default();
+
+    for (line_index, line) in input.lines().enumerate() {
+        let line_no = line_index + 1;
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with(';') {
+            continue;
+        }
+        let (keyword, value) = match trimmed.split_once(char::is_whitespace) {
+            Some((keyword, value)) => (keyword, value.trim()),
+            None => (trimmed, ""),
+        };
+        let missing = |field| ParseError::MissingField {
+            line_no,
+            content: line.to_string(),
+            field,
+        };
+
+        match keyword {
+            "nameserver" => {
+                if value.is_empty() {
+                    return Err(missing("nameserver address"));
+                }
+                let nameserver = NameserverAddress::from_str(value).map_err(|source| {
+                    ParseError::InvalidNameserver {
+                        line_no,
+                        content: line.to_string(),
+                        source,
+                    }
+                })?;
+                resolv_conf.nameservers.push(nameserver);
+            }
+            "search" => {
+                resolv_conf.search = value.split_whitespace().map(str::to_string).collect();
+            }
+            "domain" => {
+                if value.is_empty() {
+                    return Err(missing("domain"));
+                }
+                resolv_conf.domain = Some(value.to_string());
+            }
+            "options" => resolv_conf
+                .options
+                .extend(value.split_whitespace().map(str::to_string)),
+            keyword => {
+                resolv_conf
+                    .extra
+                    .insert(keyword.to_string(), value.to_string());
+            }
+        }
+    }
+
+    Ok(resolv_conf)
+}
+
+impl Resolver {
+    /// Renders this resolver as resolv.conf, eg for a container or Linux VM, with `search`,
+    /// `nameserver`, `sortlist` and `options timeout:n` lines in the order macOS writes them.
+    ///
+    /// Only the first [MAX_NAMESERVERS] nameservers and [MAX_SEARCH_DOMAINS] search domains are
+    /// written, as the rest would be ignored. resolv.conf can't set a port either, so nameservers
+    /// on a port other than 53 are left out. Anything left out is listed in a comment.
+    pub fn to_resolv_conf(&self) -> String {
+        let mut lines = vec![format!(
+            "# generated from resolver #{} of scutil --dns",
+            self.id
+        )];
+
+        let search = self.search_domain_names();
+        if !search.is_empty() {
+            let (kept, dropped) = search.split_at(search.len().min(MAX_SEARCH_DOMAINS));
+            lines.push(format!("search {}", kept.join(" ")));
+            if !dropped.is_empty() {
+                lines.push(format!(
+                    "# left out search domains past the first {}: {}",
+                    MAX_SEARCH_DOMAINS,
+                    dropped.join(" ")
+                ));
+            }
+        }
+
+        let (nameservers, other_ports): (Vec<_>, Vec<_>) = self
+            .nameservers
+            .iter()
+            .partition(|nameserver| nameserver.port.or(self.port).unwrap_or(53) == 53);
+        let (kept, dropped) = nameservers.split_at(nameservers.len().min(MAX_NAMESERVERS));
+        for nameserver in kept {
+            lines.push(format!("nameserver {}", nameserver.address));
+        }
+        if !dropped.is_empty() {
+            let dropped: Vec<String> = dropped
+                .iter()
+                .map(|nameserver| nameserver.address.to_string())
+                .collect();
+            lines.push(format!(
+                "# left out nameservers past the first {}: {}",
+                MAX_NAMESERVERS,
+                dropped.join(" ")
+            ));
+        }
+        if !other_ports.is_empty() {
+            let other_ports: Vec<String> = other_ports
+                .iter()
+                .map(|nameserver| nameserver.to_string())
+                .collect();
+            lines.push(format!(
+                "# left out nameservers that aren't on port 53: {}",
+                other_ports.join(" ")
+            ));
+        }
+
+        if !self.sortlist.is_empty() {
+            let sortlist: Vec<String> = self
+                .sortlist
+                .iter()
+                .map(|entry| entry.to_string())
+                .collect();
+            lines.push(format!("sortlist {}", sortlist.join(" ")));
+        }
+
+        if let Some(timeout) = self.timeout {
+            lines.push(format!("options timeout:{}", timeout.as_secs()));
+        }
+
+        lines.push(String::new());
+        lines.join("\n")
+    }
+}
+
+impl DNSConfig {
+    /// Renders [DNSConfig::default_resolver] as resolv.conf, see [Resolver::to_resolv_conf].
+    ///
+    /// Scoped and mdns resolvers are never used, if there's no unscoped default resolver the
+    /// output is just a comment saying so.
+    pub fn to_resolv_conf(&self) -> String {
+        match self
+            .default_resolver()
+            .filter(|resolver| !resolver.is_mdns())
+        {
+            Some(resolver) => resolver.to_resolv_conf(),
+            None => "# scutil --dns doesn't have a default resolver\n".to_string(),
+        }
+    }
+
+    /// Compares resolv.conf with [DNSConfig::default_resolver], returning nothing if they agree.
+    ///
+    /// Nameservers are compared by address, ignoring the order and any `%scope`. Search domains
+    /// are compared in order, ignoring case and trailing dots.
+    pub fn matches_resolv_conf(&self, resolv_conf: &ResolvConf) -> Vec<Mismatch> {
+        let mut mismatches = Vec::new();
+        let resolver = self.default_resolver();
+
+        let scutil_nameservers: Vec<IpAddr> = resolver
+            .map(|resolver| {
+                resolver
+                    .nameservers
+                    .iter()
+                    .map(|nameserver| nameserver.address.address)
+                    .collect()
+            })
+            .unwrap_or_default();
+        let resolv_conf_nameservers: Vec<IpAddr> = resolv_conf
+            .nameservers
+            .iter()
+            .map(|nameserver| nameserver.address)
+            .collect();
+        for address in &scutil_nameservers {
+            if !resolv_conf_nameservers.contains(address) {
+                mismatches.push(Mismatch::MissingNameserver(*address));
+            }
+        }
+        for address in &resolv_conf_nameservers {
+            if !scutil_nameservers.contains(address) {
+                mismatches.push(Mismatch::ExtraNameserver(*address));
+            }
+        }
+
+        let scutil_search = resolver
+            .map(|resolver| resolver.search_domain_names())
+            .unwrap_or_default();
+        let resolv_conf_search = resolv_conf.search_domains();
+        let normalise = |domains: &[&str]| -> Vec<String> {
+            domains
+                .iter()
+                .map(|domain| domain.trim_end_matches('.').to_ascii_lowercase())
+                .collect()
+        };
+        if normalise(&scutil_search) != normalise(&resolv_conf_search) {
+            mismatches.push(Mismatch::SearchDomains {
+                scutil: scutil_search
+                    .iter()
+                    .map(|domain| domain.to_string())
+                    .collect(),
+                resolv_conf: resolv_conf_search
+                    .iter()
+                    .map(|domain| domain.to_string())
+                    .collect(),
+            });
+        }
+
+        mismatches
+    }
+}
@@ -0,0 +1,165 @@
+//! Running `scutil`, behind a trait so the output can be swapped for canned text in tests or on
+//! hosts that aren't running macOS.
+
+#[cfg(feature = "system")]
+use std::ffi::OsString;
+use std::fmt::{Display, Formatter};
+use std::io;
+#[cfg(feature = "system")]
+use std::process::Command;
+#[cfg(feature = "system")]
+use std::string::FromUtf8Error;
+
+#[cfg(feature = "system")]
+use thiserror::Error;
+
+#[cfg(feature = "system")]
+use crate::dns::ParseError;
+
+pub trait ScutilRunner {
+    /// Runs scutil with the given arguments and returns what it printed to stdout. If scutil runs
+    /// but fails, the error should wrap a [CommandFailed].
+    fn run(&self, args: &[&str]) -> io::Result<String>;
+
+    /// Runs `scutil --dns`
+    fn run_dns(&self) -> io::Result<String> {
+        self.run(&["--dns"])
+    }
+
+    /// Runs `scutil --proxy`
+    fn run_proxy(&self) -> io::Result<String> {
+        self.run(&["--proxy"])
+    }
+
+    /// Runs `scutil --nwi`
+    fn run_nwi(&self) -> io::Result<String> {
+        self.run(&["--nwi"])
+    }
+
+    /// Runs `scutil --nc list`
+    fn run_nc_list(&self) -> io::Result<String> {
+        self.run(&["--nc", "list"])
+    }
+
+    /// Runs `scutil --nc status <service>`, where `service` is the name or the service ID
+    fn run_nc_status(&self, service: &str) -> io::Result<String> {
+        self.run(&["--nc", "status", service])
+    }
+
+    /// Runs `scutil -r <host>`
+    fn run_reachability(&self, host: &str) -> io::Result<String> {
+        self.run(&["-r", host])
+    }
+
+    /// Runs `scutil --get <name>`
+    fn run_get(&self, name: &str) -> io::Result<String> {
+        self.run(&["--get", name])
+    }
+}
+
+/// The error a [ScutilRunner] returns, wrapped in an [io::Error], when scutil ran but exited
+/// with a failure status
+#[derive(Clone, Debug)]
+pub struct CommandFailed {
+    /// The program and arguments that were run
+    pub command: String,
+    /// The exit code, `None` if it was killed by a signal
+    pub code: Option<i32>,
+    pub stderr: String,
+}
+
+impl CommandFailed {
+    /// Finds the [CommandFailed] inside an error from [ScutilRunner::run], if that's why it failed
+    pub fn from_io_error(err: &io::Error) -> Option<&Self> {
+        err.get_ref()?.downcast_ref()
+    }
+}
+
+impl Display for CommandFailed {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        match self.code {
+            Some(code) => write!(f, "{} failed with exit code {}", self.command, code)?,
+            None => write!(f, "{} was killed by a signal", self.command)?,
+        }
+        write!(f, ": {}", self.stderr)
+    }
+}
+
+impl std::error::Error for CommandFailed {}
+
+/// Why running scutil and parsing its output failed, eg from [crate::dns::DNSConfig::from_system]
+#[cfg(feature = "system")]
+#[derive(Debug, Error)]
+pub enum SystemError {
+    /// scutil isn't on the `PATH`, usually because this isn't macOS
+    #[error("scutil wasn't found: {0}")]
+    NotFound(#[source] io::Error),
+    #[error("{0}")]
+    Failed(CommandFailed),
+    #[error("scutil printed invalid UTF-8: {0}")]
+    InvalidUtf8(#[source] FromUtf8Error),
+    #[error("{0}")]
+    Io(#[source] io::Error),
+    #[error("failed to parse the output of scutil: {0}")]
+    Parse(#[from] ParseError),
+}
+
+/// Sorts an error from [ScutilRunner::run] into the matching [SystemError]
+#[cfg(feature = "system")]
+impl From<io::Error> for SystemError {
+    fn from(err: io::Error) -> Self {
+        if err.kind() == io::ErrorKind::NotFound {
+            return SystemError::NotFound(err);
+        }
+        if let Some(failed) = CommandFailed::from_io_error(&err) {
+            return SystemError::Failed(failed.clone());
+        }
+        match err
+            .get_ref()
+            .and_then(|inner| inner.downcast_ref::<FromUtf8Error>())
+        {
+            Some(utf8_error) => SystemError::InvalidUtf8(utf8_error.clone()),
+            None => SystemError::Io(err),
+        }
+    }
+}
+
+/// Runs the `scutil` binary found on the `PATH`, or another program which acts like it
+#[cfg(feature = "system")]
+#[derive(Clone, Debug)]
+pub struct SystemScutil {
+    program: OsString,
+}
+
+#[cfg(feature = "system")]
+impl SystemScutil {
+    /// Runs `program` instead of `scutil`, eg a wrapper script or a stub which prints fixtures
+    pub fn with_program(program: impl Into<OsString>) -> Self {
+        Self {
+            program: program.into(),
+        }
+    }
+}
+
+#[cfg(feature = "system")]
+impl Default for SystemScutil {
+    fn default() -> Self {
+        Self::with_program("scutil")
+    }
+}
+
+#[cfg(feature = "system")]
+impl ScutilRunner for SystemScutil {
+    fn run(&self, args: &[&str]) -> io::Result<String> {
+        let output = Command::new(&self.program).args(args).output()?;
+        if !output.status.success() {
+            return Err(io::Error::other(CommandFailed {
+                command: format!("{} {}", self.program.to_string_lossy(), args.join(" ")),
+                code: output.status.code(),
+                stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            }));
+        }
+        String::from_utf8(output.stdout)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+}
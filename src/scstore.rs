@@ -0,0 +1,182 @@
+//! Parser for the dictionaries scutil prints for SCDynamicStore keys, eg from
+//! `echo "show State:/Network/Global/IPv4" | scutil`. `scutil --proxy` prints the same format,
+//! and [crate::proxy] is built on top of this.
+//!
+//! ```text
+//! <dictionary> {
+//!   PrimaryInterface : en0
+//!   Router : 192.168.1.1
+//!   Addresses : <array> {
+//!     0 : 192.168.1.23
+//!   }
+//! }
+//! ```
+
+use std::collections::BTreeMap;
+
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::dns::ParseError;
+
+/// A value from the store. Everything that isn't a dictionary or an array is kept as the text
+/// scutil printed, as it doesn't say what type it is.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "serde", serde(untagged))]
+pub enum StoreValue {
+    String(String),
+    Array(Vec<StoreValue>),
+    Dictionary(BTreeMap<String, StoreValue>),
+}
+
+impl StoreValue {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            StoreValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[StoreValue]> {
+        match self {
+            StoreValue::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    pub fn as_dictionary(&self) -> Option<&BTreeMap<String, StoreValue>> {
+        match self {
+            StoreValue::Dictionary(entries) => Some(entries),
+            _ => None,
+        }
+    }
+
+    /// Looks up `key` if this is a dictionary
+    pub fn get(&self, key: &str) -> Option<&StoreValue> {
+        self.as_dictionary()?.get(key)
+    }
+}
+
+/// A value as it was read, keeping the line numbers for the parsers built on top of this
+#[derive(Debug)]
+pub(crate) enum Value {
+    String(String),
+    Array(Vec<Entry>),
+    Dictionary(Vec<Entry>),
+}
+
+/// A `key : value` line, where the value might be a nested array or dictionary
+#[derive(Debug)]
+pub(crate) struct Entry {
+    pub(crate) line_no: usize,
+    pub(crate) content: String,
+    pub(crate) key: String,
+    pub(crate) value: Value,
+}
+
+impl From<Value> for StoreValue {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::String(value) => StoreValue::String(value),
+            // the keys are just the indexes
+            Value::Array(entries) => StoreValue::Array(
+                entries
+                    .into_iter()
+                    .map(|entry| entry.value.into())
+                    .collect(),
+            ),
+            Value::Dictionary(entries) => StoreValue::Dictionary(
+                entries
+                    .into_iter()
+                    .map(|entry| (entry.key, entry.value.into()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Parses a `<dictionary> { ... }` printed by scutil, empty input is an empty dictionary
+pub fn parse_text(input: &str) -> Result<StoreValue, ParseError> {
+    Ok(Value::Dictionary(parse_dictionary(input)?).into())
+}
+
+/// Reads the entries of the top level `<dictionary> {`, failing if there's anything after it
+pub(crate) fn parse_dictionary(input: &str) -> Result<Vec<Entry>, ParseError> {
+    let lines: Vec<&str> = input.lines().collect();
+    let mut line_index = 0;
+
+    // skip to the opening `<dictionary> {`
+    while line_index < lines.len() && lines[line_index].trim().is_empty() {
+        line_index += 1;
+    }
+    let Some(first_line) = lines.get(line_index) else {
+        return Ok(Vec::new());
+    };
+    if first_line.trim() != "<dictionary> {" {
+        return Err(ParseError::UnexpectedLine {
+            line_no: line_index + 1,
+            content: first_line.to_string(),
+        });
+    }
+    line_index += 1;
+    let opened_at = line_index;
+    let entries = parse_entries(&lines, &mut line_index, opened_at)?;
+
+    if let Some(extra_index) = (line_index..lines.len()).find(|i| !lines[*i].trim().is_empty()) {
+        return Err(ParseError::UnexpectedLine {
+            line_no: extra_index + 1,
+            content: lines[extra_index].to_string(),
+        });
+    }
+    Ok(entries)
+}
+
+/// Reads entries up to and including the closing `}`, `opened_at` is the 1-based line number of
+/// the line that opened the block
+fn parse_entries(
+    lines: &[&str],
+    line_index: &mut usize,
+    opened_at: usize,
+) -> Result<Vec<Entry>, ParseError> {
+    let mut entries = Vec::new();
+    while let Some(line) = lines.get(*line_index) {
+        let line_no = *line_index + 1;
+        *line_index += 1;
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        if trimmed == "}" {
+            return Ok(entries);
+        }
+        let (key, value) = trimmed
+            .split_once(" : ")
+            .ok_or_else(|| ParseError::UnexpectedLine {
+                line_no,
+                content: line.to_string(),
+            })?;
+        let value = match value.trim() {
+            "<dictionary> {" => Value::Dictionary(parse_entries(lines, line_index, line_no)?),
+            "<array> {" => Value::Array(parse_entries(lines, line_index, line_no)?),
+            value => Value::String(value.to_string()),
+        };
+        entries.push(Entry {
+            line_no,
+            content: line.to_string(),
+            key: key.trim().to_string(),
+            value,
+        });
+    }
+    Err(ParseError::MissingField {
+        line_no: opened_at,
+        content: lines
+            .get(opened_at - 1)
+            .map(|line| line.to_string())
+            .unwrap_or_default(),
+        field: "closing '}'",
+    })
+}
@@ -1,6 +1,16 @@
+use std::collections::HashSet;
+use std::convert::Infallible;
+use std::net::{IpAddr, SocketAddr};
 use std::str::FromStr;
+use std::time::Duration;
 
-use crate::dns::{parse_text, ResolverFlags};
+use crate::borrowed::parse_text_borrowed;
+use crate::dns::{
+    parse_reader, parse_text, parse_text_lenient, parse_text_with_options,
+    parse_text_with_warnings, BuildError, DNSConfig, InterfaceIndex, NameserverAddress, ParseError,
+    ParseOptions, ReachabilityFlag, ReachabilityFlags, Resolver, ResolverBuilder, ResolverFlags,
+    ResolverOption, ResolverOptions, SearchDomain, SectionKind, SortListEntry, WarningCategory,
+};
 
 #[test]
 fn test_from_file() {
@@ -21,21 +31,2203 @@ fn test_from_file() {
 
     assert_eq!(res.scoped_dns_config[0].id, 1);
     assert_eq!(
-        res.scoped_dns_config[0].search_domains,
-        vec!["subdomain.example.com".to_string()]
+        res.scoped_dns_config[0].search_domain_names(),
+        vec!["subdomain.example.com"]
+    );
+    let nameservers: Vec<String> = res.scoped_dns_config[0]
+        .nameservers
+        .iter()
+        .map(|ns| format!("{}={}", ns.index, ns.address))
+        .collect();
+    assert_eq!(
+        nameservers,
+        vec![
+            "0=1234:1234:fd:0:ffe:66ff:2345:b33f",
+            "1=10.0.0.12",
+            "2=10.0.0.1"
+        ]
+    );
+    let options = res.dns_config[1]
+        .options
+        .as_ref()
+        .expect("mDNS resolver should have options");
+    assert!(options.contains(&ResolverOption::Mdns));
+    assert!(res.dns_config[1].is_mdns());
+    assert!(!res.dns_config[0].is_mdns());
+    assert!(!res.scoped_dns_config[0].is_mdns());
+    for resolver in res.dns_config.iter().filter(|r| r.is_mdns()) {
+        assert_eq!(resolver.timeout, Some(Duration::from_secs(5)));
+    }
+    #[cfg(feature = "serde")]
+    {
+        let json = serde_json::to_value(&res).expect("Failed to serialize");
+        assert_eq!(
+            json["dns_config"][1]["options"],
+            serde_json::json!(["Mdns"])
+        );
+        assert_eq!(json["dns_config"][1]["timeout_secs"], serde_json::json!(5));
+        assert!(json["dns_config"][1].get("timeout").is_none());
+    }
+
+    let reach = res.scoped_dns_config[0]
+        .reach
+        .as_ref()
+        .expect("Scoped resolver should have a reach value");
+    assert_eq!(reach.value, 0x00020002);
+    assert!(matches!(
+        reach.flags.as_slice(),
+        [
+            ReachabilityFlag::Reachable,
+            ReachabilityFlag::DirectlyReachableAddress
+        ]
+    ));
+}
+
+// parsing and the types don't need serde, so this runs with --no-default-features as well
+#[test]
+fn test_parse_without_serde() {
+    let filecontents = std::fs::read_to_string("testdata.txt").expect("Could not read fixture");
+    let res = parse_text(&filecontents).expect("Could not parse fixture");
+    assert_eq!(res.resolver_count(), 8);
+    assert_eq!(parse_text(&res.to_scutil_string()).unwrap(), res);
+
+    let resolver = Resolver::builder(1)
+        .nameserver(IpAddr::from([10, 0, 0, 1]))
+        .build()
+        .unwrap();
+    assert_eq!(
+        resolver.to_string(),
+        "resolver #1\n  nameserver[0] : 10.0.0.1\n"
     );
 }
 
 #[test]
 fn test_flags_line() {
     let test_line = "  flags    : Scoped, Request A records, Request AAAA records";
-    let test_line = test_line.trim().split(':').last().map(|s| s.trim());
+    let test_line = test_line.trim().split(':').next_back().map(|s| s.trim());
     dbg!(&test_line);
     let test_line = test_line.expect("Failed to get tail");
     let res = test_line
         .split(',')
         .map(|s| ResolverFlags::from_str(s.trim()))
-        .collect::<Result<Vec<ResolverFlags>, String>>();
+        .collect::<Result<Vec<ResolverFlags>, Infallible>>();
     dbg!(&res);
     assert!(res.expect("failed to parse").len() == 3);
 }
+
+#[test]
+fn test_unknown_flags_are_kept() {
+    let input = "DNS configuration\n\nresolver #1\n  flags    : Scoped, Request A records, Some Future Flag\n\n";
+    let res = parse_text(input).expect("Failed to parse");
+    let flags = res.dns_config[0].flags.as_ref().expect("Should have flags");
+    dbg!(flags);
+    assert_eq!(flags.len(), 3);
+    assert!(matches!(flags[0], ResolverFlags::Scoped));
+    assert!(matches!(flags[1], ResolverFlags::RequestARecords));
+    assert!(matches!(&flags[2], ResolverFlags::Unknown(s) if s == "Some Future Flag"));
+    assert_eq!(flags[2].to_string(), "Some Future Flag");
+
+    #[cfg(feature = "serde")]
+    {
+        let json = serde_json::to_value(flags).expect("Failed to serialize");
+        assert_eq!(json[2]["Unknown"], "Some Future Flag");
+    }
+
+    // an unknown flag in the middle of the line doesn't take the others with it
+    let input = "DNS configuration\n\nresolver #1\n  flags    : Request A records, Some Future Flag, Request AAAA records\n\n";
+    let res = parse_text(input).expect("Failed to parse");
+    assert_eq!(res.dns_config[0].flags.as_ref().map(Vec::len), Some(3));
+    assert!(res
+        .to_scutil_string()
+        .contains("flags    : Request A records, Some Future Flag, Request AAAA records"));
+}
+
+#[test]
+fn test_reach_line() {
+    let res = ReachabilityFlags::from_str("0x00000002 (Reachable)").expect("Failed to parse reach");
+    assert_eq!(res.value, 2);
+    assert!(matches!(
+        res.flags.as_slice(),
+        [ReachabilityFlag::Reachable]
+    ));
+
+    let res =
+        ReachabilityFlags::from_str("0x00000000 (Not Reachable)").expect("Failed to parse reach");
+    assert_eq!(res.value, 0);
+    assert!(matches!(
+        res.flags.as_slice(),
+        [ReachabilityFlag::NotReachable]
+    ));
+
+    let res = ReachabilityFlags::from_str("0x00040002 (Reachable,Some Future Thing)")
+        .expect("Failed to parse reach");
+    assert_eq!(res.value, 0x00040002);
+    assert_eq!(res.flags.len(), 2);
+    assert!(matches!(&res.flags[1], ReachabilityFlag::Unknown(s) if s == "Some Future Thing"));
+
+    assert!(ReachabilityFlags::from_str("Reachable").is_err());
+
+    // some outputs only have the value
+    let res = ReachabilityFlags::from_str("0x00000007").expect("Failed to parse reach");
+    assert!(res.flags.is_empty());
+    assert!(res.is_reachable());
+    assert!(res.is_transient_connection());
+    assert!(res.connection_required());
+    assert!(!res.is_local_address());
+}
+
+#[test]
+fn test_reach_accessors() {
+    let res = ReachabilityFlags::from_str("0x00020002 (Reachable,Directly Reachable Address)")
+        .expect("Failed to parse reach");
+    assert!(res.is_reachable());
+    assert!(res.is_direct());
+    assert!(!res.is_local_address());
+    assert!(!res.connection_required());
+    #[cfg(feature = "serde")]
+    {
+        assert_eq!(
+            serde_json::to_value(&res).unwrap(),
+            serde_json::json!({
+                "value": 0x00020002,
+                "flags": ["Reachable", "DirectlyReachableAddress"],
+                "reachable": true,
+            })
+        );
+    }
+
+    let res = ReachabilityFlags::from_str("0x00000000 (Not Reachable)").unwrap();
+    assert!(!res.is_reachable());
+    #[cfg(feature = "serde")]
+    {
+        assert_eq!(serde_json::to_value(&res).unwrap()["reachable"], false);
+    }
+
+    let res = ReachabilityFlags::from_str("0x00010002 (Reachable,Local Address)").unwrap();
+    assert!(res.is_local_address());
+}
+
+#[test]
+fn test_parse_error_line_numbers() {
+    let input = "DNS configuration\n\nresolver #1\n  nameserver[0] : 10.0.0.1\n  what is this\n";
+    let err = parse_text(input).expect_err("Should fail on the unexpected line");
+    assert!(matches!(
+        &err,
+        ParseError::UnexpectedLine { line_no: 5, content } if content == "  what is this"
+    ));
+    assert_eq!(
+        err.to_string(),
+        "line 5: unexpected line: \"  what is this\""
+    );
+
+    let input = "DNS configuration\n\nresolver #1\n  nameserver[0] : 10.0.0.256\n";
+    let err = parse_text(input).expect_err("Should fail on the bad nameserver");
+    assert!(matches!(
+        err,
+        ParseError::InvalidNameserver { line_no: 4, .. }
+    ));
+
+    let input = "DNS configuration\n\nresolver #1\n  order : lots\n";
+    let err = parse_text(input).expect_err("Should fail on the bad order");
+    assert!(matches!(err, ParseError::InvalidInteger { line_no: 4, .. }));
+
+    let input = "DNS configuration\n\nresolver #\n";
+    let err = parse_text(input).expect_err("Should fail on the missing resolver index");
+    assert!(matches!(
+        err,
+        ParseError::MissingField {
+            line_no: 3,
+            field: "resolver index",
+            ..
+        }
+    ));
+}
+
+#[test]
+fn test_field_values_with_colons() {
+    let input = "DNS configuration\n\nresolver #1\n  domain   : fe80::1.example\n  options  : timeout:5\n  reach    : 0x00000002 (Reachable,Future: Thing)\n  order    : 1\n\n";
+    let res = parse_text(input).expect("Failed to parse");
+    let resolver = &res.dns_config[0];
+    assert_eq!(resolver.domain.as_deref(), Some("fe80::1.example"));
+    assert_eq!(
+        resolver.options.as_ref().map(|options| options.to_string()),
+        Some("timeout:5".to_string())
+    );
+    let reach = resolver.reach.as_ref().expect("Should have a reach value");
+    assert!(reach.is_reachable());
+    assert!(matches!(&reach.flags[1], ReachabilityFlag::Unknown(s) if s == "Future: Thing"));
+    assert_eq!(resolver.order, Some(1));
+
+    // the borrowed parser shares field_value, so it gets the same values
+    let borrowed = parse_text_borrowed(input).expect("Failed to parse borrowed");
+    assert_eq!(borrowed.to_owned(), res);
+}
+
+#[test]
+fn test_reach_unchanged_by_first_colon_split() {
+    // splitting on the last colon used to hand everything after `reach    :` to the reach
+    // parser, which is still the case for every reach line in the fixtures
+    for filename in [
+        "testdata.txt",
+        "testdata/vpn_supplemental.txt",
+        "testdata/link_local.txt",
+    ] {
+        let filecontents = std::fs::read_to_string(filename).expect("Could not read fixture");
+        let res = parse_text(&filecontents).expect("Could not parse fixture");
+        let expected: Vec<String> = filecontents
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("reach    : "))
+            .map(|reach| reach.trim().to_string())
+            .collect();
+        let parsed: Vec<String> = res
+            .dns_config
+            .iter()
+            .chain(res.scoped_dns_config.iter())
+            .filter_map(|resolver| resolver.reach.as_ref())
+            .map(|reach| reach.to_string())
+            .collect();
+        assert_eq!(parsed, expected);
+    }
+}
+
+#[test]
+fn test_options_line() {
+    let res = ResolverOptions::from_str("mdns, timeout=5 something-new").unwrap();
+    assert_eq!(
+        res.0,
+        vec![
+            ResolverOption::Mdns,
+            ResolverOption::Value {
+                key: "timeout".to_string(),
+                value: "5".to_string()
+            },
+            ResolverOption::Unknown("something-new".to_string())
+        ]
+    );
+    assert_eq!(res.value("timeout"), Some("5"));
+    assert_eq!(res.value("ndots"), None);
+    assert_eq!(res.to_string(), "mdns, timeout=5, something-new");
+
+    let res = ResolverOptions::from_str("pdns").unwrap();
+    assert_eq!(res.0, vec![ResolverOption::Pdns]);
+}
+
+#[test]
+fn test_recover_from_unexpected_line() {
+    let filecontents =
+        std::fs::read_to_string("testdata/corrupted_resolver.txt").expect("Could not read fixture");
+
+    let err = parse_text(&filecontents).expect_err("Strict parsing should fail");
+    assert!(matches!(
+        err,
+        ParseError::UnexpectedLine { line_no: 12, .. }
+    ));
+
+    let options = ParseOptions::new().recover(true);
+    let res = parse_text_with_options(&filecontents, &options).expect("Should recover");
+    dbg!(&res);
+
+    assert_eq!(res.warnings.len(), 1);
+    assert_eq!(res.warnings[0].line_no, 12);
+    assert_eq!(res.warnings[0].category, WarningCategory::SkippedResolver);
+    assert_eq!(
+        res.warnings[0].message,
+        "unexpected line, skipping resolver #2"
+    );
+
+    // the mangled block is left out, and the rest are kept
+    let config = res.config;
+    let ids: Vec<usize> = config.dns_config.iter().map(|r| r.id).collect();
+    assert_eq!(ids, vec![1, 3]);
+    assert_eq!(config.dns_config[1].order, Some(300200));
+    assert_eq!(config.scoped_dns_config.len(), 1);
+}
+
+#[test]
+fn test_recover_from_invalid_resolvers() {
+    let filecontents =
+        std::fs::read_to_string("testdata/truncated_resolver.txt").expect("Could not read fixture");
+
+    let err = parse_text(&filecontents).expect_err("Strict parsing should fail");
+    assert!(matches!(
+        err,
+        ParseError::InvalidNameserver { line_no: 19, .. }
+    ));
+
+    let options = ParseOptions::new().recover(true);
+    let res = parse_text_with_options(&filecontents, &options).expect("Should recover");
+    dbg!(&res.warnings);
+
+    // resolver #3 is skipped up to resolver #4, which follows it without a blank line
+    let ids: Vec<usize> = res.config.dns_config.iter().map(|r| r.id).collect();
+    assert_eq!(ids, vec![1, 2, 4, 5]);
+    assert_eq!(res.config.dns_config[2].domain.as_deref(), Some("local"));
+    assert_eq!(res.config.scoped_dns_config.len(), 2);
+
+    assert_eq!(res.warnings.len(), 1);
+    assert_eq!(res.warnings[0].category, WarningCategory::SkippedResolver);
+    assert_eq!(
+        res.warnings[0].to_string(),
+        "line 19: invalid nameserver address: invalid IP address syntax, skipping resolver #3: \"  nameserver[1] : 10.9.0.\""
+    );
+
+    // recovering is all or nothing for a block, unlike lenient mode
+    let input = "DNS configuration\n\nresolver #1\n  nameserver[0] : 10.0.0.1\n  order    : x\n\n";
+    let res = parse_text_with_options(input, &options).expect("Should recover");
+    assert!(res.config.dns_config.is_empty());
+    assert_eq!(res.warnings.len(), 1);
+
+    // a resolver line we can't read skips that block
+    let input = "DNS configuration\n\nresolver #x\n  nameserver[0] : 10.0.0.1\n\nresolver #2\n  nameserver[0] : 10.0.0.2\n\n";
+    let res = parse_text_with_options(input, &options).expect("Should recover");
+    let ids: Vec<usize> = res.config.dns_config.iter().map(|r| r.id).collect();
+    assert_eq!(ids, vec![2]);
+    assert_eq!(res.warnings.len(), 1);
+    assert_eq!(res.warnings[0].line_no, 3);
+}
+
+#[test]
+fn test_truncated_lines_are_errors() {
+    for line in [
+        "  nameserver[0]",
+        "  nameserver[0] :",
+        "  nameserver[99999999999999999999999] : 10.0.0.1",
+        "  if_index :",
+        "  if_index : en0",
+        "  reach    :",
+    ] {
+        let input = format!("DNS configuration\n\nresolver #1\n{}\n", line);
+        let err = parse_text(&input).expect_err("Truncated line should be an error");
+        dbg!(&err);
+        assert_eq!(err.line_no(), 4);
+        assert_eq!(err.content(), line);
+    }
+
+    for line in ["resolver #", "resolver", "resolver #x"] {
+        let input = format!("DNS configuration\n\n{}\n", line);
+        let err = parse_text(&input).expect_err("Truncated resolver header should be an error");
+        dbg!(&err);
+        assert_eq!(err.line_no(), 3);
+    }
+}
+
+#[test]
+fn test_invalid_integers() {
+    let input = "DNS configuration\n\nresolver #1\n  timeout  : soon\n  order    : 18446744073709551616\n  domain   : local\n  options  : mdns\n\n";
+
+    let err = parse_text(input).expect_err("Strict parsing should fail");
+    assert!(matches!(
+        &err,
+        ParseError::InvalidInteger { line_no: 4, content, .. } if content == "  timeout  : soon"
+    ));
+
+    let options = ParseOptions::new().lenient(true);
+    let res = parse_text_with_options(input, &options).expect("Lenient parsing should work");
+    assert_eq!(res.warnings.len(), 2);
+    assert_eq!(res.warnings[0].line_no, 4);
+    assert_eq!(res.warnings[1].line_no, 5);
+    assert_eq!(res.warnings[1].category, WarningCategory::InvalidValue);
+    let resolver = &res.config.dns_config[0];
+    assert_eq!(resolver.timeout, None);
+    assert_eq!(resolver.order, None);
+    assert_eq!(resolver.domain.as_deref(), Some("local"));
+}
+
+#[test]
+fn test_parse_options_strict_and_lenient() {
+    let filecontents =
+        std::fs::read_to_string("testdata/unknown_field.txt").expect("Could not read fixture");
+    let input = filecontents.replace(
+        "Request AAAA records",
+        "Request AAAA records, Some Future Flag",
+    );
+
+    let strict = ParseOptions::new();
+    let err = parse_text_with_options(&input, &strict).expect_err("Strict parsing should fail");
+    assert!(matches!(err, ParseError::UnexpectedLine { line_no: 6, .. }));
+
+    let lenient = ParseOptions::new().lenient(true);
+    let res = parse_text_with_options(&input, &lenient).expect("Lenient parsing should work");
+    assert_eq!(res.config.dns_config.len(), 2);
+    let categories: Vec<WarningCategory> = res
+        .warnings
+        .iter()
+        .map(|warning| warning.category)
+        .collect();
+    assert_eq!(
+        categories,
+        vec![
+            WarningCategory::UnknownField,
+            WarningCategory::UnknownFlag,
+            WarningCategory::UnexpectedLine
+        ]
+    );
+    assert!(
+        res.config.dns_config[0].has_flag(&ResolverFlags::Unknown("Some Future Flag".to_string()))
+    );
+
+    let res = parse_text_with_options(&input, &lenient.clone().skip_mdns(true))
+        .expect("Lenient parsing should work");
+    assert_eq!(res.config.dns_config.len(), 1);
+    assert!(!res.config.dns_config[0].is_mdns());
+
+    let err = parse_text_with_options(&input, &lenient.reject_unknown_flags(true))
+        .expect_err("Unknown flags should fail");
+    assert!(matches!(
+        err,
+        ParseError::InvalidValue { line_no: 7, ref message, .. } if message.contains("Some Future Flag")
+    ));
+}
+
+#[test]
+fn test_duplicate_resolver_warnings() {
+    let filecontents =
+        std::fs::read_to_string("testdata/duplicate_resolver.txt").expect("Could not read fixture");
+
+    let err = parse_text(&filecontents).expect_err("Duplicate resolvers should be an error");
+    assert!(matches!(
+        err,
+        ParseError::DuplicateResolver {
+            line_no: 15,
+            id: 2,
+            first_line_no: 9,
+            ..
+        }
+    ));
+    assert_eq!(
+        err.to_string(),
+        "line 15: resolver #2 appears more than once, first on line 9: \"resolver #2\""
+    );
+
+    let res = parse_text_with_options(&filecontents, &ParseOptions::new().lenient(true))
+        .expect("Could not parse fixture");
+    dbg!(&res.warnings);
+
+    assert_eq!(res.config.dns_config.len(), 4);
+    let warnings: Vec<(usize, WarningCategory)> = res
+        .warnings
+        .iter()
+        .map(|warning| (warning.line_no, warning.category))
+        .collect();
+    assert_eq!(
+        warnings,
+        vec![
+            (15, WarningCategory::DuplicateResolver),
+            (21, WarningCategory::NoNameservers)
+        ]
+    );
+    assert_eq!(res.warnings[0].content, "resolver #2");
+    assert_eq!(
+        res.warnings[0].to_string(),
+        "line 15: resolver #2 appears more than once, first on line 9: \"resolver #2\""
+    );
+    // lookups by index return the first one
+    let resolver = res.config.resolver(2).expect("Should find resolver #2");
+    assert!(std::ptr::eq(resolver, &res.config.dns_config[1]));
+
+    // recovering leaves the second one out
+    let res = parse_text_with_options(&filecontents, &ParseOptions::new().recover(true))
+        .expect("Could not parse fixture");
+    let ids: Vec<usize> = res.config.dns_config.iter().map(|r| r.id).collect();
+    assert_eq!(ids, vec![1, 2, 3]);
+    assert_eq!(res.warnings[0].category, WarningCategory::SkippedResolver);
+
+    // the same index in different sections is fine
+    let filecontents = std::fs::read_to_string("testdata.txt").expect("Could not read fixture");
+    let res = parse_text_with_options(&filecontents, &ParseOptions::new()).unwrap();
+    assert!(res.warnings.is_empty());
+}
+
+#[test]
+fn test_resolver_builder() {
+    let filecontents = std::fs::read_to_string("testdata.txt").expect("Could not read fixture");
+    let res = parse_text(&filecontents).expect("Could not parse fixture");
+
+    let expected = Resolver::builder(1)
+        .search_domain("subdomain.example.com")
+        .nameserver_at(2, "10.0.0.1".parse::<IpAddr>().unwrap())
+        .nameserver_at(
+            0,
+            "1234:1234:fd:0:ffe:66ff:2345:b33f"
+                .parse::<IpAddr>()
+                .unwrap(),
+        )
+        .nameserver_at(1, "10.0.0.12".parse::<IpAddr>().unwrap())
+        .if_index(15, Some("en0"))
+        .flag(ResolverFlags::RequestARecords)
+        .flag(ResolverFlags::RequestAAAARecords)
+        .reach(
+            ReachabilityFlags::from_str("0x00020002 (Reachable,Directly Reachable Address)")
+                .unwrap(),
+        )
+        .build()
+        .unwrap();
+    assert_eq!(res.dns_config[0], expected);
+
+    let expected = Resolver::builder(2)
+        .domain("local")
+        .option(ResolverOption::Mdns)
+        .timeout(Duration::from_secs(5))
+        .flag(ResolverFlags::RequestARecords)
+        .flag(ResolverFlags::RequestAAAARecords)
+        .reach(ReachabilityFlags::from_str("0x00000000 (Not Reachable)").unwrap())
+        .order(300000)
+        .build()
+        .unwrap();
+    assert_eq!(res.dns_config[1], expected);
+
+    // replaces the nameserver at the same index
+    let resolver = Resolver::builder(1)
+        .nameserver_at(0, "10.0.0.1".parse::<IpAddr>().unwrap())
+        .nameserver_at(0, "10.0.0.2".parse::<IpAddr>().unwrap())
+        .build()
+        .unwrap();
+    assert_eq!(resolver.nameservers.len(), 1);
+    assert_eq!(resolver.nameservers[0].to_string(), "10.0.0.2");
+    assert!(resolver.flags.is_none());
+
+    // nameserver carries on after the highest index
+    let resolver = Resolver::builder(1)
+        .nameserver_at(3, IpAddr::from([10, 0, 0, 3]))
+        .nameserver(IpAddr::from([10, 0, 0, 4]))
+        .timeout_secs(5)
+        .if_index(15, Some("en0"))
+        .scoped()
+        .scoped()
+        .build()
+        .unwrap();
+    let indexes: Vec<usize> = resolver.nameservers.iter().map(|ns| ns.index).collect();
+    assert_eq!(indexes, vec![3, 4]);
+    assert_eq!(resolver.timeout, Some(Duration::from_secs(5)));
+    assert_eq!(resolver.flags, Some(vec![ResolverFlags::Scoped]));
+}
+
+#[test]
+fn test_resolver_builder_checks() {
+    let mdns = || {
+        Resolver::builder(2)
+            .domain("local")
+            .option(ResolverOption::Mdns)
+            .nameserver(IpAddr::from([224, 0, 0, 251]))
+    };
+    assert_eq!(mdns().build(), Err(BuildError::MdnsWithNameservers(2)));
+    assert!(mdns().allow_mdns_nameservers(true).build().is_ok());
+    assert!(mdns().build_unchecked().has_nameservers());
+
+    let err = Resolver::builder(1).scoped().build().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "resolver #1 is scoped but doesn't have an if_index"
+    );
+
+    let err = DNSConfig::builder()
+        .resolver(ResolverBuilder::default())
+        .resolver(mdns())
+        .build()
+        .unwrap_err();
+    // renumbered as it's the second resolver in the section
+    assert_eq!(err, BuildError::MdnsWithNameservers(2));
+}
+
+#[test]
+fn test_dns_config_builder() {
+    let filecontents = std::fs::read_to_string("testdata.txt").expect("Could not read fixture");
+    let res = parse_text(&filecontents).expect("Could not parse fixture");
+
+    let mdns = |domain: &str, order: usize| {
+        ResolverBuilder::default()
+            .domain(domain)
+            .option(ResolverOption::Mdns)
+            .timeout_secs(5)
+            .flag(ResolverFlags::RequestARecords)
+            .flag(ResolverFlags::RequestAAAARecords)
+            .reach(ReachabilityFlags::from_str("0x00000000 (Not Reachable)").unwrap())
+            .order(order)
+    };
+    let default = ResolverBuilder::default()
+        .search_domain("subdomain.example.com")
+        .nameserver(
+            "1234:1234:fd:0:ffe:66ff:2345:b33f"
+                .parse::<IpAddr>()
+                .unwrap(),
+        )
+        .nameserver(IpAddr::from([10, 0, 0, 12]))
+        .nameserver(IpAddr::from([10, 0, 0, 1]))
+        .if_index(15, Some("en0"))
+        .flag(ResolverFlags::RequestARecords)
+        .flag(ResolverFlags::RequestAAAARecords)
+        .reach(
+            ReachabilityFlags::from_str("0x00020002 (Reachable,Directly Reachable Address)")
+                .unwrap(),
+        );
+
+    let mut builder = DNSConfig::builder().resolver(default);
+    for (domain, order) in [
+        ("local", 300000),
+        ("254.169.in-addr.arpa", 300200),
+        ("8.e.f.ip6.arpa", 300400),
+        ("9.e.f.ip6.arpa", 300600),
+        ("a.e.f.ip6.arpa", 300800),
+        ("b.e.f.ip6.arpa", 301000),
+    ] {
+        builder = builder.resolver(mdns(domain, order));
+    }
+    // the Scoped flag goes first in scutil's output, so it's added here rather than by
+    // scoped_resolver
+    let built = builder
+        .scoped_resolver(
+            ResolverBuilder::default()
+                .scoped()
+                .search_domain("subdomain.example.com")
+                .nameserver(
+                    "1234:1234:fd:0:ffe:66ff:2345:b33f"
+                        .parse::<IpAddr>()
+                        .unwrap(),
+                )
+                .nameserver(IpAddr::from([10, 0, 0, 12]))
+                .nameserver(IpAddr::from([10, 0, 0, 1]))
+                .if_index(15, Some("en0"))
+                .flag(ResolverFlags::RequestARecords)
+                .flag(ResolverFlags::RequestAAAARecords)
+                .reach(
+                    ReachabilityFlags::from_str(
+                        "0x00020002 (Reachable,Directly Reachable Address)",
+                    )
+                    .unwrap(),
+                ),
+        )
+        .build()
+        .expect("Failed to build");
+    assert_eq!(built, res);
+    assert_eq!(built.to_string(), res.to_string());
+}
+
+#[test]
+fn test_hex_flags() {
+    let input = "DNS configuration\n\nresolver #1\n  nameserver[0] : 10.0.0.1\n  flags    : 0x00006102\n  order    : 1\n";
+    let res = parse_text(input).expect("Could not parse hex flags");
+    let resolver = &res.dns_config[0];
+    dbg!(resolver);
+    assert_eq!(resolver.flags_value, Some(0x6102));
+    assert_eq!(
+        resolver.flags,
+        Some(vec![
+            ResolverFlags::ServiceSpecific,
+            ResolverFlags::RequestARecords,
+            ResolverFlags::RequestAAAARecords,
+            ResolverFlags::Unknown("0x100".to_string()),
+        ])
+    );
+    assert_eq!(
+        resolver,
+        &Resolver::builder(1)
+            .nameserver("10.0.0.1".parse::<IpAddr>().unwrap())
+            .flags_value(0x6102)
+            .order(1)
+            .build()
+            .unwrap()
+    );
+    assert_eq!(
+        parse_text_borrowed(input).unwrap().to_owned(),
+        res,
+        "borrowed parse should match"
+    );
+
+    // rendered the way it was written
+    let rendered = res.to_scutil_string();
+    assert!(rendered.contains("  flags    : 0x00006102\n"));
+    assert_eq!(parse_text(&rendered).unwrap(), res);
+
+    #[cfg(feature = "serde")]
+    {
+        let json = serde_json::to_value(&res).expect("Failed to serialize");
+        assert_eq!(json["dns_config"][0]["flags_value"], 0x6102);
+        assert_eq!(json["dns_config"][0]["flags"][0], "ServiceSpecific");
+    }
+
+    // the names don't have a value
+    let filecontents = std::fs::read_to_string("testdata.txt").expect("Could not read fixture");
+    let res = parse_text(&filecontents).unwrap();
+    assert!(res
+        .dns_config
+        .iter()
+        .all(|resolver| resolver.flags_value.is_none()));
+
+    let input = "DNS configuration\n\nresolver #1\n  nameserver[0] : 10.0.0.1\n  flags    : 0xzz\n";
+    assert!(matches!(
+        parse_text(input),
+        Err(ParseError::InvalidInteger { line_no: 5, .. })
+    ));
+    assert!(parse_text_borrowed(input).is_err());
+    let (res, warnings) = parse_text_with_warnings(input).expect("Lenient parsing should work");
+    assert_eq!(res.dns_config[0].flags, None);
+    assert_eq!(warnings[0].category, WarningCategory::InvalidValue);
+}
+
+#[test]
+fn test_normalize() {
+    let filecontents = std::fs::read_to_string("testdata.txt").expect("Could not read fixture");
+    let options = ParseOptions::new().keep_raw_lines(true);
+    let mut res = parse_text_with_options(&filecontents, &options)
+        .expect("Could not parse fixture")
+        .config;
+
+    // another capture of the same config, in a different order and with the network changed
+    let mut shuffled = res.clone();
+    shuffled.dns_config.reverse();
+    shuffled.scoped_dns_config.reverse();
+    for resolver in &mut shuffled.dns_config {
+        resolver.nameservers.reverse();
+        resolver.reach = Some(ReachabilityFlags::from_str("0x00000000 (Not Reachable)").unwrap());
+    }
+    assert_ne!(shuffled, res);
+
+    res.normalize();
+    shuffled.normalize();
+    assert_ne!(shuffled, res);
+    assert!(res
+        .dns_config
+        .windows(2)
+        .all(|pair| pair[0].id < pair[1].id));
+    assert!(res
+        .dns_config
+        .iter()
+        .all(|resolver| resolver.raw_lines.is_none()));
+
+    res.strip_reach();
+    shuffled.strip_reach();
+    assert_eq!(shuffled, res);
+    #[cfg(feature = "serde")]
+    {
+        let json = serde_json::to_value(&res).expect("Failed to serialize");
+        assert!(json["dns_config"][0]["reach"].is_null());
+        assert_eq!(
+            json["dns_config"][0]["nameservers"][0]["address"],
+            "1234:1234:fd:0:ffe:66ff:2345:b33f"
+        );
+    }
+}
+
+#[test]
+fn test_resolver_lookup() {
+    // gaps in the numbering are allowed
+    let input = "DNS configuration\n\nresolver #1\n  nameserver[0] : 10.0.0.1\n\nresolver #2\n  nameserver[0] : 10.0.0.2\n\nresolver #5\n  nameserver[0] : 10.0.0.5\n\nDNS configuration (for scoped queries)\n\nresolver #1\n  nameserver[0] : 10.0.1.1\n  flags    : Scoped\n\n";
+    let res = parse_text(input).expect("Failed to parse");
+
+    let resolver = res.resolver(5).expect("Should find resolver #5");
+    assert_eq!(resolver.nameservers[0].to_string(), "10.0.0.5");
+    assert!(res.resolver(3).is_none());
+    assert_eq!(
+        res.resolver(1).map(|r| r.nameservers[0].to_string()),
+        Some("10.0.0.1".to_string())
+    );
+    assert_eq!(
+        res.scoped_resolver(1).map(|r| r.nameservers[0].to_string()),
+        Some("10.0.1.1".to_string())
+    );
+    assert!(res.scoped_resolver(2).is_none());
+}
+
+#[test]
+fn test_lenient_unknown_field() {
+    let filecontents =
+        std::fs::read_to_string("testdata/unknown_field.txt").expect("Could not read fixture");
+
+    let err = parse_text(&filecontents).expect_err("Strict parsing should fail");
+    assert!(matches!(err, ParseError::UnexpectedLine { line_no: 6, .. }));
+
+    let res = parse_text_lenient(&filecontents).expect("Lenient parsing should work");
+    dbg!(&res);
+    assert_eq!(res.dns_config.len(), 2);
+    assert_eq!(
+        res.dns_config[0]
+            .extra
+            .get("shiny new field")
+            .map(String::as_str),
+        Some("42")
+    );
+    assert_eq!(res.dns_config[0].flags.as_ref().map(Vec::len), Some(2));
+    assert!(res.dns_config[1].extra.is_empty());
+
+    #[cfg(feature = "serde")]
+    {
+        let json = serde_json::to_value(&res).expect("Failed to serialize");
+        assert_eq!(json["dns_config"][0]["extra"]["shiny new field"], "42");
+        assert!(json["dns_config"][1].get("extra").is_none());
+    }
+}
+
+#[test]
+fn test_lenient_prefixed_keys() {
+    // new keys that start like ones we know about shouldn't be parsed as them
+    let input = "DNS configuration\n\nresolver #1\n  nameserver[0] : 10.0.0.1\n  domain   : corp.example.com\n  order    : 200\n  ordering : 5\n  portal_mode : x\n  searchable : yes\n  reachability_note : hi\n  domains_extra : foo\n  configuration_key : abc\n  flagship : 1\n\n";
+    let err = parse_text(input).expect_err("Strict parsing should fail");
+    assert!(matches!(err, ParseError::UnexpectedLine { line_no: 7, .. }));
+
+    let res = parse_text_lenient(input).expect("Lenient parsing should work");
+    let resolver = &res.dns_config[0];
+    assert_eq!(resolver.order, Some(200));
+    assert_eq!(resolver.domain.as_deref(), Some("corp.example.com"));
+    let mut keys: Vec<&str> = resolver.extra.keys().map(String::as_str).collect();
+    keys.sort();
+    assert_eq!(
+        keys,
+        vec![
+            "configuration_key",
+            "domains_extra",
+            "flagship",
+            "ordering",
+            "portal_mode",
+            "reachability_note",
+            "searchable"
+        ]
+    );
+}
+
+#[test]
+fn test_parse_text_with_warnings() {
+    let filecontents =
+        std::fs::read_to_string("testdata/unknown_field.txt").expect("Could not read fixture");
+    let (config, warnings) =
+        parse_text_with_warnings(&filecontents).expect("Parsing with warnings should work");
+    dbg!(&warnings);
+    assert_eq!(config, parse_text_lenient(&filecontents).unwrap());
+    let found: Vec<(usize, WarningCategory)> = warnings
+        .iter()
+        .map(|warning| (warning.line_no, warning.category))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (6, WarningCategory::UnknownField),
+            (10, WarningCategory::UnexpectedLine)
+        ]
+    );
+    assert_eq!(
+        warnings[0].message,
+        "unknown field \"shiny new field\", keeping it in extra"
+    );
+}
+
+#[test]
+fn test_raw_lines() {
+    let filecontents = std::fs::read_to_string("testdata.txt").expect("Could not read fixture");
+    let options = ParseOptions::new().keep_raw_lines(true);
+    let res = parse_text_with_options(&filecontents, &options)
+        .expect("Could not parse fixture")
+        .config;
+
+    let raw_lines = res.dns_config[0]
+        .raw_lines
+        .as_ref()
+        .expect("Should have kept the lines");
+    dbg!(raw_lines);
+    let expected: Vec<&str> = filecontents.lines().skip(2).take(8).collect();
+    assert_eq!(raw_lines, &expected);
+    assert!(res.scoped_dns_config.iter().all(|resolver| resolver
+        .raw_lines
+        .as_ref()
+        .is_some_and(|lines| lines[0] == format!("resolver #{}", resolver.id))));
+
+    // off by default, and left out of the JSON
+    let res = parse_text(&filecontents).unwrap();
+    assert!(res
+        .dns_config
+        .iter()
+        .all(|resolver| resolver.raw_lines.is_none()));
+    #[cfg(feature = "serde")]
+    {
+        let json = serde_json::to_value(&res).expect("Failed to serialize");
+        assert!(json["dns_config"][0].get("raw_lines").is_none());
+    }
+}
+
+#[test]
+fn test_config_metadata() {
+    let input = "DNS configuration\n  order    : 1\nresolver count : 2\n\nresolver #1\n  nameserver[0] : 10.0.0.1\n\nDNS configuration (for scoped queries)\n  order    : 2\n\nresolver #1\n  nameserver[0] : 10.0.0.1\n  flags    : Scoped\n\n";
+    let res = parse_text(input).expect("Metadata lines should be accepted");
+    dbg!(&res);
+
+    assert_eq!(res.resolver_count(), 2);
+    assert_eq!(res.section(SectionKind::Scoped).len(), 1);
+    let kinds: Vec<(SectionKind, usize)> = res
+        .sections()
+        .map(|(kind, resolvers)| (kind, resolvers.len()))
+        .collect();
+    assert_eq!(
+        kinds,
+        vec![
+            (SectionKind::Unscoped, 1),
+            (SectionKind::Scoped, 1),
+            (SectionKind::ServiceSpecific, 0)
+        ]
+    );
+    assert_eq!(
+        SectionKind::Scoped.header(),
+        "DNS configuration (for scoped queries)"
+    );
+
+    // they don't end up in the resolvers
+    assert_eq!(res.dns_config[0].order, None);
+    let metadata = |kind: SectionKind| -> Vec<(&str, &str)> {
+        res.section_metadata(kind.header())
+            .into_iter()
+            .flatten()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .collect()
+    };
+    assert_eq!(
+        metadata(SectionKind::Unscoped),
+        vec![("order", "1"), ("resolver count", "2")]
+    );
+    // each section keeps its own
+    assert_eq!(metadata(SectionKind::Scoped), vec![("order", "2")]);
+    assert!(res
+        .to_scutil_string()
+        .contains("DNS configuration (for scoped queries)\norder : 2\n\nresolver #1\n"));
+
+    assert_eq!(parse_text(&res.to_scutil_string()).unwrap(), res);
+    assert_eq!(parse_text_borrowed(input).unwrap().to_owned(), res);
+    #[cfg(feature = "serde")]
+    {
+        let json = serde_json::to_value(&res).expect("Failed to serialize");
+        assert_eq!(json["metadata"]["DNS configuration"]["order"], "1");
+    }
+
+    // outside of a section they're still unexpected
+    let err = parse_text("  order : 1\n").expect_err("Should fail");
+    assert!(matches!(err, ParseError::UnexpectedLine { line_no: 1, .. }));
+}
+
+#[test]
+fn test_config_metadata_after_resolver() {
+    // fields split from their resolver by a blank line aren't metadata
+    let input = "DNS configuration\n\nresolver #1\n  nameserver[0] : 10.0.0.1\n\n  nameserver[1] : 10.0.0.2\n  domain : evil.com\n\n";
+    let err = parse_text(input).expect_err("Strict parsing should fail");
+    assert!(matches!(err, ParseError::UnexpectedLine { line_no: 6, .. }));
+    let err = parse_text_borrowed(input).expect_err("Borrowed parsing should fail");
+    assert!(matches!(err, ParseError::UnexpectedLine { line_no: 6, .. }));
+
+    let res = parse_text_with_options(input, &ParseOptions::new().lenient(true))
+        .expect("Lenient parsing should work");
+    assert!(res.config.metadata.is_empty());
+    assert_eq!(res.config.dns_config[0].nameservers.len(), 1);
+    let warnings: Vec<(usize, WarningCategory)> = res
+        .warnings
+        .iter()
+        .map(|warning| (warning.line_no, warning.category))
+        .collect();
+    assert_eq!(
+        warnings,
+        vec![
+            (6, WarningCategory::UnexpectedLine),
+            (7, WarningCategory::UnexpectedLine)
+        ]
+    );
+
+    let res = parse_text_with_options(input, &ParseOptions::new().recover(true))
+        .expect("Recovering should work");
+    assert!(res.config.metadata.is_empty());
+    assert_eq!(res.warnings.len(), 1);
+    assert_eq!(res.warnings[0].line_no, 6);
+}
+
+#[test]
+fn test_system_nameservers() {
+    let filecontents = std::fs::read_to_string("testdata.txt").expect("Could not read fixture");
+    let res = parse_text(&filecontents).expect("Could not parse fixture");
+
+    let expected: Vec<IpAddr> = ["1234:1234:fd:0:ffe:66ff:2345:b33f", "10.0.0.12", "10.0.0.1"]
+        .iter()
+        .map(|ip| ip.parse().unwrap())
+        .collect();
+    assert_eq!(res.system_nameservers(), expected);
+}
+
+#[test]
+fn test_system_nameservers_uses_order() {
+    let input = "DNS configuration\n\nresolver #1\n  nameserver[0] : 10.0.0.1\n  order    : 200\n\nresolver #2\n  nameserver[0] : 10.0.0.2\n  order    : 100\n\nresolver #3\n  domain   : local\n  nameserver[0] : 10.0.0.3\n  order    : 1\n\n";
+    let res = parse_text(input).expect("Could not parse input");
+    assert_eq!(
+        res.system_nameservers(),
+        vec!["10.0.0.2".parse::<IpAddr>().unwrap()]
+    );
+}
+
+#[test]
+fn test_all_nameservers() {
+    let filecontents = std::fs::read_to_string("testdata.txt").expect("Could not read fixture");
+    let res = parse_text(&filecontents).expect("Failed to parse");
+    let found: Vec<String> = res
+        .all_nameservers()
+        .iter()
+        .map(|address| address.to_string())
+        .collect();
+    // resolver #1 and its scoped copy share their nameservers
+    assert_eq!(
+        found,
+        vec!["1234:1234:fd:0:ffe:66ff:2345:b33f", "10.0.0.12", "10.0.0.1"]
+    );
+    let sources: Vec<(String, Vec<(&str, usize)>)> = res
+        .all_nameservers_with_sources()
+        .into_iter()
+        .map(|(address, sources)| {
+            let sources = sources
+                .iter()
+                .map(|source| (source.section, source.resolver.id))
+                .collect();
+            (address.to_string(), sources)
+        })
+        .collect();
+    assert_eq!(
+        sources[2],
+        (
+            "10.0.0.1".to_string(),
+            vec![
+                ("DNS configuration", 1),
+                ("DNS configuration (for scoped queries)", 1)
+            ]
+        )
+    );
+
+    let input = "DNS configuration\n\nresolver #1\n  nameserver[0] : 192.168.1.1\n\nDNS configuration (for scoped queries)\n\nresolver #1\n  nameserver[0] : 192.168.1.1\n  nameserver[1] : 203.0.113.53\n  if_index : 22 (utun3)\n  flags    : Scoped\n\n";
+    let res = parse_text(input).expect("Failed to parse");
+    assert_eq!(
+        res.all_nameservers(),
+        vec![
+            "192.168.1.1".parse::<IpAddr>().unwrap(),
+            "203.0.113.53".parse::<IpAddr>().unwrap()
+        ]
+    );
+    let untrusted: IpAddr = "203.0.113.53".parse().unwrap();
+    let (_, sources) = res
+        .all_nameservers_with_sources()
+        .into_iter()
+        .find(|(address, _)| *address == untrusted)
+        .expect("Should find the untrusted nameserver");
+    assert_eq!(
+        sources[0].resolver.if_index.as_ref().map(|i| i.index),
+        Some(22)
+    );
+}
+
+#[test]
+fn test_default_resolver() {
+    let filecontents = std::fs::read_to_string("testdata.txt").expect("Could not read fixture");
+    let res = parse_text(&filecontents).expect("Failed to parse");
+    let resolver = res.default_resolver().expect("No default resolver");
+    assert_eq!(resolver.id, 1);
+    assert!(resolver.domain.is_none());
+
+    // the lowest order wins, and resolvers without one sort last
+    let input = "DNS configuration\n\nresolver #1\n  nameserver[0] : 192.168.1.1\n\nresolver #2\n  domain   : example.com\n  nameserver[0] : 10.8.0.1\n  order    : 1\n\nresolver #3\n  nameserver[0] : 10.0.0.1\n  order    : 200000\n\nresolver #4\n  nameserver[0] : 10.0.0.2\n  order    : 100000\n\n";
+    let res = parse_text(input).expect("Failed to parse");
+    assert_eq!(res.default_resolver().map(|resolver| resolver.id), Some(4));
+
+    // scoped resolvers don't count
+    let input = "DNS configuration\n\nresolver #1\n  domain   : local\n  options  : mdns\n\nDNS configuration (for scoped queries)\n\nresolver #1\n  nameserver[0] : 192.168.1.1\n  if_index : 15 (en0)\n  flags    : Scoped\n\n";
+    let res = parse_text(input).expect("Failed to parse");
+    assert!(res.default_resolver().is_none());
+}
+
+#[test]
+fn test_sort_by_order() {
+    let input = "DNS configuration\n\nresolver #1\n  nameserver[0] : 192.168.1.1\n  order    : 200000\n\nresolver #2\n  domain   : local\n  options  : mdns\n\nresolver #3\n  domain   : example.com\n  nameserver[0] : 10.8.0.1\n  order    : 100\n\nresolver #4\n  domain   : example.org\n  nameserver[0] : 10.8.0.2\n  order    : 200000\n\nresolver #5\n  domain   : example.net\n  nameserver[0] : 10.8.0.3\n  order    : 100\n\nDNS configuration (for scoped queries)\n\nresolver #1\n  nameserver[0] : 192.168.1.1\n  if_index : 15 (en0)\n  flags    : Scoped\n  order    : 2\n\nresolver #2\n  nameserver[0] : 10.8.0.1\n  if_index : 22 (utun3)\n  flags    : Scoped\n  order    : 1\n\n";
+    let mut res = parse_text(input).expect("Failed to parse");
+    res.sort_by_order();
+    let ids = |section: &[Resolver]| section.iter().map(|r| r.id).collect::<Vec<_>>();
+    // ties keep their input order, and no order goes last
+    assert_eq!(ids(&res.dns_config), vec![3, 5, 1, 4, 2]);
+    assert_eq!(ids(&res.scoped_dns_config), vec![2, 1]);
+
+    // sorting twice doesn't change anything
+    let sorted = res.clone();
+    res.sort_by_order();
+    assert_eq!(res, sorted);
+}
+
+#[test]
+fn test_resolver_for_domain() {
+    let input = "DNS configuration\n\nresolver #1\n  nameserver[0] : 192.168.1.1\n  order    : 200000\n\nresolver #2\n  domain   : example.com\n  nameserver[0] : 10.8.0.1\n  order    : 100000\n\nresolver #3\n  domain   : corp.example.com\n  nameserver[0] : 10.8.0.2\n  order    : 100001\n\nresolver #4\n  domain   : corp.example.com\n  nameserver[0] : 10.8.0.3\n  order    : 100\n\n";
+    let res = parse_text(input).expect("Failed to parse");
+    let id = |host: &str| res.resolver_for_domain(host).map(|resolver| resolver.id);
+
+    // exact match, with the lowest order winning
+    assert_eq!(id("corp.example.com"), Some(4));
+    assert_eq!(id("CORP.example.com."), Some(4));
+    // the longest suffix wins
+    assert_eq!(id("db.corp.example.com"), Some(4));
+    assert_eq!(id("www.example.com"), Some(2));
+    // a suffix has to start at a label
+    assert_eq!(id("notexample.com"), Some(1));
+    // the default resolver
+    assert_eq!(id("example.org"), Some(1));
+
+    let ids = |host: &str| -> Vec<usize> {
+        res.resolvers_for_domain(host)
+            .iter()
+            .map(|resolver| resolver.id)
+            .collect()
+    };
+    assert_eq!(ids("db.corp.example.com"), vec![4, 3, 2, 1]);
+    assert_eq!(ids("www.example.com"), vec![2, 1]);
+    assert_eq!(ids("example.org"), vec![1]);
+
+    let res =
+        parse_text("DNS configuration\n\nresolver #1\n  domain   : local\n  options  : mdns\n\n")
+            .unwrap();
+    assert!(res.resolver_for_domain("example.org").is_none());
+}
+
+#[test]
+fn test_empty_flags() {
+    let parse_flags = |line: &str| {
+        let input = format!(
+            "DNS configuration\n\nresolver #1\n  nameserver[0] : 10.0.0.1\n{}\n\n",
+            line
+        );
+        let res = parse_text(&input).expect(line);
+        res.dns_config[0].flags.clone()
+    };
+    assert_eq!(parse_flags("  flags    : "), Some(vec![]));
+    assert_eq!(parse_flags("  flags    :"), Some(vec![]));
+    assert_eq!(parse_flags("  flags :\t "), Some(vec![]));
+    assert_eq!(parse_flags("  order    : 1"), None);
+    assert_eq!(
+        parse_flags("  flags    : Scoped, , Request A records,,"),
+        Some(vec![ResolverFlags::Scoped, ResolverFlags::RequestARecords])
+    );
+
+    // an empty line is kept when rendering, so it survives a round trip
+    let input = "DNS configuration\n\nresolver #1\n  nameserver[0] : 10.0.0.1\n  flags    :\n\n";
+    let res = parse_text(input).expect("Failed to parse");
+    assert!(res.to_scutil_string().contains("  flags    : \n"));
+    assert_eq!(parse_text(&res.to_scutil_string()).unwrap(), res);
+    #[cfg(feature = "serde")]
+    {
+        let json = serde_json::to_value(&res).unwrap();
+        assert_eq!(json["dns_config"][0]["flags"], serde_json::json!([]));
+    }
+}
+
+#[test]
+fn test_flags_round_trip() {
+    for flag in [
+        "Request A records",
+        "Request AAAA records",
+        "Scoped",
+        "Service-specific",
+        "Supplemental",
+        "Some Future Flag",
+    ] {
+        let parsed = ResolverFlags::from_str(flag).unwrap();
+        assert_eq!(parsed.to_string(), flag);
+        assert_eq!(
+            matches!(parsed, ResolverFlags::Unknown(_)),
+            flag == "Some Future Flag"
+        );
+    }
+}
+
+#[test]
+fn test_supplemental_flags_line_is_kept() {
+    let input = "DNS configuration\n\nresolver #1\n  domain   : corp.example.com\n  nameserver[0] : 10.8.0.1\n  flags    : Supplemental, Request A records, Request AAAA records\n\n";
+    let res = parse_text(input).expect("Failed to parse");
+    let flags: Vec<String> = res.dns_config[0]
+        .flags
+        .iter()
+        .flatten()
+        .map(|flag| flag.to_string())
+        .collect();
+    assert_eq!(
+        flags,
+        vec!["Supplemental", "Request A records", "Request AAAA records"]
+    );
+}
+
+#[test]
+fn test_vpn_supplemental_flags() {
+    let filecontents =
+        std::fs::read_to_string("testdata/vpn_supplemental.txt").expect("Could not read fixture");
+    let res = parse_text(&filecontents).expect("Could not parse fixture");
+    dbg!(&res);
+
+    assert_eq!(res.dns_config.len(), 4);
+    assert_eq!(res.scoped_dns_config.len(), 2);
+
+    let corp = &res.dns_config[1];
+    assert_eq!(corp.domain.as_deref(), Some("corp.example.com"));
+    assert!(matches!(
+        corp.flags.as_deref().unwrap_or_default(),
+        [
+            ResolverFlags::Supplemental,
+            ResolverFlags::RequestARecords,
+            ResolverFlags::RequestAAAARecords
+        ]
+    ));
+    assert!(res.scoped_dns_config[1].has_flag(&ResolverFlags::ServiceSpecific));
+
+    #[cfg(feature = "serde")]
+    {
+        let json = serde_json::to_value(&res).expect("Failed to serialize");
+        assert_eq!(json["dns_config"][1]["flags"][0], "Supplemental");
+        assert_eq!(json["scoped_dns_config"][1]["flags"][1], "ServiceSpecific");
+    }
+}
+
+#[test]
+fn test_resolver_predicates() {
+    let filecontents = std::fs::read_to_string("testdata.txt").expect("Could not read fixture");
+    let res = parse_text(&filecontents).expect("Could not parse fixture");
+
+    let default = &res.dns_config[0];
+    assert!(default.has_nameservers());
+    assert!(!default.is_mdns());
+    assert!(!default.is_scoped());
+    assert!(!default.is_supplemental());
+    // local, 254.169.in-addr.arpa and the link-local ip6.arpa zones
+    for resolver in &res.dns_config[1..] {
+        assert!(resolver.is_mdns(), "resolver #{}", resolver.id);
+        assert!(!resolver.has_nameservers());
+        assert!(!resolver.is_scoped());
+        assert!(resolver.is_supplemental());
+    }
+    assert!(res.scoped_dns_config[0].is_scoped());
+    assert!(!res.scoped_dns_config[0].is_supplemental());
+
+    let filecontents =
+        std::fs::read_to_string("testdata/vpn_supplemental.txt").expect("Could not read fixture");
+    let res = parse_text(&filecontents).expect("Could not parse fixture");
+    // the flag counts even though the VPN resolvers have an if_index
+    assert!(res.dns_config[1].is_supplemental());
+    // a reverse zone with nameservers is a real resolver
+    assert_eq!(
+        res.dns_config[2].domain.as_deref(),
+        Some("8.10.in-addr.arpa")
+    );
+    assert!(!res.dns_config[2].is_mdns());
+    assert!(res.scoped_dns_config.iter().all(Resolver::is_scoped));
+
+    // without the mdns option, it's down to the domain and having no nameservers
+    let placeholder = Resolver::builder(1).domain("Local.").build().unwrap();
+    assert!(placeholder.is_mdns());
+    let placeholder = Resolver::builder(1)
+        .domain("254.169.in-addr.arpa")
+        .build()
+        .unwrap();
+    assert!(placeholder.is_mdns());
+    assert!(!Resolver::builder(1)
+        .domain("notlocal")
+        .build()
+        .unwrap()
+        .is_mdns());
+    assert!(!Resolver::builder(1)
+        .domain("printers.local")
+        .nameserver(IpAddr::from([10, 0, 0, 53]))
+        .build()
+        .unwrap()
+        .is_mdns());
+    assert!(!Resolver::new(1).is_mdns());
+}
+
+#[test]
+fn test_domain_line() {
+    let text = "DNS configuration\n\nresolver #1\n  domain   : corp.example.com\n  nameserver[0] : 10.8.0.1\n\nresolver #2\n  domain   : lab:8080.example.com\n  domain   : other.example.com\n  nameserver[0] : 10.8.0.1\n";
+    let (res, warnings) = parse_text_with_warnings(text).expect("Could not parse text");
+    assert_eq!(
+        res.dns_config[0].domain.as_deref(),
+        Some("corp.example.com")
+    );
+    // only the one domain is kept, and everything after the first colon is the value
+    assert_eq!(
+        res.dns_config[1].domain.as_deref(),
+        Some("other.example.com")
+    );
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].line_no, 9);
+    assert_eq!(warnings[0].category, WarningCategory::DuplicateField);
+    assert_eq!(
+        warnings[0].message,
+        "resolver #2 already has domain lab:8080.example.com, replacing it"
+    );
+}
+
+#[test]
+fn test_effective_for_interface() {
+    let filecontents =
+        std::fs::read_to_string("testdata/vpn_supplemental.txt").expect("Could not read fixture");
+    let res = parse_text(&filecontents).expect("Could not parse fixture");
+    let ids = |resolvers: &[&Resolver]| -> Vec<(usize, Option<String>)> {
+        resolvers
+            .iter()
+            .map(|resolver| (resolver.id, resolver.domain.clone()))
+            .collect()
+    };
+
+    let wifi = res.effective_for_interface("en0");
+    assert_eq!(wifi.scoped, vec![&res.scoped_dns_config[0]]);
+    // the supplemental resolvers still apply, and the scoped one replaces resolver #1
+    assert_eq!(
+        ids(&wifi.resolvers),
+        vec![
+            (2, Some("corp.example.com".to_string())),
+            (3, Some("8.10.in-addr.arpa".to_string())),
+            (4, Some("local".to_string())),
+            (1, None),
+        ]
+    );
+    assert!(std::ptr::eq(wifi.resolvers[3], &res.scoped_dns_config[0]));
+
+    let vpn = res.effective_for_interface("utun3");
+    assert_eq!(vpn.scoped, vec![&res.scoped_dns_config[1]]);
+    assert!(std::ptr::eq(vpn.resolvers[3], &res.scoped_dns_config[1]));
+    assert_eq!(res.effective_for_interface_index(22), vpn);
+    assert_eq!(res.effective_for_interface_index(15), wifi);
+
+    // an interface without scoped resolvers just gets the unscoped config
+    let other = res.effective_for_interface("en1");
+    assert!(other.scoped.is_empty());
+    assert!(std::ptr::eq(other.resolvers[3], &res.dns_config[0]));
+    assert_eq!(other.resolvers.len(), res.dns_config.len());
+}
+
+#[test]
+fn test_public_resolvers() {
+    let filecontents = std::fs::read_to_string("testdata.txt").expect("Could not read fixture");
+    let res = parse_text(&filecontents).expect("Could not parse fixture");
+    assert!(res.public_resolvers().is_empty());
+    assert!(res.uses_resolver(IpAddr::from([10, 0, 0, 12])));
+    assert!(!res.uses_resolver(IpAddr::from([8, 8, 8, 8])));
+
+    let cloudflare: IpAddr = "2606:4700:4700::1111".parse().unwrap();
+    let router = IpAddr::from([192, 168, 1, 1]);
+    let res = DNSConfig::builder()
+        .resolver(
+            ResolverBuilder::default()
+                .nameserver(cloudflare)
+                .nameserver(router),
+        )
+        .resolver(
+            ResolverBuilder::default()
+                .domain("corp.example.com")
+                .nameserver(IpAddr::from([8, 8, 8, 8]))
+                .nameserver(IpAddr::from([1, 1, 1, 1])),
+        )
+        .scoped_resolver(
+            ResolverBuilder::default()
+                .nameserver(cloudflare)
+                .nameserver(router)
+                .if_index(15, Some("en0")),
+        )
+        .build()
+        .expect("Failed to build");
+    assert!(res.uses_resolver(IpAddr::from([8, 8, 8, 8])));
+    // each one once, in the order they're first seen
+    let expected: Vec<IpAddr> = ["2606:4700:4700::1111", "8.8.8.8", "1.1.1.1"]
+        .iter()
+        .map(|address| address.parse().unwrap())
+        .collect();
+    assert_eq!(res.public_resolvers(), expected);
+
+    // a list of our own
+    let known = [IpAddr::from([192, 168, 1, 1]), IpAddr::from([9, 9, 9, 9])];
+    assert_eq!(
+        res.public_resolvers_from(&known),
+        vec![IpAddr::from([192, 168, 1, 1])]
+    );
+    assert!(res.public_resolvers_from(&[]).is_empty());
+}
+
+#[test]
+fn test_duplicate_nameservers() {
+    let filecontents = std::fs::read_to_string("testdata.txt").expect("Could not read fixture");
+    let res = parse_text(&filecontents).expect("Could not parse fixture");
+    assert_eq!(
+        res.dns_config[0].nameserver_addresses(),
+        res.system_nameservers()
+    );
+    assert!(!res.dns_config[0].has_duplicate_nameservers());
+    // the scoped copy of resolver #1 doesn't count
+    assert!(res.duplicate_nameservers().is_empty());
+
+    let input = "DNS configuration\n\nresolver #1\n  nameserver[2] : 10.0.0.1\n  nameserver[0] : 10.0.0.1\n  nameserver[1] : 10.0.0.2\n  nameserver[3] : [10.0.0.2]:5353\n\nresolver #2\n  domain   : corp.example.com\n  nameserver[0] : 10.8.0.1\n\nDNS configuration (for scoped queries)\n\nresolver #1\n  nameserver[0] : fe80::1%en0\n  nameserver[1] : fe80::1%en1\n  if_index : 15 (en0)\n  flags    : Scoped\n";
+    let res = parse_text(input).expect("Failed to parse");
+    let resolver = &res.dns_config[0];
+    // in index order, whatever order they were printed in
+    let addresses: Vec<String> = resolver
+        .nameserver_addresses()
+        .iter()
+        .map(IpAddr::to_string)
+        .collect();
+    assert_eq!(
+        addresses,
+        vec!["10.0.0.1", "10.0.0.2", "10.0.0.1", "10.0.0.2"]
+    );
+    assert!(resolver.has_duplicate_nameservers());
+    assert!(!res.dns_config[1].has_duplicate_nameservers());
+
+    let duplicates: Vec<(&str, usize, Vec<String>)> = res
+        .duplicate_nameservers()
+        .into_iter()
+        .map(|(source, addresses)| {
+            (
+                source.section,
+                source.resolver.id,
+                addresses.iter().map(IpAddr::to_string).collect(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        duplicates,
+        vec![
+            (
+                "DNS configuration",
+                1,
+                vec!["10.0.0.1".to_string(), "10.0.0.2".to_string()]
+            ),
+            (
+                "DNS configuration (for scoped queries)",
+                1,
+                vec!["fe80::1".to_string()]
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_sortlist() {
+    let filecontents =
+        std::fs::read_to_string("testdata/sortlist.txt").expect("Could not read fixture");
+    let res = parse_text(&filecontents).expect("Could not parse fixture");
+    dbg!(&res);
+
+    let entries = |resolver: &Resolver| -> Vec<String> {
+        resolver
+            .sortlist
+            .iter()
+            .map(SortListEntry::to_string)
+            .collect()
+    };
+    assert_eq!(
+        entries(&res.dns_config[0]),
+        vec!["10.0.0.0/255.0.0.0", "192.168.1.0/255.255.255.0"]
+    );
+    // SortList can have several entries on one line, and the mask can be left out
+    assert_eq!(
+        res.dns_config[1].sortlist,
+        vec![
+            SortListEntry {
+                address: IpAddr::from([10, 1, 0, 0]),
+                mask: Some(IpAddr::from([255, 255, 0, 0])),
+            },
+            SortListEntry {
+                address: IpAddr::from([172, 16, 0, 0]),
+                mask: None,
+            },
+        ]
+    );
+    assert_eq!(
+        res.scoped_dns_config[0].sortlist,
+        res.dns_config[0].sortlist
+    );
+    assert!(res.dns_config[1]
+        .to_string()
+        .contains("  sortaddr[1] : 172.16.0.0\n"));
+
+    #[cfg(feature = "serde")]
+    {
+        let json = serde_json::to_value(&res).expect("Failed to serialize");
+        assert_eq!(
+            json["dns_config"][0]["sortlist"][0],
+            serde_json::json!({"address": "10.0.0.0", "mask": "255.0.0.0"})
+        );
+        // and it's left out when empty
+        let json = serde_json::to_value(&res.dns_config[1]).expect("Failed to serialize");
+        assert_eq!(json["sortlist"].as_array().map(Vec::len), Some(2));
+        let json = serde_json::to_value(Resolver::new(1)).expect("Failed to serialize");
+        assert!(json.get("sortlist").is_none());
+    }
+
+    let err =
+        parse_text("DNS configuration\n\nresolver #1\n  SortList : 10.0.0.0/nope\n").unwrap_err();
+    assert_eq!(err.line_no(), 4);
+    assert!(matches!(err, ParseError::InvalidValue { .. }));
+}
+
+#[test]
+fn test_service_specific_section() {
+    let filecontents =
+        std::fs::read_to_string("testdata/service_specific.txt").expect("Could not read fixture");
+    let res = parse_text(&filecontents).expect("Could not parse fixture");
+    dbg!(&res);
+
+    assert_eq!(res.dns_config.len(), 2);
+    assert_eq!(res.scoped_dns_config.len(), 1);
+    assert_eq!(res.service_specific_dns_config.len(), 1);
+
+    let resolver = &res.service_specific_dns_config[0];
+    assert_eq!(resolver.id, 1);
+    assert_eq!(resolver.nameservers.len(), 2);
+    assert_eq!(resolver.service_identifier, Some(3));
+    assert!(matches!(
+        resolver.flags.as_deref().and_then(|flags| flags.first()),
+        Some(ResolverFlags::ServiceSpecific)
+    ));
+    assert_eq!(resolver.order, Some(1));
+
+    let rendered = res.to_scutil_string();
+    assert!(rendered.contains("DNS configuration (for service-specific queries)"));
+    assert_eq!(
+        parse_text(&rendered)
+            .expect("Could not parse rendered text")
+            .to_scutil_string(),
+        rendered
+    );
+
+    #[cfg(feature = "serde")]
+    {
+        // only shows up in the JSON when there's something in it
+        let json = serde_json::to_value(&res).expect("Failed to serialize");
+        assert_eq!(
+            json["service_specific_dns_config"][0]["service_identifier"],
+            3
+        );
+        let filecontents = std::fs::read_to_string("testdata.txt").expect("Could not read fixture");
+        let json =
+            serde_json::to_value(parse_text(&filecontents).expect("Could not parse fixture"))
+                .expect("Failed to serialize");
+        assert!(json.get("service_specific_dns_config").is_none());
+    }
+}
+
+#[test]
+fn test_other_section() {
+    let filecontents =
+        std::fs::read_to_string("testdata/mdns_section.txt").expect("Could not read fixture");
+    let res = parse_text(&filecontents).expect("Could not parse fixture");
+    dbg!(&res);
+
+    assert_eq!(res.dns_config.len(), 1);
+    assert_eq!(res.scoped_dns_config.len(), 1);
+    let mdns = res
+        .other_section("DNS configuration (for mdns queries)")
+        .expect("Should have kept the mdns section");
+    assert_eq!(mdns.len(), 2);
+    assert!(mdns.iter().all(|resolver| resolver.is_mdns()));
+    assert_eq!(mdns[1].domain.as_deref(), Some("254.169.in-addr.arpa"));
+    assert_eq!(res.resolver_count(), 4);
+
+    let rendered = res.to_scutil_string();
+    assert!(rendered.contains("DNS configuration (for mdns queries)"));
+    assert_eq!(parse_text(&rendered).unwrap(), res);
+
+    // a section with no resolvers is still kept
+    let res = parse_text("DNS configuration\n\nmDNS configuration\n\n").unwrap();
+    assert_eq!(res.other_section("mDNS configuration"), Some(&[][..]));
+
+    #[cfg(feature = "serde")]
+    {
+        let json = serde_json::to_value(&res).expect("Failed to serialize");
+        assert!(json["other_sections"]["mDNS configuration"].is_array());
+    }
+}
+
+#[test]
+fn test_config_id() {
+    let filecontents =
+        std::fs::read_to_string("testdata/managed_profile.txt").expect("Could not read fixture");
+    let res = parse_text(&filecontents).expect("Could not parse fixture");
+    dbg!(&res);
+
+    assert_eq!(res.dns_config.len(), 3);
+    assert_eq!(
+        res.dns_config[0].config_id.as_deref(),
+        Some("com.example.profile.dns")
+    );
+    assert_eq!(res.dns_config[1].config_id, None);
+
+    let managed = res.managed_resolvers();
+    assert_eq!(managed.len(), 1);
+    assert_eq!(managed[0].id, 1);
+
+    #[cfg(feature = "serde")]
+    {
+        let json = serde_json::to_value(&res).expect("Failed to serialize");
+        assert_eq!(
+            json["dns_config"][0]["config_id"],
+            "com.example.profile.dns"
+        );
+        assert!(json["dns_config"][1].get("config_id").is_none());
+    }
+}
+
+#[test]
+fn test_last_resolver_is_kept() {
+    let unscoped = "DNS configuration\n\nresolver #1\n  nameserver[0] : 10.0.0.1\n\nresolver #2\n  nameserver[0] : 10.0.0.2\n  order    : 200";
+    for input in [
+        unscoped.to_string(),
+        format!("{}\n", unscoped),
+        format!("{}\n\n", unscoped),
+    ] {
+        let res = parse_text(&input).expect("Failed to parse");
+        assert_eq!(res.dns_config.len(), 2, "{:?}", input);
+        assert_eq!(res.dns_config[1].id, 2);
+        assert_eq!(res.dns_config[1].order, Some(200));
+    }
+
+    let scoped = "DNS configuration\n\nresolver #1\n  nameserver[0] : 10.0.0.1\n\nDNS configuration (for scoped queries)\n\nresolver #1\n  nameserver[0] : 10.0.0.1\n  flags    : Scoped";
+    for input in [scoped.to_string(), format!("{}\n", scoped)] {
+        let res = parse_text(&input).expect("Failed to parse");
+        assert_eq!(res.dns_config.len(), 1);
+        assert_eq!(res.scoped_dns_config.len(), 1, "{:?}", input);
+        // no if_index, which used to decide whether it was kept
+        assert!(res.scoped_dns_config[0].if_index.is_none());
+    }
+}
+
+#[test]
+fn test_blocks_without_blank_lines() {
+    let input = "DNS configuration\nresolver #1\n  nameserver[0] : 10.0.0.1\nresolver #2\n  domain   : local\nDNS configuration (for scoped queries)\n\nresolver #1\n  nameserver[0] : 10.0.0.1\n";
+    let res = parse_text(input).expect("Failed to parse");
+    assert_eq!(res.dns_config.len(), 2);
+    assert_eq!(res.dns_config[1].domain.as_deref(), Some("local"));
+    assert_eq!(res.scoped_dns_config.len(), 1);
+}
+
+#[test]
+fn test_parse_reader() {
+    let file = std::fs::File::open("testdata.txt").expect("Could not open fixture");
+    let res = parse_reader(std::io::BufReader::new(file)).expect("Could not parse fixture");
+    let filecontents = std::fs::read_to_string("testdata.txt").expect("Could not read fixture");
+    assert_eq!(res, parse_text(&filecontents).unwrap());
+
+    // a one-byte buffer, so every line spans several reads
+    for path in [
+        "testdata.txt",
+        "testdata/service_specific.txt",
+        "testdata/managed_profile.txt",
+        "testdata/vpn_supplemental.txt",
+    ] {
+        let filecontents = std::fs::read_to_string(path).expect("Could not read fixture");
+        let reader =
+            std::io::BufReader::with_capacity(1, std::io::Cursor::new(filecontents.as_bytes()));
+        assert_eq!(
+            parse_reader(reader).expect("Could not parse fixture"),
+            parse_text(&filecontents).unwrap(),
+            "{}",
+            path
+        );
+    }
+
+    // not UTF-8
+    let err = parse_reader(&b"DNS configuration\n\nresolver #1\n  domain   : \xff\n"[..])
+        .expect_err("Should fail to read");
+    assert!(matches!(err, ParseError::Io { line_no: 4, .. }));
+    assert_eq!(err.content(), "");
+}
+
+#[test]
+fn test_crlf_line_endings() {
+    let filecontents = std::fs::read_to_string("testdata.txt").expect("Could not read fixture");
+    let expected = parse_text(&filecontents).unwrap();
+
+    let crlf = filecontents.replace('\n', "\r\n");
+    let res = parse_text(&crlf).expect("Could not parse CRLF text");
+    assert_eq!(res, expected);
+    let res = parse_reader(crlf.as_bytes()).expect("Could not read CRLF text");
+    assert_eq!(res, expected);
+
+    // stray carriage returns and an indented resolver line
+    let input = "DNS configuration\r\r\n\r\n  resolver #1\r\r\n  nameserver[0] : 10.0.0.1\r\r\n  order    : 1\r\r\n";
+    let res = parse_text(input).expect("Could not parse stray carriage returns");
+    assert_eq!(res.dns_config.len(), 1);
+    assert_eq!(res.dns_config[0].id, 1);
+    assert_eq!(res.dns_config[0].order, Some(1));
+}
+
+#[test]
+fn test_bom_and_tabs() {
+    let filecontents = std::fs::read_to_string("testdata.txt").expect("Could not read fixture");
+    let expected = parse_text(&filecontents).unwrap();
+
+    for input in [
+        filecontents.replace('\n', "\r\n"),
+        format!("\u{feff}{}", filecontents),
+        format!("\u{feff}{}", filecontents.replace('\n', "\r\n")),
+        filecontents.replace("  ", "\t").replace(" : ", "\t:\t"),
+    ] {
+        let res = parse_text(&input).expect("Could not parse fixture");
+        assert_eq!(res, expected, "{:?}", input);
+    }
+}
+
+#[test]
+fn test_interface_index() {
+    for input in ["if_index : 15 (en0)", "if_index\t:\t15\t(en0)", "15 (en0)"] {
+        let res = InterfaceIndex::from_str(input).expect("Failed to parse if_index");
+        assert_eq!(res.index, 15);
+        assert_eq!(res.interface.as_deref(), Some("en0"));
+    }
+    let res = InterfaceIndex::from_str("if_index : 7 (Ethernet 2)").unwrap();
+    assert_eq!(res.interface.as_deref(), Some("Ethernet 2"));
+    assert_eq!(res.to_string(), "7 (Ethernet 2)");
+    let res = InterfaceIndex::from_str("if_index : 22 (utun3)").unwrap();
+    assert_eq!(res.index, 22);
+    assert_eq!(res.interface.as_deref(), Some("utun3"));
+    let res = InterfaceIndex::from_str("if_index:14 (en0)").unwrap();
+    assert_eq!(res.index, 14);
+    assert_eq!(res.interface.as_deref(), Some("en0"));
+    let res = InterfaceIndex::from_str("if_index : 0").unwrap();
+    assert_eq!(res.index, 0);
+    assert_eq!(res.interface, None);
+    assert_eq!(res.to_string(), "0");
+
+    for input in [
+        "if_index :",
+        "if_index : en0",
+        "if_index : 15 en0",
+        "if_index : 15 ()",
+        "if_index : 15 (en0",
+        "%%garbage%%",
+    ] {
+        let err = InterfaceIndex::from_str(input).expect_err(input);
+        assert!(!err.is_empty(), "{:?}", input);
+    }
+}
+
+#[test]
+fn test_resolver_interface_index() {
+    let input = "DNS configuration\n\nresolver #1\n  nameserver[0] : 10.0.0.1\n  if_index : 14\n\nresolver #2\n  nameserver[0] : 10.0.0.2\n  if_index : 14 (en0)\n\n";
+    let res = parse_text(input).expect("Failed to parse");
+    let bare = res.dns_config[0].if_index.as_ref().unwrap();
+    assert_eq!((bare.index, bare.interface.as_deref()), (14, None));
+    let named = res.dns_config[1].if_index.as_ref().unwrap();
+    assert_eq!((named.index, named.interface.as_deref()), (14, Some("en0")));
+    assert_eq!(parse_text(&res.to_scutil_string()).unwrap(), res);
+
+    let input = "DNS configuration\n\nresolver #1\n  if_index :\n\n";
+    let err = parse_text(input).expect_err("A missing index should be an error");
+    assert!(matches!(
+        err,
+        ParseError::InvalidValue { line_no: 4, ref message, .. } if message == "Missing interface index"
+    ));
+}
+
+#[test]
+fn test_annotated_resolver_header() {
+    let input = "DNS configuration\n\nresolver #1 (Default)\n  nameserver[0] : 10.0.0.1\n\nresolver  #2\t(VPN: corp)  \n  nameserver[0] : 10.0.0.2\n\nresolver #3\n  nameserver[0] : 10.0.0.3\n\n";
+    let res = parse_text(input).expect("Annotated headers should parse");
+    let headers: Vec<(usize, Option<&str>)> = res
+        .dns_config
+        .iter()
+        .map(|resolver| (resolver.id, resolver.annotation.as_deref()))
+        .collect();
+    assert_eq!(
+        headers,
+        vec![(1, Some("(Default)")), (2, Some("(VPN: corp)")), (3, None)]
+    );
+    assert!(res.to_scutil_string().contains("resolver #1 (Default)\n"));
+    assert_eq!(parse_text(&res.to_scutil_string()).unwrap(), res);
+    assert_eq!(parse_text_borrowed(input).unwrap().to_owned(), res);
+
+    #[cfg(feature = "serde")]
+    {
+        let json = serde_json::to_value(&res).expect("Failed to serialize");
+        assert_eq!(json["dns_config"][0]["annotation"], "(Default)");
+        assert!(json["dns_config"][2].get("annotation").is_none());
+    }
+
+    for (header, expected) in [
+        ("resolver #", "missing resolver index"),
+        ("resolver", "missing resolver index"),
+        ("resolver 1 (Default)", "missing resolver index"),
+        ("resolver #x (Default)", "invalid integer"),
+    ] {
+        let input = format!("DNS configuration\n\n{}\n", header);
+        let err = parse_text(&input).expect_err(header);
+        assert_eq!(err.line_no(), 3);
+        assert!(err.to_string().contains(expected), "{}", err);
+    }
+}
+
+#[test]
+fn test_config_equality() {
+    let filecontents = std::fs::read_to_string("testdata.txt").expect("Could not read fixture");
+    let first = parse_text(&filecontents).expect("Could not parse fixture");
+    let second = parse_text(&filecontents).expect("Could not parse fixture");
+    assert_eq!(first, second);
+
+    let mut changed = second.clone();
+    changed.dns_config[0].order = Some(1);
+    assert_ne!(first, changed);
+
+    // a single nameserver is enough to make them different
+    let mut changed = second.clone();
+    changed.scoped_dns_config[0].nameservers[0].address = "192.0.2.1".parse().unwrap();
+    assert_ne!(first, changed);
+    assert_eq!(first.dns_config, changed.dns_config);
+
+    let mut resolvers: Vec<Resolver> = first.dns_config.clone();
+    resolvers.extend(first.dns_config.iter().cloned());
+    let unique: HashSet<&Resolver> = resolvers.iter().collect();
+    assert_eq!(unique.len(), first.dns_config.len());
+}
+
+#[test]
+fn test_indexed_search_domains() {
+    let input = "DNS configuration\n\nresolver #1\n  search domain[2] : c.example.com\n  search domain[0] : a.example.com\n  search domain[1] : b.example.com\n  search domain : d.example.com\n\nresolver #2\n  search domain : e.example.com\n\n";
+    let res = parse_text(input).expect("Failed to parse");
+    assert_eq!(
+        res.dns_config[0].search_domain_names(),
+        vec![
+            "a.example.com",
+            "b.example.com",
+            "c.example.com",
+            "d.example.com"
+        ]
+    );
+    let indices: Vec<usize> = res.dns_config[0]
+        .search_domains
+        .iter()
+        .map(|search_domain| search_domain.index)
+        .collect();
+    assert_eq!(indices, vec![0, 1, 2, 3]);
+    assert_eq!(
+        res.dns_config[1].search_domains,
+        vec![SearchDomain {
+            index: 0,
+            domain: "e.example.com".to_string()
+        }]
+    );
+    #[cfg(feature = "serde")]
+    {
+        let json = serde_json::to_value(&res.dns_config[0]).expect("Failed to serialize");
+        assert_eq!(
+            json["search_domains"][2],
+            serde_json::json!({"index": 2, "domain": "c.example.com"})
+        );
+    }
+
+    // everything after the first colon, spaces and all
+    let input = "DNS configuration\n\nresolver #1\n  search domain[0] : corp.example.com # pushed by vpn\n\n";
+    let res = parse_text(input).expect("Failed to parse");
+    assert_eq!(
+        res.dns_config[0].search_domain_names(),
+        vec!["corp.example.com # pushed by vpn"]
+    );
+
+    let input = "DNS configuration\n\nresolver #1\n  search domain[0] :\n\n";
+    let err = parse_text(input).expect_err("Missing search domain should fail");
+    assert!(matches!(err, ParseError::MissingField { line_no: 4, .. }));
+}
+
+#[test]
+fn test_last_mdns_resolver_is_kept() {
+    // the fixture ends part way through the last line, like output that's been trimmed
+    let filecontents =
+        std::fs::read_to_string("testdata/mdns_last.txt").expect("Could not read fixture");
+    assert!(!filecontents.ends_with('\n'));
+    for input in [
+        filecontents.clone(),
+        format!("{}\n", filecontents),
+        format!("{}\n\n", filecontents),
+    ] {
+        let res = parse_text(&input).expect("Failed to parse");
+        assert_eq!(res.dns_config.len(), 3, "{:?}", input);
+        let last = &res.dns_config[2];
+        assert_eq!(last.domain.as_deref(), Some("254.169.in-addr.arpa"));
+        assert!(last.is_mdns());
+        assert!(last.nameservers.is_empty());
+        assert_eq!(last.order, Some(300200));
+    }
+}
+
+#[test]
+fn test_link_local_nameserver() {
+    let filecontents =
+        std::fs::read_to_string("testdata/link_local.txt").expect("Could not read fixture");
+    let res = parse_text(&filecontents).expect("Could not parse fixture");
+    dbg!(&res);
+
+    for resolver in res.dns_config.iter().chain(res.scoped_dns_config.iter()) {
+        let link_local = &resolver.nameservers[0].address;
+        assert_eq!(
+            link_local.address,
+            "fe80::aede:48ff:fe00:1122".parse::<IpAddr>().unwrap()
+        );
+        assert_eq!(link_local.scope.as_deref(), Some("en0"));
+
+        let plain = &resolver.nameservers[1].address;
+        assert_eq!(plain.address, "192.168.1.1".parse::<IpAddr>().unwrap());
+        assert_eq!(plain.scope, None);
+    }
+
+    #[cfg(feature = "serde")]
+    {
+        let json = serde_json::to_value(&res).expect("Failed to serialize");
+        assert_eq!(
+            json["dns_config"][0]["nameservers"][0]["address"],
+            "fe80::aede:48ff:fe00:1122%en0"
+        );
+        assert_eq!(
+            json["dns_config"][0]["nameservers"][1]["address"],
+            "192.168.1.1"
+        );
+    }
+}
+
+#[test]
+fn test_nameserver_address_scope() {
+    let res = NameserverAddress::from_str("fe80::1%en0").expect("Failed to parse");
+    assert_eq!(res.address, "fe80::1".parse::<IpAddr>().unwrap());
+    assert_eq!(res.scope.as_deref(), Some("en0"));
+    assert_eq!(res.to_string(), "fe80::1%en0");
+
+    let res = NameserverAddress::from_str("fe80::1%").expect("Failed to parse");
+    assert_eq!(res.scope, None);
+    assert_eq!(res.to_string(), "fe80::1");
+
+    let res = NameserverAddress::from_str("10.0.0.1").expect("Failed to parse");
+    assert_eq!(res.scope, None);
+
+    assert!(NameserverAddress::from_str("%en0").is_err());
+    assert!(NameserverAddress::from_str("fe80::zz%en0").is_err());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_nameservers_serialize_in_index_order() {
+    let input = "DNS configuration\n\nresolver #1\n  nameserver[2] : 10.0.0.3\n  nameserver[0] : 10.0.0.1\n  nameserver[1] : 10.0.0.2\n\n";
+    for _ in 0..10 {
+        let res = parse_text(input).expect("Failed to parse");
+        let json =
+            serde_json::to_value(&res.dns_config[0].nameservers).expect("Failed to serialize");
+        assert_eq!(
+            json,
+            serde_json::json!([
+                {"index": 0, "address": "10.0.0.1"},
+                {"index": 1, "address": "10.0.0.2"},
+                {"index": 2, "address": "10.0.0.3"},
+            ])
+        );
+    }
+}
+
+#[test]
+fn test_nameserver_ports() {
+    let input = "DNS configuration\n\nresolver #1\n  nameserver[0] : 10.0.0.1:5353\n  nameserver[1] : [fe80::1%en0]:5300\n  nameserver[2] : fe80::2\n  nameserver[3] : 10.0.0.2\n\nresolver #2\n  nameserver[0] : 127.0.0.1\n  nameserver[1] : 127.0.0.2:54\n  port : 5353\n\n";
+    let res = parse_text(input).expect("Failed to parse");
+    dbg!(&res);
+
+    let ports: Vec<Option<u16>> = res.dns_config[0]
+        .nameservers
+        .iter()
+        .map(|ns| ns.port)
+        .collect();
+    assert_eq!(ports, vec![Some(5353), Some(5300), None, None]);
+    assert_eq!(
+        res.dns_config[0].nameservers[1].address.to_string(),
+        "fe80::1%en0"
+    );
+    assert_eq!(
+        res.dns_config[0].nameservers[2].address.to_string(),
+        "fe80::2"
+    );
+
+    let ports: Vec<Option<u16>> = res.dns_config[1]
+        .nameservers
+        .iter()
+        .map(|ns| ns.port)
+        .collect();
+    assert_eq!(ports, vec![None, Some(54)]);
+    assert_eq!(res.dns_config[1].port, Some(5353));
+    assert_eq!(res.dns_config[0].port, None);
+
+    #[cfg(feature = "serde")]
+    {
+        let json =
+            serde_json::to_value(&res.dns_config[0].nameservers).expect("Failed to serialize");
+        assert_eq!(json[0]["port"], 5353);
+        assert!(json[3].get("port").is_none());
+    }
+
+    let input = "DNS configuration\n\nresolver #1\n  nameserver[0] : 10.0.0.1:99999\n\n";
+    let err = parse_text(input).expect_err("Port is out of range");
+    assert!(matches!(err, ParseError::InvalidInteger { line_no: 4, .. }));
+}
+
+#[test]
+fn test_nameserver_socket_addrs() {
+    let input = "DNS configuration\n\nresolver #1\n  nameserver[0] : 10.0.0.1\n  nameserver[1] : fe80::1%4\n  nameserver[2] : fe80::2%en0\n\nresolver #2\n  nameserver[0] : 127.0.0.1\n  nameserver[1] : [::1]:54\n  port : 5353\n\n";
+    let res = parse_text(input).expect("Failed to parse");
+
+    let expected: Vec<SocketAddr> = ["10.0.0.1:53", "[fe80::1%4]:53", "[fe80::2]:53"]
+        .iter()
+        .map(|addr| addr.parse().unwrap())
+        .collect();
+    assert_eq!(res.dns_config[0].nameserver_socket_addrs(), expected);
+
+    let expected: Vec<SocketAddr> = ["127.0.0.1:5353", "[::1]:54"]
+        .iter()
+        .map(|addr| addr.parse().unwrap())
+        .collect();
+    assert_eq!(res.dns_config[1].nameserver_socket_addrs(), expected);
+
+    #[cfg(feature = "serde")]
+    {
+        let json = serde_json::to_value(&res).expect("Failed to serialize");
+        assert!(json["dns_config"][0].get("port").is_none());
+        assert_eq!(json["dns_config"][1]["port"], 5353);
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serialization_is_stable() {
+    let filecontents = std::fs::read_to_string("testdata.txt").expect("Could not read fixture");
+    let first = serde_json::to_string_pretty(&parse_text(&filecontents).unwrap()).unwrap();
+    for _ in 0..10 {
+        let again = serde_json::to_string_pretty(&parse_text(&filecontents).unwrap()).unwrap();
+        assert_eq!(first, again);
+    }
+}
+
+#[test]
+fn test_round_trip_text() {
+    for filename in [
+        "testdata.txt",
+        "testdata/vpn_supplemental.txt",
+        "testdata/link_local.txt",
+        "testdata/service_specific.txt",
+        "testdata/managed_profile.txt",
+        "testdata/mdns_section.txt",
+        "testdata/sortlist.txt",
+        "testdata/vpn_scoped.txt",
+    ] {
+        let filecontents = std::fs::read_to_string(filename).expect("Could not read fixture");
+        let res = parse_text(&filecontents).expect("Could not parse fixture");
+
+        let rendered = res.to_scutil_string();
+        println!("{}", rendered);
+        let reparsed = parse_text(&rendered).expect("Could not parse rendered text");
+        assert_eq!(reparsed.dns_config.len(), res.dns_config.len());
+        assert_eq!(
+            reparsed.scoped_dns_config.len(),
+            res.scoped_dns_config.len()
+        );
+        assert_eq!(reparsed.to_scutil_string(), rendered);
+        assert_eq!(reparsed, res, "{}", filename);
+    }
+
+    // configs that didn't come from scutil render to something it could have printed
+    for filename in [
+        "testdata/windows_netsh.txt",
+        "testdata/windows_dnsclient.csv",
+    ] {
+        let filecontents = std::fs::read_to_string(filename).expect("Could not read fixture");
+        let mut res = crate::windows::parse_text(&filecontents).expect("Could not parse fixture");
+        let reparsed = parse_text(&res.to_scutil_string()).expect("Could not parse rendered text");
+        // netsh interface names are kept in `extra`, which isn't rendered
+        for resolver in &mut res.scoped_dns_config {
+            resolver.extra.clear();
+        }
+        assert_eq!(reparsed, res, "{}", filename);
+    }
+}
+
+#[test]
+fn test_summary() {
+    let input = "DNS configuration\n\nresolver #1\n  search domain[0] : example.com\n  nameserver[1] : 10.0.0.2\n  nameserver[0] : 10.0.0.1\n  if_index : 15 (en0)\n  flags    : Request A records\n  order    : 200000\n\nresolver #2\n  domain   : local\n  options  : mdns\n\n";
+    let res = parse_text(input).expect("Failed to parse");
+    assert_eq!(
+        res.summary().to_string(),
+        "DNS configuration
+  resolver #1
+    nameservers    10.0.0.1  10.0.0.2
+    search domains example.com
+    flags          Request A records
+    order          200000
+    interface      15 (en0)
+  resolver #2
+    domain         local
+    nameservers    -
+    search domains -
+    flags          -
+    order          -
+    interface      -
+"
+    );
+}
+
+#[test]
+fn test_resolver_display() {
+    let input = "DNS configuration\n\nresolver #2\n  domain   : local\n  nameserver[0] : fe80::1%en0\n  nameserver[1] : 10.0.0.1:5353\n  options  : mdns\n  timeout  : 5\n  flags    : Request A records, Request AAAA records\n  reach    : 0x00000000 (Not Reachable)\n  order    : 300000\n\n";
+    let res = parse_text(input).expect("Failed to parse");
+    assert_eq!(
+        res.dns_config[0].to_string(),
+        "resolver #2
+  domain   : local
+  nameserver[0] : fe80::1%en0
+  nameserver[1] : 10.0.0.1:5353
+  options  : mdns
+  timeout  : 5
+  flags    : Request A records, Request AAAA records
+  reach    : 0x00000000 (Not Reachable)
+  order    : 300000
+"
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_json_round_trip() {
+    for filename in [
+        "testdata.txt",
+        "testdata/vpn_supplemental.txt",
+        "testdata/link_local.txt",
+        "testdata/service_specific.txt",
+        "testdata/managed_profile.txt",
+        "testdata/mdns_section.txt",
+        "testdata/sortlist.txt",
+    ] {
+        let filecontents = std::fs::read_to_string(filename).expect("Could not read fixture");
+        let res = parse_text(&filecontents).expect("Could not parse fixture");
+
+        let json = serde_json::to_string(&res).expect("Failed to serialize");
+        let reloaded: DNSConfig = serde_json::from_str(&json).expect("Failed to deserialize");
+        assert_eq!(reloaded, res, "{}", filename);
+        assert_eq!(
+            serde_json::to_string(&reloaded).expect("Failed to serialize"),
+            json
+        );
+    }
+
+    let res = parse_text_lenient(
+        &std::fs::read_to_string("testdata/unknown_field.txt").expect("Could not read fixture"),
+    )
+    .expect("Could not parse fixture");
+    let reloaded: DNSConfig =
+        serde_json::from_value(serde_json::to_value(&res).unwrap()).expect("Failed to deserialize");
+    assert_eq!(reloaded, res);
+    assert_eq!(reloaded.dns_config[0].extra, res.dns_config[0].extra);
+    assert!(reloaded.dns_config[1].extra.is_empty());
+}
+
+#[cfg(feature = "arbitrary")]
+mod arbitrary;
+mod borrowed;
+mod diff;
+#[cfg(feature = "hickory")]
+mod hickory;
+mod hostnames;
+mod nc;
+mod nwi;
+mod proxy;
+mod reachability;
+mod resolvconf;
+#[cfg(feature = "system")]
+mod runner;
+#[cfg(feature = "schemars")]
+mod schema;
+mod scstore;
+mod windows;
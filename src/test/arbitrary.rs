@@ -0,0 +1,27 @@
+use arbitrary::{Arbitrary, Unstructured};
+
+use crate::dns::{parse_text, DNSConfig};
+
+#[test]
+fn test_arbitrary_configs_round_trip() {
+    // a fixed xorshift stream, so failures can be reproduced
+    let mut state: u64 = 0x2545f4914f6cdd1d;
+    let mut bytes = vec![0u8; 1 << 16];
+    for byte in bytes.iter_mut() {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        *byte = state as u8;
+    }
+
+    let mut u = Unstructured::new(&bytes);
+    let mut resolvers = 0;
+    while !u.is_empty() {
+        let config = DNSConfig::arbitrary(&mut u).expect("Failed to generate a config");
+        resolvers += config.dns_config.len() + config.scoped_dns_config.len();
+        let rendered = config.to_scutil_string();
+        let reparsed = parse_text(&rendered).expect(&rendered);
+        assert_eq!(reparsed, config, "{}", rendered);
+    }
+    assert!(resolvers > 100);
+}
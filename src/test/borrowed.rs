@@ -0,0 +1,79 @@
+use crate::borrowed::parse_text_borrowed;
+use crate::dns::{parse_text, ParseError};
+
+#[test]
+fn test_borrowed_matches_owned() {
+    for path in [
+        "testdata.txt",
+        "testdata/service_specific.txt",
+        "testdata/mdns_last.txt",
+        "testdata/managed_profile.txt",
+        "testdata/vpn_supplemental.txt",
+        "testdata/link_local.txt",
+        "testdata/mdns_section.txt",
+        "testdata/sortlist.txt",
+    ] {
+        let filecontents = std::fs::read_to_string(path).expect("Could not read fixture");
+        let borrowed = parse_text_borrowed(&filecontents).expect("Could not parse fixture");
+        assert_eq!(
+            borrowed.to_owned(),
+            parse_text(&filecontents).unwrap(),
+            "{}",
+            path
+        );
+    }
+}
+
+#[test]
+fn test_borrowed_points_into_input() {
+    let input = "DNS configuration\n\nresolver #1\n  search domain[0] : example.com\n  nameserver[0] : fe80::1%en0\n  if_index : 15 (en0)\n  flags    : Request A records\n  domain   : corp.example.com\n\n";
+    let res = parse_text_borrowed(input).expect("Failed to parse");
+    let resolver = &res.dns_config[0];
+    let range = input.as_bytes().as_ptr_range();
+    for text in [
+        resolver.search_domains[0].domain,
+        resolver.nameservers[0].scope.unwrap(),
+        resolver.if_index.unwrap().interface.unwrap(),
+        resolver.flags.as_ref().unwrap()[0],
+        resolver.domain.unwrap(),
+    ] {
+        assert!(range.contains(&text.as_ptr()), "{:?}", text);
+    }
+    assert_eq!(resolver.domain, Some("corp.example.com"));
+    assert_eq!(resolver.nameservers[0].scope, Some("en0"));
+
+    #[cfg(feature = "serde")]
+    {
+        let json = serde_json::to_value(&res).unwrap();
+        assert_eq!(json["dns_config"][0]["flags"][0], "Request A records");
+        assert_eq!(json["dns_config"][0]["if_index"]["interface"], "en0");
+    }
+}
+
+#[test]
+fn test_borrowed_errors() {
+    for (input, line_no) in [
+        ("resolver #1\n", 1),
+        ("DNS configuration\n\nresolver #1\n  ordering : 5\n", 4),
+        ("DNS configuration\n\nresolver #1\n  what : 1\n", 4),
+        ("DNS configuration\n\nresolver #x\n", 3),
+        ("  order : 1\n", 1),
+        (
+            "DNS configuration\n\nresolver #1\n  nameserver[0] : 10.0.0\n",
+            4,
+        ),
+        ("DNS configuration\n\nresolver #1\n  if_index : en0\n", 4),
+        ("DNS configuration\n\nresolver #1\n\nresolver #1\n", 5),
+    ] {
+        let err = parse_text_borrowed(input).expect_err(input);
+        assert_eq!(err.line_no(), line_no, "{:?}", err);
+    }
+    assert!(matches!(
+        parse_text_borrowed("DNS configuration\n\nresolver #1\n  order : x\n"),
+        Err(ParseError::InvalidInteger { line_no: 4, .. })
+    ));
+    assert!(matches!(
+        parse_text_borrowed("DNS configuration\n\nresolver #1\n  flagship : 1\n"),
+        Err(ParseError::UnexpectedLine { line_no: 4, .. })
+    ));
+}
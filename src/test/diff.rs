@@ -0,0 +1,124 @@
+use std::str::FromStr;
+
+use crate::dns::{parse_text, NameserverAddress};
+
+#[test]
+fn test_diff_identical() {
+    let filecontents = std::fs::read_to_string("testdata.txt").expect("Could not read fixture");
+    let res = parse_text(&filecontents).expect("Could not parse fixture");
+    assert!(res.diff(&res.clone()).is_empty());
+}
+
+#[test]
+fn test_diff_changes() {
+    let old = "DNS configuration\n\nresolver #1\n  nameserver[0] : 10.0.0.1\n  order    : 200\n\nresolver #2\n  domain   : corp.example.com\n  nameserver[0] : 10.8.0.1\n\nresolver #3\n  domain   : local\n  options  : mdns\n\n";
+    let new = "DNS configuration\n\nresolver #1\n  nameserver[0] : 10.0.0.1\n  nameserver[1] : 10.0.0.2\n  order    : 100\n\nresolver #2\n  domain   : local\n  options  : mdns\n\nresolver #3\n  domain   : home.example.net\n  nameserver[0] : 192.168.1.1\n\n";
+    let old = parse_text(old).expect("Could not parse old");
+    let new = parse_text(new).expect("Could not parse new");
+
+    let diff = old.diff(&new);
+    dbg!(&diff);
+    assert!(!diff.is_empty());
+    assert!(diff.scoped_dns_config.is_empty());
+
+    let section = &diff.dns_config;
+    assert_eq!(section.removed.len(), 1);
+    assert_eq!(
+        section.removed[0].domain.as_deref(),
+        Some("corp.example.com")
+    );
+    assert_eq!(section.added.len(), 1);
+    assert_eq!(section.added[0].domain.as_deref(), Some("home.example.net"));
+
+    // the mdns resolver moved from #3 to #2 but is otherwise the same
+    assert_eq!(section.changed.len(), 1);
+    let changed = &section.changed[0];
+    assert_eq!((changed.old_id, changed.new_id), (1, 1));
+    let fields: Vec<(&str, Option<&str>, Option<&str>)> = changed
+        .changes
+        .iter()
+        .map(|change| {
+            (
+                change.field.as_str(),
+                change.old.as_deref(),
+                change.new.as_deref(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        fields,
+        vec![
+            ("nameservers", Some("10.0.0.1"), Some("10.0.0.1, 10.0.0.2")),
+            ("order", Some("200"), Some("100")),
+        ]
+    );
+
+    assert_eq!(
+        changed.added_nameservers,
+        vec![NameserverAddress::from_str("10.0.0.2").unwrap()]
+    );
+    assert!(changed.removed_nameservers.is_empty());
+
+    #[cfg(feature = "serde")]
+    {
+        let json = serde_json::to_value(&diff).expect("Failed to serialize");
+        assert_eq!(json["dns_config"]["changed"][0]["changes"][1]["new"], "100");
+        assert_eq!(
+            json["dns_config"]["changed"][0]["added_nameservers"],
+            serde_json::json!(["10.0.0.2"])
+        );
+        assert!(json["dns_config"]["changed"][0]
+            .get("removed_nameservers")
+            .is_none());
+        assert!(json.get("other_sections").is_none());
+    }
+}
+
+#[test]
+fn test_diff_vpn_scoped_resolver() {
+    let old = std::fs::read_to_string("testdata.txt").expect("Could not read fixture");
+    let new = std::fs::read_to_string("testdata/vpn_scoped.txt").expect("Could not read fixture");
+    let old = parse_text(&old).expect("Could not parse old");
+    let new = parse_text(&new).expect("Could not parse new");
+
+    let diff = old.diff(&new);
+    dbg!(&diff);
+    assert!(diff.dns_config.is_empty());
+    assert!(diff.service_specific_dns_config.is_empty());
+    let section = &diff.scoped_dns_config;
+    assert!(section.removed.is_empty());
+    assert!(section.changed.is_empty());
+    assert_eq!(section.added.len(), 1);
+    let added = &section.added[0];
+    assert_eq!(added.id, 2);
+    assert_eq!(
+        added.if_index.as_ref().map(|if_index| if_index.to_string()),
+        Some("22 (utun3)".to_string())
+    );
+
+    // and going back is the one resolver removed
+    let diff = new.diff(&old);
+    assert_eq!(diff.scoped_dns_config.removed.len(), 1);
+    assert!(diff.scoped_dns_config.added.is_empty());
+}
+
+#[test]
+fn test_diff_other_sections() {
+    let old = std::fs::read_to_string("testdata/mdns_section.txt").expect("Could not read fixture");
+    let old = parse_text(&old).expect("Could not parse old");
+    let mut new = old.clone();
+    assert!(old.diff(&new).is_empty());
+
+    let (header, resolvers) = new
+        .other_sections
+        .iter_mut()
+        .next()
+        .expect("fixture should have another section");
+    let header = header.clone();
+    let removed = resolvers.pop().expect("section should have a resolver");
+
+    let diff = old.diff(&new);
+    assert!(!diff.is_empty());
+    assert_eq!(diff.other_sections.len(), 1);
+    assert_eq!(diff.other_sections[&header].removed, vec![removed]);
+}
@@ -0,0 +1,114 @@
+use std::net::IpAddr;
+use std::time::Duration;
+
+use hickory_resolver::config::{ResolverConfig, ResolverOpts};
+
+use crate::dns::{parse_text, Resolver, ResolverOption};
+use crate::hickory::{resolver_config, ConversionError};
+
+fn name_servers(config: &ResolverConfig) -> Vec<(IpAddr, Vec<u16>)> {
+    config
+        .name_servers
+        .iter()
+        .map(|name_server| {
+            let ports = name_server
+                .connections
+                .iter()
+                .map(|connection| connection.port)
+                .collect();
+            (name_server.ip, ports)
+        })
+        .collect()
+}
+
+#[test]
+fn test_resolver_config() {
+    let filecontents = std::fs::read_to_string("testdata.txt").expect("Could not read fixture");
+    let res = parse_text(&filecontents).expect("Could not parse fixture");
+
+    let config = ResolverConfig::try_from(&res).expect("Could not convert config");
+    let expected: Vec<(IpAddr, Vec<u16>)> =
+        ["1234:1234:fd:0:ffe:66ff:2345:b33f", "10.0.0.12", "10.0.0.1"]
+            .into_iter()
+            .map(|address| (address.parse().unwrap(), vec![53, 53]))
+            .collect();
+    assert_eq!(name_servers(&config), expected);
+    let search: Vec<String> = config.search.iter().map(|name| name.to_string()).collect();
+    assert_eq!(search, vec!["subdomain.example.com"]);
+
+    // the mdns resolvers don't have anything to query
+    let err = ResolverConfig::try_from(&res.dns_config[1]).unwrap_err();
+    assert!(matches!(err, ConversionError::NoNameservers(2)));
+    assert!(matches!(
+        resolver_config(&[]).unwrap_err(),
+        ConversionError::NoResolver
+    ));
+}
+
+#[test]
+fn test_resolver_config_from_several() {
+    let filecontents =
+        std::fs::read_to_string("testdata/vpn_supplemental.txt").expect("Could not read fixture");
+    let res = parse_text(&filecontents).expect("Could not parse fixture");
+
+    // the VPN's two nameservers, then the default resolver's
+    let config = resolver_config(&res.resolvers_for_domain("db.corp.example.com"))
+        .expect("Could not convert config");
+    let ips: Vec<IpAddr> = name_servers(&config)
+        .into_iter()
+        .map(|(ip, _)| ip)
+        .collect();
+    assert_eq!(
+        ips,
+        ["10.8.0.1", "10.8.0.2", "192.168.1.1"].map(|address| address.parse::<IpAddr>().unwrap())
+    );
+    assert_eq!(config.search.len(), 1);
+
+    // nameservers shared by several resolvers are only added once
+    let config = resolver_config(&res.effective_for_interface("utun3").resolvers)
+        .expect("Could not convert config");
+    assert_eq!(config.name_servers.len(), 2);
+}
+
+#[test]
+fn test_resolver_config_ports_and_scopes() {
+    let resolver = Resolver::builder(1)
+        .nameserver(IpAddr::from([10, 0, 0, 53]))
+        .nameserver(IpAddr::from([10, 0, 0, 54]))
+        .port(5353)
+        .build()
+        .unwrap();
+    let config = ResolverConfig::try_from(&resolver).expect("Could not convert resolver");
+    assert_eq!(
+        name_servers(&config),
+        vec![
+            (IpAddr::from([10, 0, 0, 53]), vec![5353, 5353]),
+            (IpAddr::from([10, 0, 0, 54]), vec![5353, 5353]),
+        ]
+    );
+
+    let filecontents =
+        std::fs::read_to_string("testdata/link_local.txt").expect("Could not read fixture");
+    let res = parse_text(&filecontents).expect("Could not parse fixture");
+    let err = ResolverConfig::try_from(&res).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "nameserver fe80::aede:48ff:fe00:1122%en0 has a zone, which hickory can't use"
+    );
+}
+
+#[test]
+fn test_resolver_opts() {
+    let resolver = Resolver::builder(1)
+        .timeout(Duration::from_secs(2))
+        .option(ResolverOption::Value {
+            key: "ndots".to_string(),
+            value: "3".to_string(),
+        })
+        .build()
+        .unwrap();
+    let opts = ResolverOpts::from(&resolver);
+    assert_eq!(opts.timeout, Duration::from_secs(2));
+    assert_eq!(opts.ndots, 3);
+    assert_eq!(opts.attempts, ResolverOpts::default().attempts);
+}
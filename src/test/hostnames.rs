@@ -0,0 +1,71 @@
+#[cfg(feature = "system")]
+use std::io;
+
+use crate::hostnames::{parse_text, Hostnames};
+#[cfg(feature = "system")]
+use crate::runner::{CommandFailed, ScutilRunner, SystemError};
+
+#[test]
+fn test_parse_hostnames() {
+    let res = parse_text(
+        "Jane's MacBook Pro\n",
+        "Janes-MacBook-Pro\n",
+        "HostName: not set\n",
+    );
+    assert_eq!(
+        res,
+        Hostnames {
+            computer_name: Some("Jane's MacBook Pro".to_string()),
+            local_host_name: Some("Janes-MacBook-Pro".to_string()),
+            host_name: None,
+        }
+    );
+
+    let res = parse_text("", "  \n", "build01.example.com\n");
+    assert_eq!(res.computer_name, None);
+    assert_eq!(res.local_host_name, None);
+    assert_eq!(res.host_name.as_deref(), Some("build01.example.com"));
+
+    #[cfg(feature = "serde")]
+    {
+        let json = serde_json::to_value(&res).expect("Failed to serialize");
+        assert_eq!(json["host_name"], "build01.example.com");
+        assert!(json["computer_name"].is_null());
+    }
+}
+
+/// Acts like scutil on a machine with no `HostName` set
+#[cfg(feature = "system")]
+struct FakeScutil {
+    broken: bool,
+}
+
+#[cfg(feature = "system")]
+impl ScutilRunner for FakeScutil {
+    fn run(&self, args: &[&str]) -> io::Result<String> {
+        match args {
+            _ if self.broken => Err(io::Error::new(io::ErrorKind::NotFound, "no scutil")),
+            ["--get", "ComputerName"] => Ok("Jane's MacBook Pro\n".to_string()),
+            ["--get", "LocalHostName"] => Ok("Janes-MacBook-Pro\n".to_string()),
+            ["--get", name] => Err(io::Error::other(CommandFailed {
+                command: format!("scutil --get {}", name),
+                code: Some(1),
+                stderr: format!("{}: not set", name),
+            })),
+            _ => Err(io::Error::other("unexpected arguments")),
+        }
+    }
+}
+
+#[cfg(feature = "system")]
+#[test]
+fn test_hostnames_from_runner() {
+    let res = Hostnames::from_runner(&FakeScutil { broken: false }).expect("Should work");
+    assert_eq!(res.computer_name.as_deref(), Some("Jane's MacBook Pro"));
+    assert_eq!(res.local_host_name.as_deref(), Some("Janes-MacBook-Pro"));
+    assert_eq!(res.host_name, None);
+
+    // failing to run scutil isn't the same as a name that isn't set
+    let err = Hostnames::from_runner(&FakeScutil { broken: true }).expect_err("Should fail");
+    assert!(matches!(err, SystemError::NotFound(_)));
+}
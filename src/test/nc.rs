@@ -0,0 +1,155 @@
+use crate::dns::ParseError;
+use crate::nc::{parse_status_text, parse_text, ConnectionState, VpnService};
+use crate::scstore::StoreValue;
+
+#[test]
+fn test_nc_list_fixture() {
+    let filecontents =
+        std::fs::read_to_string("testdata/nc_list.txt").expect("Could not read nc fixture");
+    let res = parse_text(&filecontents).expect("Could not parse nc output");
+    dbg!(&res);
+
+    assert_eq!(res.len(), 4);
+    assert_eq!(
+        res[0],
+        VpnService {
+            enabled: true,
+            guid: "6B9A2C14-3F0E-4D7B-9A51-0C8E7D2F1A33".to_string(),
+            state: ConnectionState::Disconnected,
+            interface_type: "PPP".to_string(),
+            name: "Office L2TP".to_string(),
+            protocol: Some("PPP:L2TP".to_string()),
+        }
+    );
+
+    // brackets and parentheses in the name don't get mixed up with the protocol
+    assert!(res[1].is_connected());
+    assert_eq!(res[1].interface_type, "VPN");
+    assert_eq!(res[1].name, "Home [WireGuard]");
+    assert_eq!(res[1].protocol.as_deref(), Some("VPN:com.wireguard.macos"));
+
+    assert!(!res[2].enabled);
+    assert_eq!(res[2].interface_type, "IPSec");
+    assert_eq!(res[2].name, "Branch (Cisco) \"legacy\"");
+    assert_eq!(res[2].protocol.as_deref(), Some("IPSec"));
+
+    assert_eq!(res[3].state, ConnectionState::Connecting);
+    assert_eq!(res[3].name, "Corp: AnyConnect");
+    assert_eq!(res[3].protocol, None);
+
+    #[cfg(feature = "serde")]
+    {
+        let json = serde_json::to_value(&res).expect("Failed to serialize");
+        assert_eq!(json[1]["state"], "Connected");
+        assert_eq!(
+            serde_json::from_value::<Vec<VpnService>>(json).unwrap(),
+            res
+        );
+    }
+}
+
+#[test]
+fn test_nc_list_edge_cases() {
+    // no services at all
+    let res = parse_text("Available network connection services in the current set (*=enabled):\n")
+        .expect("Could not parse nc output");
+    assert!(res.is_empty());
+
+    let res = parse_text("* (Some New State) ABCD VPN \"[x] (y) \"z\"\" [VPN:x]\n")
+        .expect("Could not parse nc output");
+    assert_eq!(
+        res[0].state,
+        ConnectionState::Unknown("Some New State".to_string())
+    );
+    assert_eq!(res[0].name, "[x] (y) \"z\"");
+    assert_eq!(res[0].protocol.as_deref(), Some("VPN:x"));
+}
+
+#[test]
+fn test_nc_list_errors() {
+    for (input, line_no) in [
+        ("* Connected ABCD VPN \"Name\" [VPN]\n", 1),
+        ("\n* (Connected)\n", 2),
+        ("* (Connected) ABCD VPN Name [VPN]\n", 1),
+        ("* (Connected) ABCD VPN \"Name [VPN]\n", 1),
+        ("* (Connected) ABCD \"Name\" [VPN]\n", 1),
+        ("* (Connected) ABCD VPN \"Name\" VPN\n", 1),
+    ] {
+        let err = parse_text(input).expect_err(input);
+        assert_eq!(err.line_no(), line_no, "{:?}", err);
+    }
+    assert!(matches!(
+        parse_text("* (Connected) ABCD VPN \"Name [VPN]\n"),
+        Err(ParseError::InvalidValue { .. })
+    ));
+}
+
+#[test]
+fn test_nc_status_connected() {
+    let filecontents = std::fs::read_to_string("testdata/nc_status_ikev2.txt")
+        .expect("Could not read nc status fixture");
+    let res = parse_status_text(&filecontents).expect("Could not parse nc status output");
+    dbg!(&res);
+
+    assert!(res.is_connected());
+    assert_eq!(
+        res.extended
+            .get("IPSec")
+            .and_then(|ipsec| ipsec.get("ConnectTime"))
+            .and_then(StoreValue::as_str),
+        Some("1718032512")
+    );
+    assert_eq!(
+        res.extended
+            .get("IPv4")
+            .and_then(|ipv4| ipv4.get("Addresses"))
+            .and_then(StoreValue::as_array)
+            .map(|addresses| addresses.len()),
+        Some(1)
+    );
+
+    #[cfg(feature = "serde")]
+    {
+        let json = serde_json::to_value(&res).expect("Failed to serialize");
+        assert_eq!(json["state"], "Connected");
+        assert_eq!(json["extended"]["IPv4"]["ServerAddress"], "203.0.113.10");
+    }
+}
+
+#[test]
+fn test_nc_status_disconnected() {
+    let filecontents = std::fs::read_to_string("testdata/nc_status_disconnected.txt")
+        .expect("Could not read nc status fixture");
+    let res = parse_status_text(&filecontents).expect("Could not parse nc status output");
+    assert_eq!(res.state, ConnectionState::Disconnected);
+    assert_eq!(res.extended, StoreValue::Dictionary(Default::default()));
+
+    // an empty dictionary is fine too
+    let res = parse_status_text("Disconnected\nExtended Status <dictionary> {\n  Status : 0\n}\n")
+        .expect("Could not parse nc status output");
+    assert!(!res.is_connected());
+    assert_eq!(
+        res.extended.get("Status").and_then(StoreValue::as_str),
+        Some("0")
+    );
+}
+
+#[test]
+fn test_nc_status_errors() {
+    for (input, line_no) in [
+        ("\n", 1),
+        ("\nExtended Status <dictionary> {\n}\n", 2),
+        (
+            "Connected\nExtended Status <dictionary> {\n  Status : 2\n",
+            2,
+        ),
+        (
+            "Connected\nExtended Status <dictionary> {\n  Status\n}\n",
+            3,
+        ),
+        ("Connected\nConnected\n", 2),
+    ] {
+        let err = parse_status_text(input).expect_err(input);
+        assert_eq!(err.line_no(), line_no, "{:?}", err);
+    }
+}
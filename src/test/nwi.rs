@@ -0,0 +1,115 @@
+use std::net::IpAddr;
+
+use crate::dns::ParseError;
+#[cfg(feature = "serde")]
+use crate::nwi::NetworkInfo;
+use crate::nwi::{parse_text, NwiFlags};
+
+#[test]
+fn test_nwi_wifi() {
+    let filecontents =
+        std::fs::read_to_string("testdata/nwi_wifi.txt").expect("Could not read nwi fixture");
+    let res = parse_text(&filecontents).expect("Could not parse nwi output");
+    dbg!(&res);
+
+    assert_eq!(res.ipv4.len(), 1);
+    assert!(res.ipv6.is_empty());
+    let en0 = res.primary_ipv4().expect("Should have a primary interface");
+    assert_eq!(en0.name, "en0");
+    assert_eq!(en0.rank, 0);
+    assert_eq!(en0.flags.value, NwiFlags::HAS_IPV4 | NwiFlags::HAS_DNS);
+    assert_eq!(en0.flags.flags, vec!["IPv4", "DNS"]);
+    assert_eq!(
+        en0.addresses,
+        vec!["192.168.1.23".parse::<IpAddr>().unwrap()]
+    );
+    assert!(en0.reach.as_ref().is_some_and(|reach| reach.is_reachable()));
+    assert!(en0.vpn_server.is_none());
+
+    assert!(res.ipv4_reach.as_ref().unwrap().is_reachable());
+    assert!(!res.ipv6_reach.as_ref().unwrap().is_reachable());
+    assert_eq!(res.interfaces, vec!["en0"]);
+    assert_eq!(res.generated_at, None);
+}
+
+#[test]
+fn test_nwi_vpn() {
+    let filecontents =
+        std::fs::read_to_string("testdata/nwi_vpn.txt").expect("Could not read nwi fixture");
+    let res = parse_text(&filecontents).expect("Could not parse nwi output");
+    dbg!(&res);
+
+    let names: Vec<(&str, usize)> = res
+        .ipv4
+        .iter()
+        .map(|interface| (interface.name.as_str(), interface.rank))
+        .collect();
+    assert_eq!(names, vec![("utun3", 0), ("en7", 1), ("en0", 2)]);
+    assert_eq!(
+        res.ipv4[0].vpn_server,
+        Some("203.0.113.10".parse().unwrap())
+    );
+    assert!(res.ipv4[0]
+        .reach
+        .as_ref()
+        .unwrap()
+        .is_transient_connection());
+    assert!(res.ipv4[2].flags.not_in_list());
+    assert!(!res.ipv4[1].flags.not_in_list());
+
+    let en7 = res.primary_ipv6().expect("Should have a primary interface");
+    assert_eq!(en7.name, "en7");
+    assert!(en7.flags.has_ipv6() && !en7.flags.has_ipv4());
+    assert_eq!(
+        en7.addresses,
+        vec!["2001:db8:1::50".parse::<IpAddr>().unwrap()]
+    );
+
+    assert_eq!(res.interfaces, vec!["utun3", "en7", "en0"]);
+    assert_eq!(res.generated_at.as_deref(), Some("23405187459"));
+
+    #[cfg(feature = "serde")]
+    {
+        let json = serde_json::to_value(&res).expect("Failed to serialize");
+        assert_eq!(json["ipv4"][0]["vpn_server"], "203.0.113.10");
+        assert!(json["ipv4"][0].get("extra").is_none());
+        assert_eq!(serde_json::from_value::<NetworkInfo>(json).unwrap(), res);
+    }
+}
+
+#[test]
+fn test_nwi_unknown_lines() {
+    let input = "Network information\n\nIPv4 network interface information\n     en0 : flags      : 0x5 (IPv4,DNS)\n           signature  : 0123abcd\n";
+    let res = parse_text(input).expect("Could not parse nwi output");
+    assert_eq!(
+        res.ipv4[0].extra.get("signature").map(String::as_str),
+        Some("0123abcd")
+    );
+}
+
+#[test]
+fn test_nwi_errors() {
+    for (input, line_no) in [
+        ("DNS configuration\n", 1),
+        ("IPv4 network interface information\n  what\n", 2),
+        (
+            "IPv4 network interface information\n           address    : 10.0.0.1\n",
+            2,
+        ),
+        (
+            "IPv4 network interface information\n     en0 : flags      : 0x5 (IPv4,DNS)\n           address    : 10.0.0\n",
+            3,
+        ),
+        (
+            "IPv4 network interface information\n     en0 : flags      : IPv4\n",
+            2,
+        ),
+    ] {
+        let err = parse_text(input).expect_err(input);
+        assert_eq!(err.line_no(), line_no, "{:?}", err);
+    }
+    assert!(matches!(
+        parse_text("IPv6 network interface information\n   REACH : flags x\n"),
+        Err(ParseError::InvalidValue { line_no: 2, .. })
+    ));
+}
@@ -0,0 +1,80 @@
+use crate::dns::ParseError;
+use crate::proxy::parse_text;
+
+#[test]
+fn test_proxy_fixture() {
+    let filecontents =
+        std::fs::read_to_string("testdata/proxy.txt").expect("Could not read proxy fixture");
+    let res = parse_text(&filecontents).expect("Could not parse proxy output");
+    dbg!(&res);
+
+    assert_eq!(
+        res.exceptions_list,
+        vec!["*.local", "169.254/16", "intranet.example.com"]
+    );
+    assert!(res.exclude_simple_hostnames);
+    assert!(res.ftp_passive);
+    assert!(res.http.enabled);
+    assert_eq!(res.http.host.as_deref(), Some("proxy.example.com"));
+    assert_eq!(res.http.port, Some(3128));
+    assert!(res.https.enabled);
+    assert_eq!(res.https.port, Some(3128));
+    assert!(!res.ftp.enabled);
+    assert!(!res.socks.enabled);
+    assert!(res.auto_config_enabled);
+    assert_eq!(
+        res.auto_config_url.as_deref(),
+        Some("http://wpad.example.com/proxy.pac")
+    );
+    assert!(!res.auto_discovery_enabled);
+    assert!(res.extra.is_empty());
+
+    let en0 = res.scoped.get("en0").expect("Should have en0 settings");
+    assert_eq!(en0.exceptions_list.len(), 2);
+    assert_eq!(en0.http.port, Some(3128));
+    assert!(!en0.https.enabled);
+
+    #[cfg(feature = "serde")]
+    {
+        let json = serde_json::to_value(&res).expect("Failed to serialize");
+        assert_eq!(json["http"]["host"], "proxy.example.com");
+        assert!(json["scoped"]["en0"].get("scoped").is_none());
+    }
+}
+
+#[test]
+fn test_proxy_unknown_keys() {
+    let input = "<dictionary> {\n  GopherEnable : 0\n  HTTPUser : someone\n}\n";
+    let res = parse_text(input).expect("Could not parse proxy output");
+    assert_eq!(res.extra.get("GopherEnable").map(String::as_str), Some("0"));
+    assert_eq!(
+        res.extra.get("HTTPUser").map(String::as_str),
+        Some("someone")
+    );
+}
+
+#[test]
+fn test_proxy_errors() {
+    let err = parse_text("DNS configuration\n").unwrap_err();
+    assert!(matches!(err, ParseError::UnexpectedLine { line_no: 1, .. }));
+
+    let err = parse_text("<dictionary> {\n  HTTPEnable : yes\n}\n").unwrap_err();
+    assert!(matches!(err, ParseError::InvalidValue { line_no: 2, .. }));
+
+    let err = parse_text("<dictionary> {\n  HTTPPort : proxy\n}\n").unwrap_err();
+    assert!(matches!(err, ParseError::InvalidInteger { line_no: 2, .. }));
+
+    let err =
+        parse_text("<dictionary> {\n  ExceptionsList : <array> {\n    0 : *.local\n").unwrap_err();
+    assert!(matches!(
+        err,
+        ParseError::MissingField {
+            line_no: 2,
+            field: "closing '}'",
+            ..
+        }
+    ));
+
+    let err = parse_text("<dictionary> {\n}\nleftovers\n").unwrap_err();
+    assert!(matches!(err, ParseError::UnexpectedLine { line_no: 3, .. }));
+}
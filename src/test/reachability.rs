@@ -0,0 +1,112 @@
+use crate::dns::ParseError;
+#[cfg(feature = "serde")]
+use crate::reachability::Reachability;
+use crate::reachability::{parse_text, ReachabilityFlag, ReachabilityFlags};
+
+#[test]
+fn test_reachability_fixture() {
+    let filecontents = std::fs::read_to_string("testdata/reachability.txt")
+        .expect("Could not read reachability fixture");
+    let res = parse_text(&filecontents).expect("Could not parse reachability output");
+    dbg!(&res);
+
+    assert!(res.is_reachable());
+    assert!(res.reach.is_direct());
+    assert_eq!(
+        res.reach.value,
+        ReachabilityFlags::REACHABLE | ReachabilityFlags::IS_DIRECT
+    );
+    assert!(matches!(
+        res.reach.flags.as_slice(),
+        [
+            ReachabilityFlag::Reachable,
+            ReachabilityFlag::DirectlyReachableAddress
+        ]
+    ));
+
+    #[cfg(feature = "serde")]
+    {
+        let json = serde_json::to_value(&res).expect("Failed to serialize");
+        assert_eq!(json["reach"]["value"], 0x00020002);
+        assert_eq!(json["reach"]["reachable"], true);
+        assert!(json.get("host").is_none());
+        // the computed `reachable` is ignored when reading it back
+        assert_eq!(serde_json::from_value::<Reachability>(json).unwrap(), res);
+    }
+}
+
+#[test]
+fn test_reachability_outputs() {
+    let res = parse_text("Not Reachable\n").expect("Could not parse");
+    assert!(!res.is_reachable());
+    assert_eq!(res.reach.value, 0);
+
+    let res = parse_text("Reachable,Transient Connection,Connection Required,Some Future Thing\n")
+        .expect("Could not parse");
+    assert_eq!(res.reach.value, 0x7);
+    assert_eq!(res.reach.flags.len(), 4);
+
+    // the same as a `reach` line
+    let res = parse_text("0x00000003 (Reachable,Transient Connection)\n").expect("Could not parse");
+    assert_eq!(res.reach.value, 3);
+    assert_eq!(res.reach.flags.len(), 2);
+}
+
+#[test]
+fn test_reachability_space_separated() {
+    let res = parse_text("Reachable\n").expect("Could not parse");
+    assert_eq!(res.reach.flags, vec![ReachabilityFlag::Reachable]);
+    assert_eq!(res.reach.value, ReachabilityFlags::REACHABLE);
+
+    let res = parse_text("Reachable Transient Connection Connection Required\n")
+        .expect("Could not parse");
+    assert_eq!(
+        res.reach.flags,
+        vec![
+            ReachabilityFlag::Reachable,
+            ReachabilityFlag::TransientConnection,
+            ReachabilityFlag::ConnectionRequired
+        ]
+    );
+    assert_eq!(res.reach.value, 0x7);
+
+    // `Reachable` on its own is a flag, but here it's part of a longer one
+    let res = parse_text("Directly Reachable Address Local Address\n").expect("Could not parse");
+    assert_eq!(
+        res.reach.flags,
+        vec![
+            ReachabilityFlag::DirectlyReachableAddress,
+            ReachabilityFlag::LocalAddress
+        ]
+    );
+
+    let res = parse_text("Not Reachable\n").expect("Could not parse");
+    assert_eq!(res.reach.flags, vec![ReachabilityFlag::NotReachable]);
+    assert!(!res.is_reachable());
+
+    let res = parse_text("Reachable Sparkly WWAN\n").expect("Could not parse");
+    assert_eq!(
+        res.reach.flags,
+        vec![
+            ReachabilityFlag::Reachable,
+            ReachabilityFlag::Unknown("Sparkly".to_string()),
+            ReachabilityFlag::WWAN
+        ]
+    );
+}
+
+#[test]
+fn test_reachability_errors() {
+    assert!(matches!(
+        parse_text("\n").unwrap_err(),
+        ParseError::MissingField { .. }
+    ));
+    assert!(matches!(
+        parse_text("Reachable\nReachable\n").unwrap_err(),
+        ParseError::UnexpectedLine { line_no: 2, .. }
+    ));
+    assert!(matches!(
+        parse_text("0xzz (Reachable)\n").unwrap_err(),
+        ParseError::InvalidValue { line_no: 1, .. }
+    ));
+}
@@ -0,0 +1,137 @@
+use std::net::IpAddr;
+
+use crate::dns::{parse_text, ParseError, Resolver};
+use crate::resolvconf::{self, Mismatch};
+
+#[test]
+fn test_resolv_conf() {
+    let filecontents =
+        std::fs::read_to_string("testdata/resolv_conf.txt").expect("Could not read fixture");
+    let res = resolvconf::parse_text(&filecontents).expect("Could not parse fixture");
+    dbg!(&res);
+
+    assert_eq!(res.nameservers.len(), 3);
+    assert_eq!(res.nameservers[1].to_string(), "10.0.0.12");
+    assert_eq!(res.search, vec!["subdomain.example.com"]);
+    assert_eq!(res.domain, None);
+    assert!(res.options.is_empty());
+    assert!(res.extra.is_empty());
+}
+
+#[test]
+fn test_resolv_conf_vpn() {
+    let filecontents =
+        std::fs::read_to_string("testdata/resolv_conf_vpn.txt").expect("Could not read fixture");
+    let res = resolvconf::parse_text(&filecontents).expect("Could not parse fixture");
+    dbg!(&res);
+
+    assert_eq!(res.nameservers[2].scope.as_deref(), Some("en0"));
+    assert!(res.search.is_empty());
+    // falls back to the domain
+    assert_eq!(res.search_domains(), vec!["corp.example.com"]);
+    assert_eq!(res.options, vec!["ndots:2", "timeout:3", "rotate"]);
+    assert_eq!(
+        res.extra.get("sortlist").map(String::as_str),
+        Some("10.8.0.0/255.255.0.0")
+    );
+}
+
+#[test]
+fn test_resolv_conf_errors() {
+    assert_eq!(resolvconf::parse_text("").unwrap(), Default::default());
+
+    let err = resolvconf::parse_text("; comment\nnameserver 10.0.0.300\n").unwrap_err();
+    assert!(matches!(
+        err,
+        ParseError::InvalidNameserver { line_no: 2, .. }
+    ));
+
+    let err = resolvconf::parse_text("nameserver\n").unwrap_err();
+    assert!(matches!(err, ParseError::MissingField { line_no: 1, .. }));
+}
+
+#[test]
+fn test_matches_resolv_conf() {
+    let filecontents = std::fs::read_to_string("testdata.txt").expect("Could not read fixture");
+    let config = parse_text(&filecontents).expect("Could not parse fixture");
+
+    let filecontents =
+        std::fs::read_to_string("testdata/resolv_conf.txt").expect("Could not read fixture");
+    let resolv_conf = resolvconf::parse_text(&filecontents).unwrap();
+    assert_eq!(config.matches_resolv_conf(&resolv_conf), vec![]);
+
+    // the nameservers can be in any order, and case and trailing dots don't matter for domains
+    let resolv_conf = resolvconf::parse_text(
+        "search SUBDOMAIN.example.com.\nnameserver 10.0.0.1\nnameserver 10.0.0.12\nnameserver 1234:1234:fd:0:ffe:66ff:2345:b33f\n",
+    )
+    .unwrap();
+    assert_eq!(config.matches_resolv_conf(&resolv_conf), vec![]);
+
+    let filecontents =
+        std::fs::read_to_string("testdata/resolv_conf_vpn.txt").expect("Could not read fixture");
+    let resolv_conf = resolvconf::parse_text(&filecontents).unwrap();
+    let mismatches = config.matches_resolv_conf(&resolv_conf);
+    dbg!(&mismatches);
+    assert_eq!(
+        mismatches,
+        vec![
+            Mismatch::MissingNameserver("1234:1234:fd:0:ffe:66ff:2345:b33f".parse().unwrap()),
+            Mismatch::MissingNameserver("10.0.0.12".parse().unwrap()),
+            Mismatch::ExtraNameserver("10.8.0.1".parse().unwrap()),
+            Mismatch::ExtraNameserver("fe80::1".parse().unwrap()),
+            Mismatch::SearchDomains {
+                scutil: vec!["subdomain.example.com".to_string()],
+                resolv_conf: vec!["corp.example.com".to_string()],
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_to_resolv_conf() {
+    let filecontents = std::fs::read_to_string("testdata.txt").expect("Could not read fixture");
+    let res = parse_text(&filecontents).expect("Could not parse fixture");
+    let expected = std::fs::read_to_string("testdata/resolv_conf_generated.txt")
+        .expect("Could not read expected output");
+    let generated = res.to_resolv_conf();
+    assert_eq!(generated, expected);
+
+    // and reading it back agrees with scutil
+    let resolv_conf = resolvconf::parse_text(&generated).expect("Could not parse generated");
+    assert!(res.matches_resolv_conf(&resolv_conf).is_empty());
+
+    // the mdns resolvers are never used
+    let mut mdns_only = res.clone();
+    mdns_only.dns_config.remove(0);
+    assert_eq!(
+        mdns_only.to_resolv_conf(),
+        "# scutil --dns doesn't have a default resolver\n"
+    );
+}
+
+#[test]
+fn test_to_resolv_conf_limits() {
+    let mut builder = Resolver::builder(1).timeout_secs(3);
+    for index in 0..8 {
+        builder = builder.search_domain(format!("d{}.example.com", index));
+    }
+    for last in 1..=5 {
+        builder = builder.nameserver(IpAddr::from([10, 0, 0, last]));
+    }
+    let mut resolver = builder.build().unwrap();
+    resolver.nameservers[1].port = Some(5353);
+
+    assert_eq!(
+        resolver.to_resolv_conf(),
+        "# generated from resolver #1 of scutil --dns
+search d0.example.com d1.example.com d2.example.com d3.example.com d4.example.com d5.example.com
+# left out search domains past the first 6: d6.example.com d7.example.com
+nameserver 10.0.0.1
+nameserver 10.0.0.3
+nameserver 10.0.0.4
+# left out nameservers past the first 3: 10.0.0.5
+# left out nameservers that aren't on port 53: 10.0.0.2:5353
+options timeout:3
+"
+    );
+}
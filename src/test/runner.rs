@@ -0,0 +1,121 @@
+use std::cell::RefCell;
+use std::io;
+use std::os::unix::fs::PermissionsExt;
+
+use crate::dns::{parse_text, DNSConfig};
+use crate::reachability::Reachability;
+use crate::runner::{ScutilRunner, SystemError, SystemScutil};
+
+/// Hands back the fixtures instead of running scutil, and keeps track of what it was asked for
+#[derive(Default)]
+struct FakeScutil {
+    calls: RefCell<Vec<Vec<String>>>,
+}
+
+impl ScutilRunner for FakeScutil {
+    fn run(&self, args: &[&str]) -> io::Result<String> {
+        self.calls
+            .borrow_mut()
+            .push(args.iter().map(|arg| arg.to_string()).collect());
+        let fixture = match args {
+            ["--dns"] => "testdata.txt",
+            ["--proxy"] => "testdata/proxy.txt",
+            ["--nwi"] => "testdata/nwi_wifi.txt",
+            ["--nc", "list"] => "testdata/nc_list.txt",
+            ["-r", _] => "testdata/reachability.txt",
+            _ => return Err(io::Error::new(io::ErrorKind::NotFound, "no such fixture")),
+        };
+        std::fs::read_to_string(fixture)
+    }
+}
+
+#[test]
+fn test_fake_runner() {
+    let runner = FakeScutil::default();
+
+    let res = parse_text(&runner.run_dns().expect("Failed to run")).expect("Failed to parse");
+    assert_eq!(res.dns_config.len(), 7);
+
+    let res = crate::proxy::parse_text(&runner.run_proxy().expect("Failed to run"))
+        .expect("Failed to parse");
+    assert!(res.http.enabled);
+
+    let res = crate::reachability::parse_text(
+        &runner.run_reachability("8.8.8.8").expect("Failed to run"),
+    )
+    .expect("Failed to parse");
+    assert!(res.is_reachable());
+
+    let res =
+        crate::nwi::parse_text(&runner.run_nwi().expect("Failed to run")).expect("Failed to parse");
+    assert_eq!(res.ipv4.len(), 1);
+
+    let res = crate::nc::parse_text(&runner.run_nc_list().expect("Failed to run"))
+        .expect("Failed to parse");
+    assert_eq!(res.len(), 4);
+
+    assert!(runner.run(&["--prefs"]).is_err());
+    assert_eq!(
+        runner.calls.borrow().as_slice(),
+        [
+            vec!["--dns"],
+            vec!["--proxy"],
+            vec!["-r", "8.8.8.8"],
+            vec!["--nwi"],
+            vec!["--nc", "list"],
+            vec!["--prefs"]
+        ]
+    );
+}
+
+#[test]
+fn test_reachability_from_runner() {
+    let runner = FakeScutil::default();
+    let res = Reachability::from_runner(&runner, "example.com").expect("Failed to check");
+    assert_eq!(res.host.as_deref(), Some("example.com"));
+    assert!(res.reach.is_direct());
+    assert_eq!(
+        runner.calls.borrow().as_slice(),
+        [vec!["-r", "example.com"]]
+    );
+}
+
+#[test]
+fn test_dns_from_runner() {
+    let res = DNSConfig::from_runner(&FakeScutil::default()).expect("Failed to run");
+    assert_eq!(res.dns_config.len(), 7);
+}
+
+#[test]
+fn test_system_errors() {
+    let err = DNSConfig::from_runner(&SystemScutil::with_program("/nonexistent/scutil"))
+        .expect_err("Should fail");
+    assert!(matches!(err, SystemError::NotFound(_)), "{:?}", err);
+
+    let err =
+        DNSConfig::from_runner(&SystemScutil::with_program("false")).expect_err("Should fail");
+    let SystemError::Failed(failed) = err else {
+        panic!("Wrong error {:?}", err);
+    };
+    assert_eq!(failed.code, Some(1));
+    assert_eq!(failed.command, "false --dns");
+
+    // a stub which prints something that isn't UTF-8, then one that isn't scutil
+    let script = std::env::temp_dir().join(format!("scutil-parser-utf8-{}", std::process::id()));
+    std::fs::write(
+        &script,
+        "#!/bin/sh\nprintf 'DNS configuration\\n\\377\\n'\n",
+    )
+    .expect("Failed to write stub");
+    std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755))
+        .expect("Failed to make stub executable");
+    let err =
+        DNSConfig::from_runner(&SystemScutil::with_program(&script)).expect_err("Should fail");
+    assert!(matches!(err, SystemError::InvalidUtf8(_)), "{:?}", err);
+
+    std::fs::write(&script, "#!/bin/sh\necho 'not scutil'\n").expect("Failed to write stub");
+    let err =
+        DNSConfig::from_runner(&SystemScutil::with_program(&script)).expect_err("Should fail");
+    assert!(matches!(err, SystemError::Parse(_)), "{:?}", err);
+    std::fs::remove_file(&script).ok();
+}
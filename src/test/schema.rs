@@ -0,0 +1,39 @@
+use crate::dns::{parse_text, parse_text_lenient, DNSConfig};
+
+#[test]
+fn test_schema_validates_fixtures() {
+    let schema = serde_json::to_value(DNSConfig::schema()).expect("Failed to serialize schema");
+    let validator = jsonschema::validator_for(&schema).expect("Schema should be valid");
+
+    for filename in [
+        "testdata.txt",
+        "testdata/vpn_supplemental.txt",
+        "testdata/link_local.txt",
+        "testdata/service_specific.txt",
+        "testdata/managed_profile.txt",
+        "testdata/mdns_section.txt",
+        "testdata/sortlist.txt",
+        "testdata/unknown_field.txt",
+    ] {
+        let filecontents = std::fs::read_to_string(filename).expect("Could not read fixture");
+        // lenient, for the unknown field
+        let res = parse_text_lenient(&filecontents).expect("Could not parse fixture");
+        let json = serde_json::to_value(&res).expect("Failed to serialize");
+        let errors: Vec<String> = validator
+            .iter_errors(&json)
+            .map(|err| err.to_string())
+            .collect();
+        assert!(errors.is_empty(), "{}: {:?}", filename, errors);
+    }
+
+    // and it does catch things
+    let mut json = serde_json::to_value(
+        parse_text(&std::fs::read_to_string("testdata.txt").unwrap()).unwrap(),
+    )
+    .unwrap();
+    json["dns_config"][1]["timeout_secs"] = serde_json::json!("5");
+    assert!(!validator.is_valid(&json));
+    json["dns_config"][1]["timeout_secs"] = serde_json::json!(5);
+    json["dns_config"][0]["reach"]["reachable"] = serde_json::json!(null);
+    assert!(!validator.is_valid(&json));
+}
@@ -0,0 +1,74 @@
+use std::collections::BTreeMap;
+
+use crate::dns::ParseError;
+use crate::scstore::{parse_text, StoreValue};
+
+#[test]
+fn test_scstore_flat() {
+    let filecontents = std::fs::read_to_string("testdata/scstore_global_ipv4.txt")
+        .expect("Could not read store fixture");
+    let res = parse_text(&filecontents).expect("Could not parse store output");
+    dbg!(&res);
+
+    assert_eq!(
+        res.get("PrimaryInterface").and_then(StoreValue::as_str),
+        Some("en0")
+    );
+    assert_eq!(res.as_dictionary().map(BTreeMap::len), Some(3));
+    assert!(res.get("Missing").is_none());
+
+    #[cfg(feature = "serde")]
+    {
+        let json = serde_json::to_value(&res).expect("Failed to serialize");
+        assert_eq!(json["Router"], "192.168.1.1");
+    }
+}
+
+#[test]
+fn test_scstore_nested() {
+    let filecontents = std::fs::read_to_string("testdata/scstore_service_dns.txt")
+        .expect("Could not read store fixture");
+    let res = parse_text(&filecontents).expect("Could not parse store output");
+    dbg!(&res);
+
+    let addresses: Vec<&str> = res
+        .get("ServerAddresses")
+        .and_then(StoreValue::as_array)
+        .expect("Should have server addresses")
+        .iter()
+        .filter_map(StoreValue::as_str)
+        .collect();
+    assert_eq!(addresses, vec!["10.8.0.1", "10.8.0.2", "fd00:8::1"]);
+    assert_eq!(
+        res.get("__OVERRIDE_PRIMARY__")
+            .and_then(|value| value.get("Options"))
+            .and_then(|value| value.get("Timeout"))
+            .and_then(StoreValue::as_str),
+        Some("5")
+    );
+    // a string isn't a dictionary
+    assert!(res.get("DomainName").unwrap().get("x").is_none());
+
+    #[cfg(feature = "serde")]
+    {
+        let json = serde_json::to_value(&res).expect("Failed to serialize");
+        assert_eq!(json["SearchDomains"][1], "example.com");
+        assert_eq!(json["__OVERRIDE_PRIMARY__"]["Options"]["Timeout"], "5");
+        assert_eq!(serde_json::from_value::<StoreValue>(json).unwrap(), res);
+    }
+}
+
+#[test]
+fn test_scstore_errors() {
+    assert_eq!(
+        parse_text("\n").unwrap(),
+        StoreValue::Dictionary(BTreeMap::new())
+    );
+
+    let err = parse_text("  No such key\n").unwrap_err();
+    assert!(matches!(err, ParseError::UnexpectedLine { line_no: 1, .. }));
+
+    let err =
+        parse_text("<dictionary> {\n  Addresses : <array> {\n    0 : 10.0.0.1\n}\n").unwrap_err();
+    assert!(matches!(err, ParseError::MissingField { line_no: 1, .. }));
+}
@@ -0,0 +1,136 @@
+use std::net::IpAddr;
+
+use crate::dns::ResolverOption;
+use crate::windows::{parse_csv, parse_netsh, parse_text, split_csv_line};
+
+fn nameservers(resolver: &crate::dns::Resolver) -> Vec<IpAddr> {
+    resolver
+        .nameservers
+        .iter()
+        .map(|ns| ns.address.address)
+        .collect()
+}
+
+#[test]
+fn test_netsh_fixture() {
+    let filecontents = std::fs::read_to_string("testdata/windows_netsh.txt")
+        .expect("Could not read netsh fixture");
+    let res = parse_netsh(&filecontents).expect("Could not parse netsh output");
+    dbg!(&res);
+
+    assert!(res.dns_config.is_empty());
+    assert_eq!(res.scoped_dns_config.len(), 2);
+
+    let ethernet = &res.scoped_dns_config[0];
+    assert_eq!(ethernet.id, 1);
+    // netsh doesn't print the interface index, so there isn't one to make up
+    assert!(ethernet.if_index.is_none());
+    assert_eq!(
+        ethernet.extra.get("interface").map(String::as_str),
+        Some("Ethernet 2")
+    );
+    assert_eq!(
+        ethernet.options.as_ref().map(|o| o.0.clone()),
+        Some(vec![ResolverOption::Unknown("dhcp".to_string())])
+    );
+    assert_eq!(
+        nameservers(ethernet),
+        vec![
+            "192.168.1.1".parse::<IpAddr>().unwrap(),
+            "fe80::1".parse().unwrap()
+        ]
+    );
+
+    let wifi = &res.scoped_dns_config[1];
+    assert_eq!(wifi.id, 2);
+    assert!(wifi.if_index.is_none());
+    assert_eq!(
+        wifi.extra.get("interface").map(String::as_str),
+        Some("Wi-Fi")
+    );
+    assert_eq!(
+        wifi.options.as_ref().map(|o| o.0.clone()),
+        Some(vec![ResolverOption::Unknown("static".to_string())])
+    );
+    assert_eq!(
+        nameservers(wifi),
+        vec![
+            "1.1.1.1".parse::<IpAddr>().unwrap(),
+            "8.8.8.8".parse().unwrap()
+        ]
+    );
+}
+
+#[test]
+fn test_netsh_interfaces_without_index() {
+    let input = "Configuration for interface \"Ethernet\"\n    Statically Configured DNS Servers:    10.0.0.1\n\nConfiguration for interface \"Ethernet 3\"\n    DNS servers configured through DHCP:  10.0.0.2\n";
+    let res = parse_netsh(input).expect("Could not parse netsh output");
+
+    // without an index, the two interfaces shouldn't look like the same one
+    let interfaces: Vec<(usize, Option<usize>, Option<&str>)> = res
+        .scoped_dns_config
+        .iter()
+        .map(|resolver| {
+            (
+                resolver.id,
+                resolver.if_index.as_ref().map(|i| i.index),
+                resolver.extra.get("interface").map(String::as_str),
+            )
+        })
+        .collect();
+    assert_eq!(
+        interfaces,
+        vec![(1, None, Some("Ethernet")), (2, None, Some("Ethernet 3"))]
+    );
+}
+
+#[test]
+fn test_csv_fixture() {
+    let filecontents = std::fs::read_to_string("testdata/windows_dnsclient.csv")
+        .expect("Could not read CSV fixture");
+    let res = parse_csv(&filecontents).expect("Could not parse CSV output");
+    dbg!(&res);
+
+    assert_eq!(res.scoped_dns_config.len(), 3);
+    let ethernet = &res.scoped_dns_config[0];
+    assert_eq!(ethernet.if_index.as_ref().map(|i| i.index), Some(12));
+    assert_eq!(nameservers(ethernet).len(), 3);
+
+    let loopback = &res.scoped_dns_config[2];
+    assert_eq!(loopback.id, 3);
+    assert_eq!(
+        loopback
+            .if_index
+            .as_ref()
+            .and_then(|i| i.interface.as_deref()),
+        Some("Loopback Pseudo-Interface 1")
+    );
+}
+
+#[test]
+fn test_detects_format() {
+    let netsh = std::fs::read_to_string("testdata/windows_netsh.txt").unwrap();
+    let csv = std::fs::read_to_string("testdata/windows_dnsclient.csv").unwrap();
+    assert_eq!(parse_text(&netsh).unwrap().scoped_dns_config.len(), 2);
+    assert_eq!(parse_text(&csv).unwrap().scoped_dns_config.len(), 3);
+
+    // PowerShell likes to write a byte order mark
+    let csv = format!("\u{feff}{}", csv);
+    assert_eq!(parse_text(&csv).unwrap().scoped_dns_config.len(), 3);
+    assert_eq!(parse_csv(&csv).unwrap().scoped_dns_config.len(), 3);
+}
+
+#[test]
+fn test_csv_errors() {
+    assert!(parse_csv("\"InterfaceAlias\",\"ServerAddresses\"\n").is_err());
+    let unjoined = "\"InterfaceAlias\",\"InterfaceIndex\",\"ServerAddresses\"\n\"Wi-Fi\",\"7\",\"System.String[]\"\n";
+    assert!(parse_csv(unjoined).is_err());
+}
+
+#[test]
+fn test_split_csv_line() {
+    assert_eq!(
+        split_csv_line(r#""a","b ""quoted""",,c"#),
+        vec!["a", "b \"quoted\"", "", "c"]
+    );
+}
@@ -0,0 +1,269 @@
+//! Parsers for the Windows DNS client configuration, mapped onto the same [DNSConfig] model as
+//! the macOS output so there's one JSON shape regardless of OS.
+//!
+//! Each interface with DNS servers becomes a scoped [Resolver], with the interface name stored
+//! in `if_index`. `netsh` doesn't print interface indexes, so for it `if_index` is left as `None`
+//! and the name goes in `extra` under `interface`. Where the source says how the servers were
+//! configured, `options` is set to `dhcp` or `static`.
+
+use std::str::FromStr;
+
+use crate::dns::{
+    strip_bom, DNSConfig, InterfaceIndex, Nameserver, NameserverAddress, ParseError, Resolver,
+    ResolverFlags, ResolverOption, ResolverOptions,
+};
+
+/// Parses either `netsh` or CSV output, based on what the input looks like
+pub fn parse_text(input: &str) -> Result<DNSConfig, ParseError> {
+    let input = strip_bom(input);
+    let first_line = input
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with('#'));
+    match first_line {
+        Some(line) if line.starts_with('"') => parse_csv(input),
+        _ => parse_netsh(input),
+    }
+}
+
+/// Parses the output of `netsh interface ip show dnsservers`.
+///
+/// Interfaces that have no DNS servers configured are skipped.
+pub fn parse_netsh(input: &str) -> Result<DNSConfig, ParseError> {
+    let input = strip_bom(input);
+    let mut dns_config = DNSConfig {
+        dns_config: Vec::new(),
+        scoped_dns_config: Vec::new(),
+        service_specific_dns_config: Vec::new(),
+        metadata: Default::default(),
+        other_sections: Default::default(),
+    };
+    let mut current_resolver: Option<Resolver> = None;
+    // whether the lines we're reading are continuations of a list of DNS servers
+    let mut in_servers = false;
+
+    for (line_index, line) in input.lines().enumerate() {
+        let line_no = line_index + 1;
+        let trimmed = line.trim();
+
+        if let Some(interface) = trimmed.strip_prefix("Configuration for interface ") {
+            let interface = interface
+                .strip_prefix('"')
+                .and_then(|i| i.strip_suffix('"'))
+                .ok_or_else(|| ParseError::InvalidValue {
+                    line_no,
+                    content: line.to_string(),
+                    message: "Interface name should be quoted".to_string(),
+                })?;
+            finish_resolver(&mut dns_config, current_resolver.take());
+            current_resolver = Some(new_resolver(
+                dns_config.scoped_dns_config.len() + 1,
+                None,
+                interface,
+            ));
+            in_servers = false;
+            continue;
+        }
+
+        let Some(resolver) = current_resolver.as_mut() else {
+            // there's nothing interesting before the first interface
+            continue;
+        };
+
+        if trimmed.is_empty() {
+            in_servers = false;
+        } else if in_servers && NameserverAddress::from_str(trimmed).is_ok() {
+            add_nameserver(resolver, trimmed, line_no, line)?;
+        } else if let Some((key, value)) = trimmed.split_once(':') {
+            in_servers = false;
+            let source = if key.contains("DHCP") {
+                "dhcp"
+            } else if key.starts_with("Statically Configured") {
+                "static"
+            } else {
+                // "Register with which suffix" and friends aren't part of the model
+                continue;
+            };
+            resolver.options = Some(ResolverOptions(vec![ResolverOption::Unknown(
+                source.to_string(),
+            )]));
+            in_servers = true;
+            let value = value.trim();
+            if !value.is_empty() && value != "None" {
+                add_nameserver(resolver, value, line_no, line)?;
+            }
+        }
+    }
+    finish_resolver(&mut dns_config, current_resolver);
+
+    Ok(dns_config)
+}
+
+/// Parses the output of `Get-DnsClientServerAddress` exported with `ConvertTo-Csv`.
+///
+/// The `InterfaceAlias`, `InterfaceIndex` and `ServerAddresses` columns are required. PowerShell
+/// exports arrays as `System.String[]`, so the addresses need to be joined first, for example:
+///
+/// ```text
+/// Get-DnsClientServerAddress |
+///     Select-Object InterfaceAlias,InterfaceIndex,AddressFamily,@{n='ServerAddresses';e={$_.ServerAddresses -join ' '}} |
+///     ConvertTo-Csv -NoTypeInformation
+/// ```
+///
+/// Rows for the same interface (eg IPv4 and IPv6) are merged into one resolver, and interfaces
+/// with no DNS servers are skipped.
+pub fn parse_csv(input: &str) -> Result<DNSConfig, ParseError> {
+    let input = strip_bom(input);
+    let mut resolvers: Vec<Resolver> = Vec::new();
+    let mut columns: Option<(usize, usize, usize)> = None;
+
+    for (line_index, line) in input.lines().enumerate() {
+        let line_no = line_index + 1;
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields = split_csv_line(line);
+
+        let Some((alias_col, index_col, servers_col)) = columns else {
+            let find = |name: &str| {
+                fields
+                    .iter()
+                    .position(|f| f == name)
+                    .ok_or_else(|| ParseError::InvalidValue {
+                        line_no,
+                        content: line.to_string(),
+                        message: format!("Missing {} column in CSV header", name),
+                    })
+            };
+            columns = Some((
+                find("InterfaceAlias")?,
+                find("InterfaceIndex")?,
+                find("ServerAddresses")?,
+            ));
+            continue;
+        };
+
+        let field = |col: usize| {
+            fields
+                .get(col)
+                .map(|f| f.as_str())
+                .ok_or_else(|| ParseError::InvalidValue {
+                    line_no,
+                    content: line.to_string(),
+                    message: format!("Row is missing column {}", col + 1),
+                })
+        };
+        let alias = field(alias_col)?;
+        let if_index: usize =
+            field(index_col)?
+                .parse()
+                .map_err(|source| ParseError::InvalidInteger {
+                    line_no,
+                    content: line.to_string(),
+                    source,
+                })?;
+
+        let position = match resolvers
+            .iter()
+            .position(|r| r.if_index.as_ref().map(|i| i.index) == Some(if_index))
+        {
+            Some(position) => position,
+            None => {
+                resolvers.push(new_resolver(resolvers.len() + 1, Some(if_index), alias));
+                resolvers.len() - 1
+            }
+        };
+        for server in field(servers_col)?.split([' ', ',', ';']) {
+            if !server.is_empty() {
+                add_nameserver(&mut resolvers[position], server, line_no, line)?;
+            }
+        }
+    }
+
+    let mut dns_config = DNSConfig {
+        dns_config: Vec::new(),
+        scoped_dns_config: Vec::new(),
+        service_specific_dns_config: Vec::new(),
+        metadata: Default::default(),
+        other_sections: Default::default(),
+    };
+    for resolver in resolvers {
+        finish_resolver(&mut dns_config, Some(resolver));
+    }
+    // ids are handed out as interfaces are seen, so renumber after dropping empty ones
+    for (id, resolver) in dns_config.scoped_dns_config.iter_mut().enumerate() {
+        resolver.id = id + 1;
+    }
+    Ok(dns_config)
+}
+
+/// A scoped resolver for the interface, with the name in `extra` when there's no index to go
+/// with it
+fn new_resolver(id: usize, index: Option<usize>, interface: &str) -> Resolver {
+    let mut resolver = Resolver::new(id);
+    match index {
+        Some(index) => {
+            resolver.if_index = Some(InterfaceIndex {
+                index,
+                interface: Some(interface.to_string()),
+            })
+        }
+        None => {
+            resolver
+                .extra
+                .insert("interface".to_string(), interface.to_string());
+        }
+    }
+    resolver.flags = Some(vec![ResolverFlags::Scoped]);
+    resolver
+}
+
+fn add_nameserver(
+    resolver: &mut Resolver,
+    address: &str,
+    line_no: usize,
+    line: &str,
+) -> Result<(), ParseError> {
+    let address =
+        NameserverAddress::from_str(address).map_err(|source| ParseError::InvalidNameserver {
+            line_no,
+            content: line.to_string(),
+            source,
+        })?;
+    resolver.add_nameserver(Nameserver {
+        index: resolver.nameservers.len(),
+        address,
+        port: None,
+    });
+    Ok(())
+}
+
+fn finish_resolver(dns_config: &mut DNSConfig, resolver: Option<Resolver>) {
+    if let Some(resolver) = resolver {
+        if !resolver.nameservers.is_empty() {
+            dns_config.scoped_dns_config.push(resolver);
+        }
+    }
+}
+
+/// Splits a CSV row into fields, handling quoting and `""` escapes
+pub(crate) fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.trim_end().chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
@@ -0,0 +1,395 @@
+#![cfg(unix)]
+
+use std::io::Write;
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+/// A directory holding a fake `scutil` which prints the test fixtures, so the CLI can be
+/// exercised on hosts that aren't running macOS. It's removed when dropped.
+struct FakeScutilDir {
+    path: PathBuf,
+}
+
+impl FakeScutilDir {
+    fn join(&self, name: &str) -> PathBuf {
+        self.path.join(name)
+    }
+}
+
+impl Drop for FakeScutilDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
+
+fn fake_scutil_dir(name: &str) -> FakeScutilDir {
+    let dir = std::env::temp_dir().join(format!("scutil-parser-{}-{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).expect("Failed to create temp dir");
+
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let script = dir.join("scutil");
+    std::fs::write(
+        &script,
+        format!(
+            "#!/bin/sh\ncase \"$1\" in\n  --proxy) cat '{}' ;;\n  --nwi) cat '{}' ;;\n  --nc) if [ \"$2\" = status ]; then cat '{}'; else cat '{}'; fi ;;\n  -r) cat '{}' ;;\n  *) cat '{}' ;;\nesac\n",
+            manifest_dir.join("testdata/proxy.txt").display(),
+            manifest_dir.join("testdata/nwi_vpn.txt").display(),
+            manifest_dir.join("testdata/nc_status_ikev2.txt").display(),
+            manifest_dir.join("testdata/nc_list.txt").display(),
+            manifest_dir.join("testdata/reachability.txt").display(),
+            manifest_dir.join("testdata.txt").display()
+        ),
+    )
+    .expect("Failed to write fake scutil");
+    std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755))
+        .expect("Failed to make fake scutil executable");
+    FakeScutilDir { path: dir }
+}
+
+fn run_cli(name: &str, args: &[&str]) -> Output {
+    let dir = fake_scutil_dir(name);
+    let path = format!(
+        "{}:{}",
+        dir.path.display(),
+        std::env::var("PATH").unwrap_or_default()
+    );
+    Command::new(env!("CARGO_BIN_EXE_scutil-parser"))
+        .args(args)
+        .env("PATH", path)
+        .output()
+        .expect("Failed to run scutil-parser")
+}
+
+#[test]
+fn test_dns_stdout_is_only_json() {
+    let output = run_cli("stdout-json", &["dns"]);
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).expect("stdout wasn't UTF-8");
+    let res: serde_json::Value = serde_json::from_str(&stdout).expect("stdout wasn't valid JSON");
+    assert!(res.get("dns_config").is_some());
+
+    let stderr = String::from_utf8(output.stderr).expect("stderr wasn't UTF-8");
+    assert!(stderr.contains("Running DNS"));
+}
+
+#[test]
+fn test_dns_quiet() {
+    // the flag is global, so it works either side of the subcommand
+    for args in [
+        ["dns", "--quiet"],
+        ["-q", "dns"],
+        ["proxy", "-q"],
+        ["--quiet", "proxy"],
+    ] {
+        let output = run_cli("quiet", &args);
+        assert!(output.status.success(), "{:?}", args);
+        assert!(output.stderr.is_empty(), "{:?}", args);
+        serde_json::from_slice::<serde_json::Value>(&output.stdout)
+            .expect("stdout wasn't valid JSON");
+    }
+}
+
+#[test]
+fn test_dns_stdin() {
+    let fixture = std::fs::read(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("testdata.txt"))
+        .expect("Failed to read fixture");
+
+    // an empty PATH makes sure we're not running scutil
+    let mut child = Command::new(env!("CARGO_BIN_EXE_scutil-parser"))
+        .args(["dns", "--stdin"])
+        .env("PATH", "")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("Failed to run scutil-parser");
+    child
+        .stdin
+        .take()
+        .expect("Failed to get stdin")
+        .write_all(&fixture)
+        .expect("Failed to write stdin");
+    let output = child
+        .wait_with_output()
+        .expect("Failed to wait on scutil-parser");
+    assert!(output.status.success());
+
+    let res: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("stdout wasn't valid JSON");
+    assert_eq!(res["dns_config"].as_array().map(|a| a.len()), Some(7));
+}
+
+#[test]
+fn test_dns_file() {
+    let fixture = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("testdata.txt");
+    let output = Command::new(env!("CARGO_BIN_EXE_scutil-parser"))
+        .args(["dns", "--file", &fixture.display().to_string()])
+        .env("PATH", "")
+        .output()
+        .expect("Failed to run scutil-parser");
+    assert!(output.status.success());
+    let res: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("stdout wasn't valid JSON");
+    assert_eq!(
+        res["scoped_dns_config"].as_array().map(|a| a.len()),
+        Some(1)
+    );
+}
+
+#[test]
+fn test_dns_missing_file() {
+    let output = Command::new(env!("CARGO_BIN_EXE_scutil-parser"))
+        .args(["dns", "--file", "/this/file/does/not/exist"])
+        .output()
+        .expect("Failed to run scutil-parser");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8(output.stderr).expect("stderr wasn't UTF-8");
+    assert!(stderr.starts_with("Failed to read /this/file/does/not/exist"));
+}
+
+#[test]
+fn test_proxy() {
+    let output = run_cli("proxy", &["proxy"]);
+    assert!(output.status.success());
+
+    let res: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("stdout wasn't valid JSON");
+    assert_eq!(res["http"]["port"], 3128);
+    let stderr = String::from_utf8(output.stderr).expect("stderr wasn't UTF-8");
+    assert!(stderr.contains("Running proxy"));
+}
+
+#[test]
+fn test_nwi() {
+    let output = run_cli("nwi", &["nwi", "--quiet"]);
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty());
+
+    let res: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("stdout wasn't valid JSON");
+    assert_eq!(res["ipv4"][0]["name"], "utun3");
+    assert_eq!(res["ipv6"][0]["addresses"][0], "2001:db8:1::50");
+
+    let output = run_cli("nwi-text", &["nwi", "--quiet", "--format", "text"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).expect("stdout wasn't UTF-8");
+    assert!(stdout.contains("  utun3  10.8.0.2 (IPv4,DNS) via 203.0.113.10\n"));
+}
+
+#[test]
+fn test_nc_list() {
+    let output = run_cli("nc-list", &["nc", "list", "--quiet"]);
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty());
+
+    let res: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("stdout wasn't valid JSON");
+    assert_eq!(res[1]["name"], "Home [WireGuard]");
+    assert_eq!(res[1]["state"], "Connected");
+
+    let output = run_cli(
+        "nc-list-text",
+        &["nc", "list", "--quiet", "--format", "text"],
+    );
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).expect("stdout wasn't UTF-8");
+    assert!(stdout.contains("* Connected     VPN    Home [WireGuard]\n"));
+}
+
+#[test]
+fn test_nc_status() {
+    let output = run_cli("nc-status", &["nc", "status", "Corp IKEv2", "--quiet"]);
+    assert!(output.status.success());
+
+    let res: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("stdout wasn't valid JSON");
+    assert_eq!(res["state"], "Connected");
+    assert_eq!(res["extended"]["IPSec"]["RemoteAddress"], "vpn.example.com");
+
+    let output = run_cli(
+        "nc-status-text",
+        &["nc", "status", "Corp IKEv2", "--quiet", "--format", "text"],
+    );
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).expect("stdout wasn't UTF-8");
+    assert!(stdout.starts_with("Connected\n"));
+    assert!(stdout.contains("  IPv4.Addresses.0 : 10.8.0.23\n"));
+}
+
+#[test]
+fn test_reach() {
+    let output = run_cli("reach", &["reach", "8.8.8.8", "--quiet"]);
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty());
+
+    let res: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("stdout wasn't valid JSON");
+    assert_eq!(res["host"], "8.8.8.8");
+    assert_eq!(res["reach"]["reachable"], true);
+}
+
+#[test]
+fn test_diff() {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let output = Command::new(env!("CARGO_BIN_EXE_scutil-parser"))
+        .arg("diff")
+        .arg(manifest_dir.join("testdata.txt"))
+        .arg(manifest_dir.join("testdata/vpn_supplemental.txt"))
+        .env("PATH", "")
+        .output()
+        .expect("Failed to run scutil-parser");
+    assert!(output.status.success());
+
+    let res: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("stdout wasn't valid JSON");
+    assert!(!res["dns_config"]["added"]
+        .as_array()
+        .expect("added should be an array")
+        .is_empty());
+}
+
+#[test]
+fn test_dns_warnings() {
+    let dir = fake_scutil_dir("warnings");
+    let input = dir.join("no_nameservers.txt");
+    std::fs::write(
+        &input,
+        "DNS configuration\n\nresolver #1\n  domain   : example.net\n  flags    : Request A records\n\n",
+    )
+    .expect("Failed to write input");
+    let output = Command::new(env!("CARGO_BIN_EXE_scutil-parser"))
+        .args(["dns", "--file", &input.display().to_string()])
+        .env("PATH", "")
+        .output()
+        .expect("Failed to run scutil-parser");
+    assert!(output.status.success());
+    serde_json::from_slice::<serde_json::Value>(&output.stdout).expect("stdout wasn't valid JSON");
+    let stderr = String::from_utf8(output.stderr).expect("stderr wasn't UTF-8");
+    assert!(stderr.contains("Warning: line 3: resolver #1 has no nameservers and isn't mdns"));
+}
+
+#[test]
+fn test_dns_duplicate_resolver() {
+    let fixture = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("testdata/duplicate_resolver.txt");
+    let output = Command::new(env!("CARGO_BIN_EXE_scutil-parser"))
+        .args(["dns", "--file", &fixture.display().to_string()])
+        .env("PATH", "")
+        .output()
+        .expect("Failed to run scutil-parser");
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).expect("stderr wasn't UTF-8");
+    assert!(stderr.contains("line 15: resolver #2 appears more than once, first on line 9"));
+}
+
+#[test]
+fn test_dns_lenient() {
+    let fixture = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("testdata/unknown_field.txt");
+    let fixture = fixture.display().to_string();
+
+    let output = run_cli("strict", &["dns", "--quiet", "--file", &fixture]);
+    assert_eq!(output.status.code(), Some(1));
+
+    let output = run_cli("lenient", &["dns", "--file", &fixture, "--lenient"]);
+    assert!(output.status.success());
+    let res: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("stdout wasn't valid JSON");
+    assert_eq!(res["dns_config"][0]["extra"]["shiny new field"], "42");
+    let stderr = String::from_utf8(output.stderr).expect("stderr wasn't UTF-8");
+    assert!(stderr.contains("Warning: line 10: unexpected line, ignoring it"));
+}
+
+#[test]
+fn test_dns_normalize() {
+    let output = run_cli(
+        "normalize",
+        &["dns", "--quiet", "--normalize", "--strip-reach"],
+    );
+    assert!(output.status.success());
+    let res: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("stdout wasn't valid JSON");
+    assert_eq!(res["dns_config"][0]["id"], 1);
+    assert!(res["dns_config"][0]["reach"].is_null());
+
+    let output = run_cli("normalize-reach", &["dns", "--quiet", "--normalize"]);
+    let res: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("stdout wasn't valid JSON");
+    assert_eq!(res["dns_config"][0]["reach"]["value"], 0x00020002);
+}
+
+#[test]
+fn test_dns_formats() {
+    let output = run_cli("format-yaml", &["dns", "--format", "yaml", "--quiet"]);
+    assert!(output.status.success());
+    let res: serde_json::Value =
+        serde_yaml::from_slice(&output.stdout).expect("stdout wasn't valid YAML");
+    assert_eq!(res["dns_config"].as_array().map(|a| a.len()), Some(7));
+
+    let output = run_cli("format-text", &["--format", "text", "dns", "--quiet"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).expect("stdout wasn't UTF-8");
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines[0], "DNS configuration");
+    assert_eq!(lines[1], "  resolver #1");
+    assert!(lines[2].starts_with("    nameservers    1234:1234:fd:0:ffe:66ff:2345:b33f  10.0.0.12"));
+    assert_eq!(lines[3], "    search domains subdomain.example.com");
+    assert_eq!(lines[5], "    order          -");
+    assert_eq!(lines[6], "    interface      15 (en0)");
+    assert!(lines.contains(&"Scoped"));
+
+    let output = run_cli("format-bad", &["dns", "--format", "xml"]);
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn test_dns_compact() {
+    let output = run_cli("compact", &["dns", "--compact", "--quiet"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).expect("stdout wasn't UTF-8");
+    assert_eq!(stdout.lines().count(), 1);
+    let res: serde_json::Value = serde_json::from_str(&stdout).expect("stdout wasn't valid JSON");
+    assert_eq!(res["dns_config"].as_array().map(|a| a.len()), Some(7));
+}
+
+#[test]
+fn test_scutil_path() {
+    let dir = fake_scutil_dir("scutil-path");
+    // the fake isn't on the PATH, so it's only found through SCUTIL_PATH
+    let output = Command::new(env!("CARGO_BIN_EXE_scutil-parser"))
+        .args(["dns", "--quiet"])
+        .env("SCUTIL_PATH", dir.join("scutil"))
+        .output()
+        .expect("Failed to run scutil-parser");
+    assert!(output.status.success());
+    let res: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("stdout wasn't valid JSON");
+    assert_eq!(res["dns_config"].as_array().map(|a| a.len()), Some(7));
+}
+
+#[test]
+fn test_dns_parse_error() {
+    let fixture = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("testdata/truncated_resolver.txt");
+    let output = run_cli(
+        "parse-error",
+        &["dns", "--quiet", "--file", fixture.to_str().unwrap()],
+    );
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8(output.stderr).expect("stderr wasn't UTF-8");
+    assert!(stderr.starts_with("Failed to parse DNS configuration: line 19:"));
+    assert!(!stderr.contains("panicked"));
+}
+
+#[test]
+fn test_scutil_failure() {
+    let output = Command::new(env!("CARGO_BIN_EXE_scutil-parser"))
+        .args(["dns", "--quiet"])
+        .env("SCUTIL_PATH", "false")
+        .output()
+        .expect("Failed to run scutil-parser");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8(output.stderr).expect("stderr wasn't UTF-8");
+    assert!(stderr.starts_with("Failed to run scutil: false --dns failed"));
+}